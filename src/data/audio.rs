@@ -0,0 +1,114 @@
+// 音频文件检查（sounds.txt 与 music.txt 引用的采样）
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use super::models::GameData;
+
+// 缺失的音频文件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingAudio {
+    pub owner: String, // 声音 id，或音乐序号 "track #n"
+    pub file: String,
+}
+
+impl fmt::Display for MissingAudio {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: 找不到音频文件 {}", self.owner, self.file)
+    }
+}
+
+// 依次在模块目录和游戏根目录下的子目录中查找文件
+// 游戏在 Windows 上不区分大小写，目录和文件名按不区分大小写匹配
+pub fn find_audio_file(module_dir: &Path, game_dir: &Path, folder: &str, file: &str) -> Option<PathBuf> {
+    let relative = Path::new(folder).join(file);
+    [module_dir, game_dir]
+        .iter()
+        .find_map(|dir| find_path_ignore_case(dir, &relative))
+}
+
+// 逐级查找路径，先按原样匹配，找不到时在目录中查找只有大小写不同的项
+fn find_path_ignore_case(dir: &Path, relative: &Path) -> Option<PathBuf> {
+    let exact = dir.join(relative);
+    if exact.is_file() {
+        return Some(exact);
+    }
+
+    let mut path = dir.to_path_buf();
+    for component in relative.components() {
+        let name = component.as_os_str().to_str()?;
+        let candidate = path.join(name);
+        path = if candidate.exists() {
+            candidate
+        } else {
+            std::fs::read_dir(&path).ok()?
+                .filter_map(|entry| entry.ok())
+                .find(|entry| entry.file_name().to_str().is_some_and(|entry_name| entry_name.eq_ignore_ascii_case(name)))?
+                .path()
+        };
+    }
+    path.is_file().then_some(path)
+}
+
+// 检查所有声音和音乐引用的文件是否存在
+pub fn find_missing_audio(data: &GameData, module_dir: &Path, game_dir: &Path) -> Vec<MissingAudio> {
+    let mut missing = Vec::new();
+
+    for sound in &data.sounds {
+        for sample in &sound.samples {
+            if find_audio_file(module_dir, game_dir, "Sounds", &sample.file).is_none() {
+                missing.push(MissingAudio { owner: sound.id.clone(), file: sample.file.clone() });
+            }
+        }
+    }
+
+    for (index, track) in data.music.iter().enumerate() {
+        if find_audio_file(module_dir, game_dir, "Music", &track.file).is_none() {
+            missing.push(MissingAudio { owner: format!("track #{}", index), file: track.file.clone() });
+        }
+    }
+
+    missing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{MusicTrack, Sound, SoundSample};
+    use crate::data::test_util::TempDir;
+
+    #[test]
+    fn test_module_then_game_fallback() {
+        let root = TempDir::new("audio_check");
+        let module_dir = root.join("Modules/Native");
+        root.write("Modules/Native/Sounds/click.wav", b"");
+        root.write("Music/calm.ogg", b"");
+
+        let sample = |file: &str| SoundSample { file: file.to_string(), variation: 0 };
+        let data = GameData {
+            sounds: vec![Sound { id: "snd_click".into(), flags: 0, samples: vec![sample("click.wav"), sample("lost.wav")] }],
+            music: vec![
+                MusicTrack { file: "calm.ogg".into(), ..Default::default() },
+                MusicTrack { file: "gone.ogg".into(), ..Default::default() },
+            ],
+            ..Default::default()
+        };
+
+        let missing = find_missing_audio(&data, &module_dir, root.path());
+        assert_eq!(missing, vec![
+            MissingAudio { owner: "snd_click".into(), file: "lost.wav".into() },
+            MissingAudio { owner: "track #1".into(), file: "gone.ogg".into() },
+        ]);
+    }
+
+    #[test]
+    fn test_find_audio_file_ignores_case() {
+        let root = TempDir::new("audio_case");
+        let module_dir = root.join("Modules/Native");
+        let click = root.write("Modules/Native/sounds/Click.WAV", b"");
+        let calm = root.write("music/Calm.ogg", b"");
+
+        assert_eq!(find_audio_file(&module_dir, root.path(), "Sounds", "click.wav"), Some(click));
+        assert_eq!(find_audio_file(&module_dir, root.path(), "Music", "calm.OGG"), Some(calm));
+        assert_eq!(find_audio_file(&module_dir, root.path(), "Sounds", "clack.wav"), None);
+    }
+}
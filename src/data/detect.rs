@@ -0,0 +1,291 @@
+// 游戏安装检测（Steam 库、Flatpak、Proton/Wine 前缀和用户配置的路径）
+
+use std::path::{Path, PathBuf};
+
+// Steam 中 Warband 的安装目录名
+const STEAM_GAME_DIR: &str = "MountBlade Warband";
+
+// 相对 Steam 根目录的位置（Linux 原生、macOS）
+const STEAM_ROOTS: [&str; 4] = [
+    ".local/share/Steam",
+    ".steam/steam",
+    ".steam/root",
+    "Library/Application Support/Steam",
+];
+
+// Flatpak 版 Steam 的根目录
+const FLATPAK_STEAM_ROOTS: [&str; 2] = [
+    ".var/app/com.valvesoftware.Steam/.local/share/Steam",
+    ".var/app/com.valvesoftware.Steam/data/Steam",
+];
+
+// Wine 前缀中常见的安装位置（相对 drive_c）
+const WINE_GAME_DIRS: [&str; 4] = [
+    "Program Files (x86)/Mount&Blade Warband",
+    "Program Files/Mount&Blade Warband",
+    "Program Files (x86)/Steam/steamapps/common/MountBlade Warband",
+    "Program Files/Steam/steamapps/common/MountBlade Warband",
+];
+
+// 用户配置的搜索路径环境变量（按系统路径分隔符分隔）
+pub const GAME_PATHS_ENV: &str = "MB_GAME_PATHS";
+
+// 候选来源，按优先级排列
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CandidateSource {
+    UserConfigured,
+    Steam,
+    FlatpakSteam,
+    Proton,
+    Wine,
+}
+
+// 检测到的游戏目录
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameCandidate {
+    pub path: PathBuf,
+    pub source: CandidateSource,
+    pub complete: bool, // 是否包含 Modules/Native
+}
+
+// 游戏检测器，主目录可替换以便在测试目录树上运行
+#[derive(Debug, Clone, Default)]
+pub struct GameDetector {
+    pub home: Option<PathBuf>,
+    pub search_paths: Vec<PathBuf>,
+    pub wine_prefixes: Vec<PathBuf>,
+}
+
+impl GameDetector {
+    // 使用当前用户的主目录、MB_GAME_PATHS 和 WINEPREFIX
+    pub fn new() -> Self {
+        let env_paths = |name: &str| -> Vec<PathBuf> {
+            std::env::var_os(name)
+                .map(|value| std::env::split_paths(&value).collect())
+                .unwrap_or_default()
+        };
+        Self {
+            home: dirs::home_dir(),
+            search_paths: env_paths(GAME_PATHS_ENV),
+            wine_prefixes: env_paths("WINEPREFIX"),
+        }
+    }
+
+    pub fn with_home<P: Into<PathBuf>>(home: P) -> Self {
+        Self { home: Some(home.into()), ..Default::default() }
+    }
+
+    pub fn with_search_paths(mut self, paths: Vec<PathBuf>) -> Self {
+        self.search_paths.extend(paths);
+        self
+    }
+
+    // 所有候选目录，完整安装在前，再按来源优先级排序，重复的目录只保留第一次
+    pub fn detect(&self) -> Vec<GameCandidate> {
+        let mut found: Vec<(PathBuf, CandidateSource)> = Vec::new();
+
+        for path in &self.search_paths {
+            found.push((path.clone(), CandidateSource::UserConfigured));
+        }
+
+        let home_paths = |roots: &[&str]| -> Vec<PathBuf> {
+            self.home.iter().flat_map(|home| roots.iter().map(move |root| home.join(root))).collect()
+        };
+        let steam_libraries = Self::steam_libraries(&home_paths(&STEAM_ROOTS));
+        let flatpak_libraries = Self::steam_libraries(&home_paths(&FLATPAK_STEAM_ROOTS));
+
+        for library in &steam_libraries {
+            found.push((library.join("steamapps/common").join(STEAM_GAME_DIR), CandidateSource::Steam));
+        }
+        for library in &flatpak_libraries {
+            found.push((library.join("steamapps/common").join(STEAM_GAME_DIR), CandidateSource::FlatpakSteam));
+        }
+
+        // Proton 为每个应用在 compatdata/<appid>/pfx 下建立前缀
+        for library in steam_libraries.iter().chain(&flatpak_libraries) {
+            for prefix in Self::subdirs(&library.join("steamapps/compatdata")) {
+                Self::push_wine_dirs(&mut found, &prefix.join("pfx"), CandidateSource::Proton);
+            }
+        }
+
+        let mut prefixes = self.wine_prefixes.clone();
+        prefixes.extend(home_paths(&[".wine"]));
+        for prefix in &prefixes {
+            Self::push_wine_dirs(&mut found, prefix, CandidateSource::Wine);
+        }
+
+        let mut candidates: Vec<GameCandidate> = Vec::new();
+        let mut seen: Vec<PathBuf> = Vec::new();
+        for (path, source) in found {
+            if !path.join("Modules").is_dir() {
+                continue;
+            }
+            let canonical = path.canonicalize().unwrap_or_else(|_| path.clone());
+            if seen.contains(&canonical) {
+                continue;
+            }
+            seen.push(canonical);
+            let complete = path.join("Modules").join(super::game::NATIVE_MODULE).is_dir();
+            candidates.push(GameCandidate { path, source, complete });
+        }
+
+        // sort_by_key 是稳定排序，同级候选保持发现顺序
+        candidates.sort_by_key(|candidate| (!candidate.complete, candidate.source));
+        candidates
+    }
+
+    // 最佳候选
+    pub fn detect_best(&self) -> Option<GameCandidate> {
+        self.detect().into_iter().next()
+    }
+
+    fn push_wine_dirs(found: &mut Vec<(PathBuf, CandidateSource)>, prefix: &Path, source: CandidateSource) {
+        let drive_c = prefix.join("drive_c");
+        for dir in WINE_GAME_DIRS {
+            found.push((drive_c.join(dir), source));
+        }
+    }
+
+    // Steam 根目录本身及 libraryfolders.vdf 中登记的库目录
+    fn steam_libraries(roots: &[PathBuf]) -> Vec<PathBuf> {
+        let mut libraries = Vec::new();
+        for root in roots.iter().filter(|root| root.is_dir()) {
+            libraries.push(root.clone());
+            for vdf in ["steamapps/libraryfolders.vdf", "config/libraryfolders.vdf"] {
+                if let Ok(content) = std::fs::read_to_string(root.join(vdf)) {
+                    libraries.extend(parse_library_folders(&content));
+                }
+            }
+        }
+        libraries
+    }
+
+    fn subdirs(dir: &Path) -> Vec<PathBuf> {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        let mut dirs: Vec<PathBuf> = entries.flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir())
+            .collect();
+        dirs.sort();
+        dirs
+    }
+}
+
+// 读取 libraryfolders.vdf 中的库路径
+// 新格式为 "0" { "path" "..." }，旧格式为顶层的 "1" "..."
+pub fn parse_library_folders(content: &str) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    let mut depth = 0;
+    let mut last_key: Option<String> = None;
+    let mut chars = content.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => {
+                depth += 1;
+                last_key = None;
+            }
+            '}' => {
+                depth -= 1;
+                last_key = None;
+            }
+            '"' => {
+                let mut token = String::new();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => token.extend(chars.next()),
+                        _ => token.push(c),
+                    }
+                }
+                match last_key.take() {
+                    Some(key) => {
+                        let old_format = depth == 1 && key.parse::<u32>().is_ok();
+                        if key == "path" || old_format {
+                            paths.push(PathBuf::from(token));
+                        }
+                    }
+                    None => last_key = Some(token),
+                }
+            }
+            _ => {}
+        }
+    }
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::test_util::TempDir;
+
+    #[test]
+    fn test_parse_library_folders() {
+        let content = r#"
+"libraryfolders"
+{
+	"contentstatsid"		"123"
+	"0"
+	{
+		"path"		"/home/user/.local/share/Steam"
+		"apps"
+		{
+			"48700"		"1066404024"
+		}
+	}
+	"1"
+	{
+		"path"		"D:\\SteamLibrary"
+	}
+}
+"#;
+        assert_eq!(parse_library_folders(content), vec![
+            PathBuf::from("/home/user/.local/share/Steam"),
+            PathBuf::from("D:\\SteamLibrary"),
+        ]);
+
+        let old = "\"LibraryFolders\"\n{\n\t\"TimeNextStatsReport\"\t\"1\"\n\t\"1\"\t\"/mnt/games\"\n}\n";
+        assert_eq!(parse_library_folders(old), vec![PathBuf::from("/mnt/games")]);
+    }
+
+    #[test]
+    fn test_detect_ranked_candidates() {
+        let root = TempDir::new("detect_game");
+        let home = root.join("home");
+        let game = |dir: PathBuf, native: bool| {
+            let modules = dir.join("Modules");
+            std::fs::create_dir_all(if native { modules.join("Native") } else { modules }).unwrap();
+            dir
+        };
+
+        let steam = home.join(".local/share/Steam");
+        let library = root.join("library");
+        std::fs::create_dir_all(steam.join("steamapps")).unwrap();
+        std::fs::write(
+            steam.join("steamapps/libraryfolders.vdf"),
+            format!("\"libraryfolders\" {{ \"1\" {{ \"path\" \"{}\" }} }}", library.display()),
+        ).unwrap();
+        let steam_game = game(library.join("steamapps/common/MountBlade Warband"), true);
+        let flatpak_game = game(home.join(".var/app/com.valvesoftware.Steam/.local/share/Steam/steamapps/common/MountBlade Warband"), false);
+        let proton_game = game(steam.join("steamapps/compatdata/48700/pfx/drive_c/Program Files/Mount&Blade Warband"), true);
+        let wine_game = game(home.join(".wine/drive_c/Program Files (x86)/Mount&Blade Warband"), true);
+        let user_game = game(root.join("custom"), true);
+
+        let candidates = GameDetector::with_home(&home)
+            .with_search_paths(vec![user_game.clone(), root.join("missing")])
+            .detect();
+
+        let ranked: Vec<(PathBuf, CandidateSource, bool)> = candidates.into_iter()
+            .map(|candidate| (candidate.path, candidate.source, candidate.complete))
+            .collect();
+        assert_eq!(ranked, vec![
+            (user_game, CandidateSource::UserConfigured, true),
+            (steam_game, CandidateSource::Steam, true),
+            (proton_game, CandidateSource::Proton, true),
+            (wine_game, CandidateSource::Wine, true),
+            (flatpak_game, CandidateSource::FlatpakSteam, false),
+        ]);
+    }
+}
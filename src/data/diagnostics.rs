@@ -0,0 +1,105 @@
+// 解析诊断信息
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// 解析模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ParseMode {
+    #[default]
+    Lenient, // 宽松：记录问题并继续解析
+    Strict,  // 严格：遇到第一个问题即失败
+}
+
+// 解析错误（文件、行列、记录 id、期望的字段）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParseError {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    pub record: Option<String>,
+    pub field: String,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}:{}: ", self.file, self.line, self.column)?;
+        if let Some(record) = &self.record {
+            write!(f, "[{}] ", record)?;
+        }
+        write!(f, "字段 {}: {}", self.field, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// 宽松模式下收集的问题，与严格模式下的 ParseError 内容相同
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ParseWarning(pub ParseError);
+
+impl From<ParseError> for ParseWarning {
+    fn from(error: ParseError) -> Self {
+        Self(error)
+    }
+}
+
+impl std::ops::Deref for ParseWarning {
+    type Target = ParseError;
+
+    fn deref(&self) -> &ParseError {
+        &self.0
+    }
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+// 汇总诊断信息用于界面显示，最多列出 limit 条
+pub fn summarize_warnings(warnings: &[ParseWarning], limit: usize) -> Option<String> {
+    if warnings.is_empty() {
+        return None;
+    }
+
+    let mut summary = format!("解析时发现 {} 个问题:", warnings.len());
+    for warning in warnings.iter().take(limit) {
+        summary.push('\n');
+        summary.push_str(&warning.to_string());
+    }
+    if warnings.len() > limit {
+        summary.push_str(&format!("\n……另有 {} 个问题", warnings.len() - limit));
+    }
+    Some(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn warning(line: usize, field: &str) -> ParseWarning {
+        ParseWarning(ParseError {
+            file: "troops.txt".to_string(),
+            line,
+            column: 3,
+            record: Some("trp_player".to_string()),
+            field: field.to_string(),
+            message: "值无效: x".to_string(),
+        })
+    }
+
+    #[test]
+    fn test_summarize_warnings() {
+        assert_eq!(summarize_warnings(&[], 10), None);
+
+        let warnings = vec![warning(3, "flags"), warning(4, "level"), warning(5, "faction")];
+        assert_eq!(
+            summarize_warnings(&warnings, 2).unwrap(),
+            "解析时发现 3 个问题:\ntroops.txt:3:3: [trp_player] 字段 flags: 值无效: x\ntroops.txt:4:3: [trp_player] 字段 level: 值无效: x\n……另有 1 个问题"
+        );
+        assert_eq!(summarize_warnings(&warnings, 3).unwrap().lines().count(), 4);
+    }
+}
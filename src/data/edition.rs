@@ -0,0 +1,376 @@
+// 游戏版本检测（安装目录中的文件、可执行文件版本信息和数据文件头）
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use super::models::ITEM_FACTIONS_VERSION;
+use super::module_ini::ModuleIni;
+
+// 游戏版本
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum GameEdition {
+    MountAndBlade, // 初代骑马与砍杀（1.011），物品没有派系列表
+    #[default]
+    Warband,
+    WithFireAndSword,
+    NapoleonicWars,
+}
+
+impl GameEdition {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Self::MountAndBlade => "骑马与砍杀",
+            Self::Warband => "骑马与砍杀：战团",
+            Self::WithFireAndSword => "火与剑",
+            Self::NapoleonicWars => "拿破仑战争",
+        }
+    }
+    
+    // item_kinds1.txt 的文件头版本
+    pub fn items_version(&self) -> u32 {
+        match self {
+            Self::MountAndBlade => 2,
+            Self::Warband | Self::WithFireAndSword | Self::NapoleonicWars => ITEM_FACTIONS_VERSION,
+        }
+    }
+    
+    // 物品记录是否带派系列表（火与剑和拿破仑战争沿用战团的物品格式）
+    pub fn has_item_factions(&self) -> bool {
+        match self {
+            Self::MountAndBlade => false,
+            Self::Warband | Self::WithFireAndSword | Self::NapoleonicWars => true,
+        }
+    }
+}
+
+// 脚本扩展
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScriptExtender {
+    Wse,
+    Wse2,
+}
+
+impl ScriptExtender {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Self::Wse => "WSE",
+            Self::Wse2 => "WSE2",
+        }
+    }
+}
+
+// 拿破仑战争 DLC 的模块目录
+pub const NAPOLEONIC_WARS_MODULE: &str = "Napoleonic Wars";
+// 火与剑的原版模块目录
+pub const WFAS_NATIVE_MODULE: &str = "Ogniem i Mieczem";
+
+// 用于读取版本信息的可执行文件，按优先级排列
+const GAME_EXECUTABLES: [&str; 3] = ["mb_warband_wse2.exe", "mb_warband.exe", "mount&blade.exe"];
+
+// 资源节过大时不再读取（正常的游戏可执行文件只有几百 KB）
+const MAX_RESOURCE_SECTION: u32 = 16 * 1024 * 1024;
+
+// 检测结果
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct EditionInfo {
+    pub edition: GameEdition,
+    pub extender: Option<ScriptExtender>,
+    pub version: Option<String>, // 可执行文件中的 FileVersion
+}
+
+impl EditionInfo {
+    // 按安装目录和要打开的模块检测
+    pub fn detect(game_dir: &Path, module: &str) -> Self {
+        let exe_info = GAME_EXECUTABLES.iter()
+            .map(|exe| game_dir.join(exe))
+            .find(|path| path.is_file())
+            .and_then(|path| ExeVersionInfo::read(&path))
+            .unwrap_or_default();
+        Self {
+            edition: detect_edition(game_dir, module, &exe_info),
+            extender: detect_extender(game_dir),
+            version: exe_info.file_version,
+        }
+    }
+
+    // 界面显示，例如 "骑马与砍杀：战团 1.174 (WSE2)"
+    pub fn label(&self) -> String {
+        let mut label = self.edition.display_name().to_string();
+        if let Some(version) = &self.version {
+            label.push(' ');
+            label.push_str(version);
+        }
+        if let Some(extender) = self.extender {
+            label.push_str(&format!(" ({})", extender.display_name()));
+        }
+        label
+    }
+}
+
+impl fmt::Display for EditionInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.label())
+    }
+}
+
+// 可执行文件版本资源中的字符串
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ExeVersionInfo {
+    pub product_name: Option<String>,
+    pub file_description: Option<String>,
+    pub file_version: Option<String>,
+}
+
+impl ExeVersionInfo {
+    // 只读取 PE 文件的资源节（.rsrc），不读取整个可执行文件
+    pub fn read(path: &Path) -> Option<Self> {
+        let mut file = File::open(path).ok()?;
+        let resources = read_resource_section(&mut file)?;
+        Some(Self::parse(&resources))
+    }
+
+    // 从资源数据中提取版本字符串
+    pub fn parse(resources: &[u8]) -> Self {
+        Self {
+            product_name: read_version_string(resources, "ProductName"),
+            file_description: read_version_string(resources, "FileDescription"),
+            file_version: read_version_string(resources, "FileVersion"),
+        }
+    }
+
+    // 火与剑的产品名和文件说明为 "Mount&Blade With Fire & Sword"，波兰语版本为 "Ogniem i Mieczem"
+    pub fn is_fire_and_sword(&self) -> bool {
+        [&self.product_name, &self.file_description].into_iter().flatten().any(|text| {
+            let text = text.to_lowercase();
+            (text.contains("fire") && text.contains("sword")) || text.contains("ogniem")
+        })
+    }
+    
+    // 拿破仑战争的产品名和文件说明中带有 "Napoleonic Wars"
+    pub fn is_napoleonic_wars(&self) -> bool {
+        [&self.product_name, &self.file_description].into_iter().flatten()
+            .any(|text| text.to_lowercase().contains("napoleonic"))
+    }
+}
+
+fn detect_edition(game_dir: &Path, module: &str, exe_info: &ExeVersionInfo) -> GameEdition {
+    if exe_info.is_napoleonic_wars() || is_napoleonic_wars_module(&game_dir.join("Modules").join(module), module) {
+        return GameEdition::NapoleonicWars;
+    }
+    if exe_info.is_fire_and_sword() {
+        return GameEdition::WithFireAndSword;
+    }
+    // 可执行文件没有版本资源时按原版模块目录判断
+    if *exe_info == ExeVersionInfo::default() && game_dir.join("Modules").join(WFAS_NATIVE_MODULE).is_dir() {
+        return GameEdition::WithFireAndSword;
+    }
+    // 初代的物品文件为 itemsfile version 2
+    let items = game_dir.join("Modules").join(module).join("item_kinds1.txt");
+    if read_header_version(&items, "itemsfile").is_some_and(|version| version < 3) {
+        return GameEdition::MountAndBlade;
+    }
+    if game_dir.join("mount&blade.exe").is_file() && !game_dir.join("mb_warband.exe").is_file() {
+        return GameEdition::MountAndBlade;
+    }
+    GameEdition::Warband
+}
+
+// 拿破仑战争模块：DLC 自带的模块目录，或 module.ini 声明与其存档兼容的模块（改名的副本和基于它的模组）
+fn is_napoleonic_wars_module(module_dir: &Path, module: &str) -> bool {
+    module.eq_ignore_ascii_case(NAPOLEONIC_WARS_MODULE) ||
+        ModuleIni::load(module_dir.join("module.ini")).is_ok_and(|ini| {
+            ini.get("compatible_savegame_module")
+                .is_some_and(|name| name.trim().eq_ignore_ascii_case(NAPOLEONIC_WARS_MODULE))
+        })
+}
+
+fn detect_extender(game_dir: &Path) -> Option<ScriptExtender> {
+    if game_dir.join("mb_warband_wse2.exe").is_file() {
+        Some(ScriptExtender::Wse2)
+    } else if game_dir.join("WSELib.dll").is_file() {
+        Some(ScriptExtender::Wse)
+    } else {
+        None
+    }
+}
+
+// 读取数据文件头 "<名称> version <版本>" 中的版本号
+pub fn read_header_version(path: &Path, name: &str) -> Option<u32> {
+    let mut head = [0u8; 64];
+    let len = File::open(path).and_then(|mut file| file.read(&mut head)).ok()?;
+    let head = String::from_utf8_lossy(&head[..len]).into_owned();
+    let mut words = head.split_whitespace();
+    if words.next()? != name || words.next()? != "version" {
+        return None;
+    }
+    words.next()?.parse().ok()
+}
+
+fn read_at(file: &mut File, offset: u64, len: usize) -> Option<Vec<u8>> {
+    let mut buffer = vec![0u8; len];
+    file.seek(SeekFrom::Start(offset)).ok()?;
+    file.read_exact(&mut buffer).ok()?;
+    Some(buffer)
+}
+
+fn u16_at(bytes: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(offset..offset + 2)?.try_into().ok()?))
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+// 按 DOS 头、PE 头和节表定位并读取资源节
+fn read_resource_section(file: &mut File) -> Option<Vec<u8>> {
+    let dos_header = read_at(file, 0, 64)?;
+    if !dos_header.starts_with(b"MZ") {
+        return None;
+    }
+    let pe_offset = u32_at(&dos_header, 0x3c)? as u64;
+
+    // PE 签名 + COFF 文件头
+    let pe_header = read_at(file, pe_offset, 24)?;
+    if !pe_header.starts_with(b"PE\0\0") {
+        return None;
+    }
+    let section_count = u16_at(&pe_header, 6)? as usize;
+    let optional_header_size = u16_at(&pe_header, 20)? as u64;
+
+    // 每个节表项 40 字节：名称、虚拟大小、虚拟地址、文件中的大小、文件偏移……
+    let sections = read_at(file, pe_offset + 24 + optional_header_size, section_count * 40)?;
+    let section = sections.chunks_exact(40).find(|section| section.starts_with(b".rsrc"))?;
+    let size = u32_at(section, 16)?;
+    let offset = u32_at(section, 20)?;
+    if size > MAX_RESOURCE_SECTION {
+        return None;
+    }
+    read_at(file, offset as u64, size as usize)
+}
+
+// 在版本资源中查找字符串（UTF-16LE，键后补零对齐）
+pub fn read_version_string(bytes: &[u8], name: &str) -> Option<String> {
+    let key: Vec<u8> = format!("{}\0", name).encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect();
+    let start = bytes.windows(key.len()).position(|window| window == key.as_slice())? + key.len();
+
+    let mut units = bytes[start..]
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .skip_while(|&unit| unit == 0);
+    let mut value = Vec::new();
+    for unit in units.by_ref() {
+        if unit == 0 {
+            break;
+        }
+        value.push(unit);
+    }
+    let value = String::from_utf16(&value).ok()?.trim().to_string();
+    (!value.is_empty()).then_some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::test_util::TempDir;
+
+    fn utf16(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect()
+    }
+
+    // 版本资源中的字符串表：键、补零对齐、值
+    fn version_strings(strings: &[(&str, &str)]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for (key, value) in strings {
+            bytes.extend(utf16(&format!("{}\0", key)));
+            bytes.extend([0, 0]);
+            bytes.extend(utf16(&format!("{}\0", value)));
+        }
+        bytes
+    }
+
+    // 只有一个资源节的最小 PE 文件
+    fn pe_file(resources: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![0u8; 0x80];
+        bytes[..2].copy_from_slice(b"MZ");
+        bytes[0x3c..0x40].copy_from_slice(&0x80u32.to_le_bytes());
+        bytes.extend(b"PE\0\0");
+        let mut coff_header = [0u8; 20];
+        coff_header[2..4].copy_from_slice(&1u16.to_le_bytes());
+        bytes.extend(coff_header);
+        // 资源节前放一段会误匹配的内容，确认只读取资源节
+        let mut section = [0u8; 40];
+        section[..5].copy_from_slice(b".rsrc");
+        let filler = version_strings(&[("FileVersion", "0.0")]);
+        let offset = bytes.len() + section.len() + filler.len();
+        section[16..20].copy_from_slice(&(resources.len() as u32).to_le_bytes());
+        section[20..24].copy_from_slice(&(offset as u32).to_le_bytes());
+        bytes.extend(section);
+        bytes.extend(filler);
+        bytes.extend(resources);
+        bytes
+    }
+
+    #[test]
+    fn test_read_version_info() {
+        let dir = TempDir::new("exe_version");
+        let resources = version_strings(&[
+            ("CompanyName", "TaleWorlds"),
+            ("FileDescription", "Mount&Blade Warband"),
+            ("FileVersion", "1.174"),
+            ("ProductName", "Mount&Blade Warband"),
+        ]);
+        let exe = dir.write("mb_warband.exe", pe_file(&resources));
+        let not_pe = dir.write("readme.exe", b"no version here");
+
+        let info = ExeVersionInfo::read(&exe).unwrap();
+        assert_eq!(info.file_version.as_deref(), Some("1.174"));
+        assert_eq!(info.product_name.as_deref(), Some("Mount&Blade Warband"));
+        assert!(!info.is_fire_and_sword());
+        assert_eq!(ExeVersionInfo::read(&not_pe), None);
+        assert_eq!(read_version_string(b"no version here", "FileVersion"), None);
+    }
+
+    #[test]
+    fn test_detect_edition() {
+        let root = TempDir::new("detect_edition");
+        root.mkdir("warband/Modules/Napoleonic Wars");
+        root.write("warband/Modules/Native/item_kinds1.txt", "itemsfile version 3\n0\n");
+        root.write("warband/mb_warband_wse2.exe", b"");
+        root.write("mount_and_blade/Modules/Native/item_kinds1.txt", "itemsfile version 2\n0\n");
+        // 火与剑按可执行文件的产品名识别，模块目录名称不影响结果
+        root.mkdir("wfas/Modules/Native");
+        root.write("wfas/mb_warband.exe", pe_file(&version_strings(&[
+            ("FileDescription", "Mount&Blade With Fire & Sword"),
+            ("FileVersion", "1.143"),
+        ])));
+        root.mkdir("wfas_no_exe/Modules/Ogniem i Mieczem");
+        // 拿破仑战争按可执行文件或 module.ini 识别，不依赖模块目录名
+        root.write("warband/Modules/NW Mod/module.ini", "compatible_savegame_module = Napoleonic Wars\n");
+        root.write("nw_server/mb_warband.exe", pe_file(&version_strings(&[("ProductName", "Mount&Blade Warband Napoleonic Wars")])));
+        root.mkdir("nw_server/Modules/Renamed");
+        let game = root.join("warband");
+
+        let native = EditionInfo::detect(&game, "Native");
+        let napoleonic = EditionInfo::detect(&game, NAPOLEONIC_WARS_MODULE);
+        let napoleonic_mod = EditionInfo::detect(&game, "NW Mod");
+        let napoleonic_exe = EditionInfo::detect(&root.join("nw_server"), "Renamed");
+        let original = EditionInfo::detect(&root.join("mount_and_blade"), "Native");
+        let fire_and_sword = EditionInfo::detect(&root.join("wfas"), "Native");
+        let fire_and_sword_folder = EditionInfo::detect(&root.join("wfas_no_exe"), WFAS_NATIVE_MODULE);
+
+        assert_eq!(native.edition, GameEdition::Warband);
+        assert_eq!(native.extender, Some(ScriptExtender::Wse2));
+        assert_eq!(native.version, None);
+        assert_eq!(native.label(), "骑马与砍杀：战团 (WSE2)");
+        assert_eq!(napoleonic.edition, GameEdition::NapoleonicWars);
+        assert_eq!(napoleonic_mod.edition, GameEdition::NapoleonicWars);
+        assert_eq!(napoleonic_exe.edition, GameEdition::NapoleonicWars);
+        assert_eq!(original.edition, GameEdition::MountAndBlade);
+        assert_eq!(fire_and_sword.edition, GameEdition::WithFireAndSword);
+        assert_eq!(fire_and_sword.label(), "火与剑 1.143");
+        assert_eq!(fire_and_sword_folder.edition, GameEdition::WithFireAndSword);
+        assert_eq!(fire_and_sword_folder.extender, None);
+    }
+}
@@ -2,19 +2,76 @@
 
 use anyhow::Result;
 use std::path::{Path, PathBuf};
-use super::models::GameData;
+use std::time::SystemTime;
+use super::models::{GameData, Module, ModuleStats};
 use super::parser::Parser;
+use super::diagnostics::{ParseMode, ParseWarning};
+use super::writer::Writer;
+use super::audio::{find_missing_audio, MissingAudio};
+use super::module_ini::{ModuleIni, MODULE_NAME};
+use super::detect::{GameCandidate, GameDetector};
+use super::edition::{EditionInfo, GameEdition, ScriptExtender};
+
+// 原版模块目录名
+pub const NATIVE_MODULE: &str = "Native";
+
+// 模块目录中由编辑器解析的数据文件
+pub const MODULE_DATA_FILES: &[&str] = &[
+    "item_kinds1.txt", "troops.txt", "factions.txt", "parties.txt", "party_templates.txt",
+    "scenes.txt", "map_icons.txt", "scripts.txt", "simple_triggers.txt", "triggers.txt",
+    "mission_templates.txt", "conversation.txt", "dialog_states.txt", "strings.txt", "quests.txt",
+    "info_pages.txt", "skills.txt", "skins.txt", "sounds.txt", "music.txt",
+    "scene_props.txt", "particle_systems.txt", "menus.txt", "presentations.txt", "tableau_materials.txt",
+    "postfx.txt", "quick_strings.txt", "variables.txt", "actions.txt", "tag_uses.txt",
+];
 
 #[derive(Debug, Clone)]
 pub struct GameInstance {
     pub path: PathBuf,
-    pub version: String,
+    pub version: String, // 可执行文件中的版本号，读取不到时为空
+    pub edition: GameEdition,
+    pub extender: Option<ScriptExtender>,
+    pub module: String, // 当前打开的模块（Modules 下的目录名）
+}
+
+impl GameInstance {
+    // 检测安装目录的游戏版本
+    pub fn new(path: PathBuf, module: &str) -> Self {
+        let info = EditionInfo::detect(&path, module);
+        Self {
+            path,
+            version: info.version.unwrap_or_default(),
+            edition: info.edition,
+            extender: info.extender,
+            module: module.to_string(),
+        }
+    }
+    
+    pub fn edition_info(&self) -> EditionInfo {
+        EditionInfo {
+            edition: self.edition,
+            extender: self.extender,
+            version: (!self.version.is_empty()).then(|| self.version.clone()),
+        }
+    }
+    
+    // 当前模块目录
+    pub fn module_dir(&self) -> PathBuf {
+        self.path.join("Modules").join(&self.module)
+    }
 }
 
 pub struct GameManager {
     parser: Parser,
     current_game: Option<GameInstance>,
     current_data: Option<GameData>,
+    search_paths: Vec<PathBuf>, // 用户配置的游戏搜索路径
+}
+
+impl Default for GameManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl GameManager {
@@ -23,29 +80,100 @@ impl GameManager {
             parser: Parser::new(),
             current_game: None,
             current_data: None,
+            search_paths: Vec::new(),
         }
     }
     
-    // 检测游戏安装
-    pub fn detect_game() -> Result<Option<GameInstance>> {
-        // macOS Steam路径
-        let steam_path = dirs::home_dir()
-            .map(|home| home.join("Library/Application Support/Steam/steamapps/common/MountBlade Warband"));
+    // 检测游戏安装，返回排名最高的候选
+    pub fn detect_game(&self) -> Result<Option<GameInstance>> {
+        Ok(self.detect_games().into_iter().next().map(|candidate| GameInstance::new(candidate.path, NATIVE_MODULE)))
+    }
+    
+    // 检测所有候选安装目录（已排序）
+    pub fn detect_games(&self) -> Vec<GameCandidate> {
+        GameDetector::new()
+            .with_search_paths(self.search_paths.clone())
+            .detect()
+    }
+    
+    // 设置用户配置的搜索路径
+    pub fn set_search_paths(&mut self, paths: Vec<PathBuf>) {
+        self.search_paths = paths;
+    }
+    
+    // 列出游戏目录下的模块：原版在前，其余为含 module.ini 的目录，按目录名排序
+    pub fn list_modules<P: AsRef<Path>>(game_path: P) -> Result<Vec<Module>> {
+        let modules_path = game_path.as_ref().join("Modules");
+        if !modules_path.is_dir() {
+            return Err(anyhow::anyhow!("未找到Modules目录: {}", modules_path.display()));
+        }
         
-        if let Some(path) = steam_path {
-            if path.exists() {
-                return Ok(Some(GameInstance {
-                    path,
-                    version: "1.174".to_string(),
-                }));
+        let mut modules = Vec::new();
+        for entry in std::fs::read_dir(&modules_path)?.flatten() {
+            if !entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+                continue;
+            }
+            let id = entry.file_name().to_string_lossy().to_string();
+            let is_native = id == NATIVE_MODULE;
+            if is_native || entry.path().join("module.ini").is_file() {
+                modules.push(Self::read_module(&entry.path(), id, is_native));
             }
         }
         
-        Ok(None)
+        modules.sort_by(|a, b| b.is_native.cmp(&a.is_native).then_with(|| a.id.cmp(&b.id)));
+        Ok(modules)
+    }
+    
+    // 读取模块目录的信息（只读 module.ini 和检查已知文件，不遍历目录）
+    fn read_module(dir: &Path, id: String, is_native: bool) -> Module {
+        let ini = ModuleIni::load(dir.join("module.ini")).ok();
+        let name = ini.as_ref()
+            .and_then(|ini| ini.get(MODULE_NAME))
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map_or_else(|| id.clone(), str::to_string);
+        Module {
+            name,
+            id,
+            path: dir.to_string_lossy().to_string(),
+            is_native,
+            compatible_with_warband: ini.is_some_and(|ini| ini.compatible_with_warband()),
+            data_files: MODULE_DATA_FILES.iter()
+                .filter(|file| dir.join(file).is_file())
+                .map(|file| file.to_string())
+                .collect(),
+            has_main_bmp: dir.join("main.bmp").is_file(),
+            stats: None,
+        }
     }
     
-    // 加载游戏数据
-    pub fn load_game<P: AsRef<Path>>(&mut self, game_path: P) -> Result<()> {
+    // 统计模块目录总大小和最近修改时间（递归遍历，读不到的条目跳过）
+    pub fn module_stats<P: AsRef<Path>>(dir: P) -> ModuleStats {
+        let mut stats = ModuleStats::default();
+        let mut pending = vec![dir.as_ref().to_path_buf()];
+        while let Some(dir) = pending.pop() {
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let Ok(metadata) = entry.metadata() else {
+                    continue;
+                };
+                if metadata.is_dir() {
+                    pending.push(entry.path());
+                    continue;
+                }
+                stats.size += metadata.len();
+                if let Ok(time) = metadata.modified() {
+                    stats.modified = Some(stats.modified.map_or(time, |latest: SystemTime| latest.max(time)));
+                }
+            }
+        }
+        stats
+    }
+    
+    // 加载游戏中指定模块的数据
+    pub fn load_game<P: AsRef<Path>>(&mut self, game_path: P, module: &str) -> Result<()> {
         let path = game_path.as_ref().to_path_buf();
         
         // 验证游戏路径
@@ -53,37 +181,125 @@ impl GameManager {
             return Err(anyhow::anyhow!("游戏路径不存在: {}", path.display()));
         }
         
-        // 解析游戏数据
-        let data = self.parser.parse_game_data(&path)?;
+        let game = GameInstance::new(path, module);
+        let module_dir = game.module_dir();
+        if !module_dir.is_dir() {
+            return Err(anyhow::anyhow!("模块不存在: {}", module_dir.display()));
+        }
+        
+        // 按检测到的游戏版本选择记录格式
+        tracing::info!("游戏版本: {}", game.edition_info());
+        let data = self.parser.parse_game_data(&module_dir, game.edition)?;
         
-        self.current_game = Some(GameInstance {
-            path,
-            version: "1.174".to_string(),
-        });
+        self.current_game = Some(game);
         self.current_data = Some(data);
         
-        tracing::info!("游戏数据加载成功");
+        for warning in self.get_warnings() {
+            tracing::warn!("{}", warning);
+        }
+        tracing::info!("模块 {} 数据加载成功", module);
         Ok(())
     }
     
+    // 设置解析模式（宽松/严格）
+    pub fn set_parse_mode(&mut self, mode: ParseMode) {
+        self.parser.set_mode(mode);
+    }
+    
+    // 获取上次加载时收集的解析问题
+    pub fn get_warnings(&self) -> &[ParseWarning] {
+        self.current_data
+            .as_ref()
+            .map(|data| data.warnings.as_slice())
+            .unwrap_or_default()
+    }
+    
+    // 读取当前模块的 module.ini
+    pub fn load_module_ini(&self) -> Result<ModuleIni> {
+        let game = self.current_game.as_ref().ok_or_else(|| anyhow::anyhow!("没有加载的游戏"))?;
+        ModuleIni::load(game.module_dir().join("module.ini"))
+    }
+    
+    // 写回当前模块的 module.ini
+    pub fn save_module_ini(&self, ini: &ModuleIni) -> Result<()> {
+        let game = self.current_game.as_ref().ok_or_else(|| anyhow::anyhow!("没有加载的游戏"))?;
+        ini.save(game.module_dir().join("module.ini"))
+    }
+    
     // 获取当前游戏数据
     pub fn get_data(&self) -> Option<&GameData> {
         self.current_data.as_ref()
     }
     
+    // 获取可修改的游戏数据
+    pub fn get_data_mut(&mut self) -> Option<&mut GameData> {
+        self.current_data.as_mut()
+    }
+    
     // 获取当前游戏实例
     pub fn get_game(&self) -> Option<&GameInstance> {
         self.current_game.as_ref()
     }
     
+    // 检查声音和音乐引用的文件，先找模块目录，再找游戏根目录
+    pub fn find_missing_audio(&self) -> Vec<MissingAudio> {
+        match (&self.current_game, &self.current_data) {
+            (Some(game), Some(data)) => find_missing_audio(data, &game.module_dir(), &game.path),
+            _ => Vec::new(),
+        }
+    }
+    
     // 保存游戏数据
-    pub fn save_data(&self) -> Result<()> {
-        if let (Some(game), Some(_data)) = (&self.current_game, &self.current_data) {
-            tracing::info!("保存游戏数据到: {}", game.path.display());
-            // 这里将来实现实际的保存逻辑
-            Ok(())
+    pub fn save_data(&mut self) -> Result<()> {
+        if let (Some(game), Some(data)) = (&self.current_game, &mut self.current_data) {
+            let module_dir = game.module_dir();
+            tracing::info!("保存游戏数据到: {}", module_dir.display());
+            Writer::save_game_data(data, &module_dir)
         } else {
             Err(anyhow::anyhow!("没有加载的游戏数据"))
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::test_util::TempDir;
+
+    #[test]
+    fn test_list_modules() {
+        let root = TempDir::new("list_modules");
+        root.mkdir("Modules/Broken");
+        root.write("Modules/Native/troops.txt", b"troopsfile version 2\n0\n");
+        root.write("Modules/Native/main.bmp", b"BM");
+        root.write("Modules/Alpha/module.ini", b"compatible_with_warband = 1\nmodule_name = Alpha Campaign\n");
+        root.write("Modules/Zeta/module.ini", b"");
+
+        let list = GameManager::list_modules(root.path()).unwrap();
+
+        let ids: Vec<&str> = list.iter().map(|module| module.id.as_str()).collect();
+        assert_eq!(ids, vec!["Native", "Alpha", "Zeta"]);
+        let names: Vec<&str> = list.iter().map(|module| module.name.as_str()).collect();
+        assert_eq!(names, vec!["Native", "Alpha Campaign", "Zeta"]);
+        assert_eq!(list[0].data_files, vec!["troops.txt"]);
+        assert!(list[0].has_main_bmp);
+        assert!(list[0].stats.is_none());
+        assert!(list[1].compatible_with_warband);
+        assert!(!list[2].compatible_with_warband);
+    }
+
+    #[test]
+    fn test_module_stats() {
+        let root = TempDir::new("module_stats");
+        let files: [(&str, &[u8]); 2] = [("Mod/troops.txt", b"troopsfile version 2\n0\n"), ("Mod/Textures/a.dds", b"DDS ")];
+        for (path, content) in files {
+            root.write(path, content);
+        }
+
+        let stats = GameManager::module_stats(root.path().join("Mod"));
+
+        let total: usize = files.iter().map(|(_, content)| content.len()).sum();
+        assert_eq!(stats.size, total as u64);
+        assert!(stats.modified.is_some());
+    }
+}
@@ -1,9 +1,25 @@
 // 数据模型和解析模块
 
 pub mod models;
+pub mod diagnostics;
+pub mod reader;
 pub mod parser;
+pub mod writer;
 pub mod game;
+pub mod audio;
+pub mod module_ini;
+pub mod detect;
+pub mod edition;
+#[cfg(test)]
+pub(crate) mod test_util;
 
 pub use models::*;
+pub use diagnostics::*;
+pub use reader::*;
 pub use parser::*;
+pub use writer::*;
 pub use game::*;
+pub use audio::*;
+pub use module_ini::*;
+pub use detect::*;
+pub use edition::*;
@@ -1,63 +1,1252 @@
 // 游戏数据模型
 
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use super::diagnostics::ParseWarning;
+use super::edition::GameEdition;
+
+// 触发器操作（操作码 + 参数列表）
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct Operation {
+    pub opcode: i64,
+    pub operands: Vec<i64>,
+}
+
+// 简单触发器（检查间隔 + 操作块）
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct SimpleTrigger {
+    pub interval: f64,
+    pub operations: Vec<Operation>,
+}
+
+// 物品模型（网格名 + 修正位）
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct ItemMesh {
+    pub name: String,
+    pub modifier_bits: u64,
+}
+
+// 从该版本（itemsfile version 3）起物品记录带派系列表，初代为 version 2（见 GameEdition::items_version）
+pub const ITEM_FACTIONS_VERSION: u32 = 3;
 
 // 物品数据
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Item {
     pub id: String,
     pub name: String,
-    pub item_type: String, // 改为String类型以简化处理
+    pub plural_name: String,
+    pub meshes: Vec<ItemMesh>,
+    pub flags: u64,          // itp_* 标志，低字节为物品类型
+    pub capabilities: u64,   // itcf_* 战斗能力
     pub price: i32,
-    pub weight: f32,
-    pub damage: i32,
-    pub armor: i32,
+    pub modifier_bits: u64,  // imodbits 可用前缀
+    pub weight: f64,
+    pub abundance: i32,
+    pub head_armor: i32,
+    pub body_armor: i32,
+    pub leg_armor: i32,
+    pub difficulty: i32,
+    pub hit_points: i32,
+    pub speed_rating: i32,
+    pub missile_speed: i32,
+    pub weapon_length: i32,
+    pub max_ammo: i32,
+    pub thrust_damage: i32,  // 低8位为伤害值，高位为伤害类型
+    pub swing_damage: i32,
+    pub factions: Vec<i32>,
+    pub triggers: Vec<SimpleTrigger>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl Item {
+    // 物品类型（flags 低字节）
+    pub fn item_type(&self) -> ItemType {
+        ItemType::from_flags(self.flags)
+    }
+
+    // 设置物品类型，保留其余标志位
+    pub fn set_item_type(&mut self, item_type: ItemType) {
+        self.flags = (self.flags & !0xff) | item_type as u64;
+    }
+
+    // 主要伤害值（挥砍与刺击取较大者）
+    pub fn damage(&self) -> i32 {
+        damage_amount(self.swing_damage).max(damage_amount(self.thrust_damage))
+    }
+
+    // 修改主要伤害值，保留伤害类型
+    pub fn set_damage(&mut self, amount: i32) {
+        let target = if damage_amount(self.swing_damage) >= damage_amount(self.thrust_damage) {
+            &mut self.swing_damage
+        } else {
+            &mut self.thrust_damage
+        };
+        *target = (*target & !0xff) | (amount & 0xff);
+    }
+
+    // 主要护甲值
+    pub fn armor(&self) -> i32 {
+        self.body_armor
+    }
+}
+
+// 伤害数值（去掉伤害类型位）
+pub fn damage_amount(raw: i32) -> i32 {
+    raw & 0xff
+}
+
+// 伤害类型
+pub fn damage_type(raw: i32) -> DamageType {
+    match (raw >> 8) & 0x3 {
+        1 => DamageType::Pierce,
+        2 => DamageType::Blunt,
+        _ => DamageType::Cut,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DamageType {
+    Cut,
+    Pierce,
+    Blunt,
+}
+
+// 物品类型（itp_type_*）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ItemType {
-    Weapon,
-    Armor,
-    Horse,
-    Other,
+    Invalid = 0x00,
+    Horse = 0x01,
+    OneHandedWeapon = 0x02,
+    TwoHandedWeapon = 0x03,
+    Polearm = 0x04,
+    Arrows = 0x05,
+    Bolts = 0x06,
+    Shield = 0x07,
+    Bow = 0x08,
+    Crossbow = 0x09,
+    Thrown = 0x0a,
+    Goods = 0x0b,
+    HeadArmor = 0x0c,
+    BodyArmor = 0x0d,
+    FootArmor = 0x0e,
+    HandArmor = 0x0f,
+    Pistol = 0x10,
+    Musket = 0x11,
+    Bullets = 0x12,
+    Animal = 0x13,
+    Book = 0x14,
+}
+
+impl ItemType {
+    const ALL: [ItemType; 21] = [
+        ItemType::Invalid, ItemType::Horse, ItemType::OneHandedWeapon, ItemType::TwoHandedWeapon,
+        ItemType::Polearm, ItemType::Arrows, ItemType::Bolts, ItemType::Shield, ItemType::Bow,
+        ItemType::Crossbow, ItemType::Thrown, ItemType::Goods, ItemType::HeadArmor,
+        ItemType::BodyArmor, ItemType::FootArmor, ItemType::HandArmor, ItemType::Pistol,
+        ItemType::Musket, ItemType::Bullets, ItemType::Animal, ItemType::Book,
+    ];
+
+    pub fn from_flags(flags: u64) -> Self {
+        Self::ALL.get((flags & 0xff) as usize).copied().unwrap_or(ItemType::Invalid)
+    }
+
+    // 对应模块系统中的常量名
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ItemType::Invalid => "invalid",
+            ItemType::Horse => "horse",
+            ItemType::OneHandedWeapon => "one_handed_wpn",
+            ItemType::TwoHandedWeapon => "two_handed_wpn",
+            ItemType::Polearm => "polearm",
+            ItemType::Arrows => "arrows",
+            ItemType::Bolts => "bolts",
+            ItemType::Shield => "shield",
+            ItemType::Bow => "bow",
+            ItemType::Crossbow => "crossbow",
+            ItemType::Thrown => "thrown",
+            ItemType::Goods => "goods",
+            ItemType::HeadArmor => "head_armor",
+            ItemType::BodyArmor => "body_armor",
+            ItemType::FootArmor => "foot_armor",
+            ItemType::HandArmor => "hand_armor",
+            ItemType::Pistol => "pistol",
+            ItemType::Musket => "musket",
+            ItemType::Bullets => "bullets",
+            ItemType::Animal => "animal",
+            ItemType::Book => "book",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|t| t.as_str() == name)
+    }
+}
+
+// 兵种装备栏位（物品编号 + 物品修正）
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct TroopItem {
+    pub item: i32,     // -1 表示空栏位
+    pub modifier: u32,
+}
+
+impl Default for TroopItem {
+    fn default() -> Self {
+        Self { item: -1, modifier: 0 }
+    }
+}
+
+// 面部特征码（256位，按高位在前的4个64位字存储）
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+pub struct FaceKey {
+    pub words: [u64; 4],
+}
+
+impl FaceKey {
+    // 模块系统中使用的十六进制写法
+    pub fn to_hex(&self) -> String {
+        let digits: String = self.words.iter().map(|word| format!("{:016x}", word)).collect();
+        format!("0x{}", digits)
+    }
 }
 
+pub const TROOP_INVENTORY_SLOTS: usize = 64;
+pub const TROOP_PROFICIENCY_COUNT: usize = 7;
+pub const TROOP_SKILL_WORDS: usize = 6;
+
+// 兵种标志（tf_*）
+pub const TF_SKIN_MASK: u64 = 0x0000000f; // 低 4 位为 skins.txt 中的皮肤编号
+pub const TF_HERO: u64 = 0x00000010;
+pub const TF_GUARANTEE_HORSE: u64 = 0x01000000;
+pub const TF_GUARANTEE_RANGED: u64 = 0x04000000;
+
 // 兵种数据
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Troop {
     pub id: String,
     pub name: String,
-    pub level: i32,
-    pub faction: String,
-    pub troop_class: String, // 添加兵种类型字段
+    pub plural_name: String,
+    pub image: String,
+    pub flags: u64,
+    pub scene: u32,
+    pub reserved: u32,
+    pub faction: i32,
+    pub upgrade_1: i32,
+    pub upgrade_2: i32,
+    pub inventory: Vec<TroopItem>,
     pub strength: i32,
     pub agility: i32,
     pub intelligence: i32,
     pub charisma: i32,
+    pub level: i32,
+    pub proficiencies: [u32; TROOP_PROFICIENCY_COUNT], // 单手 双手 长柄 弓 弩 投掷 火器
+    pub skill_words: [u32; TROOP_SKILL_WORDS],          // 每个字保存8个4位技能等级
+    pub face_keys: [FaceKey; 2],
+}
+
+impl Default for Troop {
+    fn default() -> Self {
+        Self {
+            id: String::new(),
+            name: String::new(),
+            plural_name: String::new(),
+            image: "0".to_string(),
+            flags: 0,
+            scene: 0,
+            reserved: 0,
+            faction: 0,
+            upgrade_1: 0,
+            upgrade_2: 0,
+            inventory: vec![TroopItem::default(); TROOP_INVENTORY_SLOTS],
+            strength: 4,
+            agility: 4,
+            intelligence: 4,
+            charisma: 4,
+            level: 1,
+            proficiencies: [0; TROOP_PROFICIENCY_COUNT],
+            skill_words: [0; TROOP_SKILL_WORDS],
+            face_keys: [FaceKey::default(); 2],
+        }
+    }
 }
 
+impl Troop {
+    // 读取技能等级
+    pub fn skill(&self, skill_no: usize) -> u32 {
+        let word = self.skill_words.get(skill_no / 8).copied().unwrap_or(0);
+        (word >> ((skill_no % 8) * 4)) & 0xf
+    }
+
+    // 设置技能等级（0-15）
+    pub fn set_skill(&mut self, skill_no: usize, level: u32) {
+        if let Some(word) = self.skill_words.get_mut(skill_no / 8) {
+            let shift = (skill_no % 8) * 4;
+            *word = (*word & !(0xf << shift)) | ((level.min(15)) << shift);
+        }
+    }
+
+    pub fn is_hero(&self) -> bool {
+        self.flags & TF_HERO != 0
+    }
+
+    // 皮肤编号（0 为男性，1 为女性）
+    pub fn skin(&self) -> usize {
+        (self.flags & TF_SKIN_MASK) as usize
+    }
+
+    pub fn set_skin(&mut self, skin: usize) {
+        self.flags = (self.flags & !TF_SKIN_MASK) | (skin as u64 & TF_SKIN_MASK);
+    }
+
+    // 根据保证装备标志推断的兵种类型
+    pub fn troop_class(&self) -> &'static str {
+        if self.flags & TF_GUARANTEE_HORSE != 0 {
+            "cavalry"
+        } else if self.flags & TF_GUARANTEE_RANGED != 0 {
+            "archer"
+        } else {
+            "infantry"
+        }
+    }
+
+    // 非空装备栏位
+    pub fn equipped_items(&self) -> impl Iterator<Item = &TroopItem> {
+        self.inventory.iter().filter(|slot| slot.item >= 0)
+    }
+}
+
+// 派系标志（ff_*）
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+pub struct FactionFlags {
+    pub bits: u64,
+}
+
+impl FactionFlags {
+    pub const ALWAYS_HIDE_LABEL: u64 = 0x00000001;
+    pub const MAX_RATING_MASK: u64 = 0x0000ff00;
+
+    pub fn always_hide_label(&self) -> bool {
+        self.bits & Self::ALWAYS_HIDE_LABEL != 0
+    }
+
+    pub fn set_always_hide_label(&mut self, hide: bool) {
+        if hide {
+            self.bits |= Self::ALWAYS_HIDE_LABEL;
+        } else {
+            self.bits &= !Self::ALWAYS_HIDE_LABEL;
+        }
+    }
+
+    // 玩家在该派系可达到的最高声望
+    pub fn max_player_rating(&self) -> u32 {
+        ((self.bits & Self::MAX_RATING_MASK) >> 8) as u32
+    }
+
+    pub fn set_max_player_rating(&mut self, rating: u32) {
+        self.bits = (self.bits & !Self::MAX_RATING_MASK) | (((rating & 0xff) as u64) << 8);
+    }
+}
+
+pub const DEFAULT_FACTION_COLOR: u32 = 0xAAAAAA;
+
 // 派系数据
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Faction {
     pub id: String,
     pub name: String,
-    pub color: String,
-    pub culture: String, // 添加文化字段
+    pub flags: FactionFlags,
+    pub coherence: Option<f64>, // 战团导出的文件不含一致性字段
+    pub color: u32,             // ARGB
+    pub relations: Vec<f64>,    // 与每个派系（按编号）的关系，-1.0 ~ 1.0
+    pub ranks: Vec<String>,
 }
 
-// 游戏数据集合
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl Default for Faction {
+    fn default() -> Self {
+        Self {
+            id: String::new(),
+            name: String::new(),
+            flags: FactionFlags::default(),
+            coherence: None,
+            color: DEFAULT_FACTION_COLOR,
+            relations: Vec::new(),
+            ranks: Vec::new(),
+        }
+    }
+}
+
+impl Faction {
+    // 与指定派系的关系
+    pub fn relation_with(&self, faction_no: usize) -> f64 {
+        self.relations.get(faction_no).copied().unwrap_or(0.0)
+    }
+
+    // 颜色的 #RRGGBB 写法
+    pub fn color_hex(&self) -> String {
+        format!("#{:06X}", self.color & 0xFFFFFF)
+    }
+}
+
+// 队伍中的一组兵种
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+pub struct PartyStack {
+    pub troop: i32,
+    pub count: i32,
+    pub flags: u32, // pmf_*
+}
+
+impl PartyStack {
+    pub const PMF_IS_PRISONER: u32 = 0x0001;
+
+    pub fn is_prisoner(&self) -> bool {
+        self.flags & Self::PMF_IS_PRISONER != 0
+    }
+}
+
+// 大地图队伍（城镇、城堡、村庄等）
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct Party {
+    pub id: String,
+    pub name: String,
+    pub flags: u64,         // pf_*，低字节为地图图标
+    pub menu: i32,
+    pub template: i32,      // 队伍模板编号
+    pub faction: i32,
+    pub personality: i32,
+    pub ai_behavior: i32,
+    pub ai_target: i32,
+    pub behavior_position: [f64; 2], // 默认行为位置
+    pub target_position: [f64; 2],   // AI 目标位置
+    pub position: [f64; 2],          // 大地图坐标
+    pub position_z: f64,             // 导出脚本固定写为 0.0
+    pub stacks: Vec<PartyStack>,
+    pub bearing: f64,       // 朝向（弧度）
+}
+
+impl Party {
+    pub const ICON_MASK: u64 = 0x000000ff;
+
+    // 地图图标编号
+    pub fn icon(&self) -> usize {
+        (self.flags & Self::ICON_MASK) as usize
+    }
+
+    pub fn set_icon(&mut self, icon: usize) {
+        self.flags = (self.flags & !Self::ICON_MASK) | (icon as u64 & Self::ICON_MASK);
+    }
+
+    // 移动队伍，与原坐标相同的默认行为位置和 AI 目标位置一起移动
+    pub fn set_position(&mut self, position: [f64; 2]) {
+        for other in [&mut self.behavior_position, &mut self.target_position] {
+            if *other == self.position {
+                *other = position;
+            }
+        }
+        self.position = position;
+    }
+
+    // 地图图标名称（图标编号超出范围时为 None）
+    pub fn icon_name<'a>(&self, icons: &'a [MapIcon]) -> Option<&'a str> {
+        icons.get(self.icon()).map(|icon| icon.id.as_str())
+    }
+
+    // 按 id 前缀区分城镇、城堡、村庄
+    pub fn party_kind(&self) -> &'static str {
+        if self.id.starts_with("p_town_") {
+            "town"
+        } else if self.id.starts_with("p_castle_") {
+            "castle"
+        } else if self.id.starts_with("p_village_") {
+            "village"
+        } else {
+            "other"
+        }
+    }
+
+    // 队伍中的兵种总数（不含俘虏）
+    pub fn troop_count(&self) -> i32 {
+        self.stacks.iter()
+            .filter(|stack| !stack.is_prisoner())
+            .map(|stack| stack.count)
+            .sum()
+    }
+}
+
+// 大地图图标
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct MapIcon {
+    pub id: String,
+    pub flags: u64,        // mcn_*
+    pub mesh_name: String,
+    pub scale: f64,
+    pub sound: i32,        // 音效编号
+    pub offset: [f64; 3],  // 旗帜偏移
+    pub triggers: Vec<SimpleTrigger>,
+}
+
+// 场景物品（scene_props.txt）
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct SceneProp {
+    pub id: String,
+    pub flags: u64,       // sokf_*
+    pub hit_points: i32,  // 文件中单独保存的生命值
+    pub mesh_name: String,
+    pub physics_object: String, // 碰撞体，"0" 表示没有
+    pub triggers: Vec<SimpleTrigger>,
+}
+
+impl SceneProp {
+    pub const SOKF_DYNAMIC: u64 = 0x00002000;
+    pub const SOKF_INVISIBLE: u64 = 0x00004000;
+    pub const SOKF_DESTRUCTIBLE: u64 = 0x00008000;
+    pub const SOKF_MOVEABLE: u64 = 0x00010000;
+    pub const SOKF_DYNAMIC_PHYSICS: u64 = 0x00040000;
+
+    pub fn is_destructible(&self) -> bool {
+        self.flags & Self::SOKF_DESTRUCTIBLE != 0
+    }
+
+    pub fn has_physics(&self) -> bool {
+        self.physics_object != "0"
+    }
+}
+
+// 粒子曲线上的关键帧（时间与数值）
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+pub struct Keyframe {
+    pub time: f64,
+    pub value: f64,
+}
+
+// 粒子属性随生命周期变化的曲线，由两个关键帧线性插值
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+pub struct ParticleCurve {
+    pub start: Keyframe,
+    pub end: Keyframe,
+}
+
+impl ParticleCurve {
+    // 计算某一时刻的数值，关键帧之外取端点值
+    pub fn value_at(&self, time: f64) -> f64 {
+        if time <= self.start.time {
+            return self.start.value;
+        }
+        if time >= self.end.time || self.end.time <= self.start.time {
+            return self.end.value;
+        }
+        let t = (time - self.start.time) / (self.end.time - self.start.time);
+        self.start.value + (self.end.value - self.start.value) * t
+    }
+}
+
+// 粒子系统（particle_systems.txt）
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct ParticleSystem {
+    pub id: String,
+    pub flags: u64, // psf_*
+    pub mesh_name: String,
+    pub particles_per_second: i32,
+    pub life: f64,
+    pub damping: f64,
+    pub gravity_strength: f64,
+    pub turbulence_size: f64,
+    pub turbulence_strength: f64,
+    pub alpha: ParticleCurve,
+    pub red: ParticleCurve,
+    pub green: ParticleCurve,
+    pub blue: ParticleCurve,
+    pub scale: ParticleCurve,
+    pub emit_box_size: [f64; 3],
+    pub emit_velocity: [f64; 3],
+    pub emit_dir_randomness: f64,
+    pub rotation_speed: f64,
+    pub rotation_damping: f64,
+}
+
+impl ParticleSystem {
+    // 按文件中的顺序列出所有曲线
+    pub fn curves(&self) -> [(&'static str, &ParticleCurve); 5] {
+        [
+            ("alpha", &self.alpha),
+            ("red", &self.red),
+            ("green", &self.green),
+            ("blue", &self.blue),
+            ("scale", &self.scale),
+        ]
+    }
+
+    // 按名称修改曲线
+    pub fn curve_mut(&mut self, name: &str) -> Option<&mut ParticleCurve> {
+        match name {
+            "alpha" => Some(&mut self.alpha),
+            "red" => Some(&mut self.red),
+            "green" => Some(&mut self.green),
+            "blue" => Some(&mut self.blue),
+            "scale" => Some(&mut self.scale),
+            _ => None,
+        }
+    }
+}
+
+// 菜单选项
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct GameMenuOption {
+    pub id: String,
+    pub conditions: Vec<Operation>,
+    pub text: String,
+    pub consequences: Vec<Operation>,
+    pub door_name: String, // 场景中对应的门，"." 表示没有
+}
+
+// 游戏菜单（menus.txt）
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct GameMenu {
+    pub id: String,
+    pub flags: u64, // mnf_*
+    pub text: String,
+    pub mesh_name: String,
+    pub operations: Vec<Operation>,
+    pub options: Vec<GameMenuOption>,
+}
+
+impl GameMenu {
+    pub const MNF_JOIN_BATTLE: u64 = 0x00000001;
+    pub const MNF_AUTO_ENTER: u64 = 0x00000010;
+    pub const MNF_ENABLE_HOT_KEYS: u64 = 0x00000100;
+    pub const MNF_DISABLE_ALL_KEYS: u64 = 0x00000200;
+    pub const MNF_SCALE_PICTURE: u64 = 0x00001000;
+}
+
+// 界面（presentations.txt）
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct Presentation {
+    pub id: String,
+    pub flags: u64, // prsntf_*
+    pub mesh: i32,  // 背景网格编号
+    pub triggers: Vec<SimpleTrigger>,
+}
+
+impl Presentation {
+    pub const PRSNTF_READ_ONLY: u64 = 0x00000001;
+    pub const PRSNTF_MANUAL_END_ONLY: u64 = 0x00000002;
+}
+
+// 动态贴图材质（tableau_materials.txt）
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct TableauMaterial {
+    pub id: String,
+    pub flags: u64,
+    pub sample_material: String,
+    pub width: i32,
+    pub height: i32,
+    pub mesh_bounds: [i32; 4], // min_x min_y max_x max_y
+    pub operations: Vec<Operation>,
+}
+
+// 后期处理参数（postfx.txt）
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct PostFx {
+    pub id: String,
+    pub flags: u64,
+    pub tonemap_operator: i32,
+    pub params: [[f64; 4]; 3],
+}
+
+// 快速字符串（quick_strings.txt）
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct QuickString {
+    pub id: String,
+    pub text: String,
+}
+
+// 动作中的一段动画
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct ActionSequence {
+    pub duration: f64,
+    pub resource: String,
+    pub start_frame: i32,
+    pub end_frame: i32,
+    pub flags: u64,       // arf_*
+    pub extra_flags: u64, // 模块系统中的第 6 项，省略时为 0
+    pub position: [f64; 3],
+    pub blend_in: f64,
+}
+
+// 动作（actions.txt），按动作编号排列，未定义的编号名称为 "none"
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct Action {
+    pub name: String,
+    pub flags: u64,
+    pub master_flags: u64,
+    pub sequences: Vec<ActionSequence>,
+}
+
+impl Action {
+    pub const UNDEFINED: &'static str = "none";
+
+    pub fn is_defined(&self) -> bool {
+        self.name != Self::UNDEFINED
+    }
+}
+
+// 标签使用次数（tag_uses.txt 中的 "标签 编号 次数;"）
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+pub struct TagUse {
+    pub tag: i32,
+    pub index: i32,
+    pub uses: i32,
+}
+
+pub const PARTY_TEMPLATE_STACKS: usize = 6;
+
+// 队伍模板中的一组兵种（数量在 min ~ max 之间随机）
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+pub struct PartyTemplateStack {
+    pub troop: i32,
+    pub min: i32,
+    pub max: i32,
+    pub flags: u32, // pmf_*
+}
+
+// 队伍模板（巡逻队、强盗等）
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct PartyTemplate {
+    pub id: String,
+    pub name: String,
+    pub flags: u64,
+    pub menu: i32,
+    pub faction: i32,
+    pub personality: i32,
+    pub stacks: Vec<PartyTemplateStack>, // 最多 PARTY_TEMPLATE_STACKS 组
+}
+
+// 队伍模板校验发现的问题
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemplateIssue {
+    TooManyStacks(usize),
+    MissingTroop { stack: usize, troop: i32 },
+    InvalidRange { stack: usize, min: i32, max: i32 },
+}
+
+impl std::fmt::Display for TemplateIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            TemplateIssue::TooManyStacks(count) => {
+                write!(f, "兵种组数量 {} 超过上限 {}", count, PARTY_TEMPLATE_STACKS)
+            }
+            TemplateIssue::MissingTroop { stack, troop } => {
+                write!(f, "第 {} 组引用了不存在的兵种 {}", stack + 1, troop)
+            }
+            TemplateIssue::InvalidRange { stack, min, max } => {
+                write!(f, "第 {} 组最小数量 {} 大于最大数量 {}", stack + 1, min, max)
+            }
+        }
+    }
+}
+
+impl PartyTemplate {
+    // 检查兵种引用和数量范围
+    pub fn validate(&self, troop_count: usize) -> Vec<TemplateIssue> {
+        let mut issues = Vec::new();
+        if self.stacks.len() > PARTY_TEMPLATE_STACKS {
+            issues.push(TemplateIssue::TooManyStacks(self.stacks.len()));
+        }
+        for (stack_no, stack) in self.stacks.iter().enumerate() {
+            if stack.troop < 0 || stack.troop as usize >= troop_count {
+                issues.push(TemplateIssue::MissingTroop { stack: stack_no, troop: stack.troop });
+            }
+            if stack.min > stack.max {
+                issues.push(TemplateIssue::InvalidRange { stack: stack_no, min: stack.min, max: stack.max });
+            }
+        }
+        issues
+    }
+}
+
+// 场景出口的特殊编号（离开场景）
+pub const SCENE_PASSAGE_EXIT: i32 = 100000;
+
+// 场景数据
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct Scene {
+    pub id: String,
+    pub name: String,
+    pub flags: u64,             // sf_*
+    pub mesh_name: String,
+    pub body_name: String,
+    pub min_pos: [f64; 2],
+    pub max_pos: [f64; 2],
+    pub water_level: f64,
+    pub terrain_code: String,   // 十六进制地形码，保留原文以免丢失前导零
+    pub passages: Vec<i32>,     // 通往的场景编号，0 为无，SCENE_PASSAGE_EXIT 为出口
+    pub chest_troops: Vec<i32>, // 储物箱对应的兵种编号
+    pub outer_terrain: String,  // 外部地形网格，"0" 表示无
+}
+
+impl Scene {
+    pub const SF_INDOORS: u64 = 0x00000001;
+    pub const SF_FORCE_SKYBOX: u64 = 0x00000002;
+    pub const SF_GENERATE: u64 = 0x00000100;
+    pub const SF_RANDOMIZE: u64 = 0x00000200;
+    pub const SF_AUTO_ENTRY_POINTS: u64 = 0x00000400;
+    pub const SF_NO_HORSES: u64 = 0x00000800;
+    pub const SF_MUDDY_WATER: u64 = 0x00001000;
+
+    pub fn is_indoors(&self) -> bool {
+        self.flags & Self::SF_INDOORS != 0
+    }
+
+    // 是否由地形码随机生成
+    pub fn is_generated(&self) -> bool {
+        self.flags & Self::SF_GENERATE != 0
+    }
+}
+
+// 触发器（triggers.txt，任务模板中也使用相同结构）
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct Trigger {
+    pub check_interval: f64,
+    pub delay_interval: f64,
+    pub rearm_interval: f64,
+    pub conditions: Vec<Operation>,
+    pub consequences: Vec<Operation>,
+}
+
+pub const MISSION_ITEM_OVERRIDES: usize = 8;
+
+// 任务模板中的出生点记录
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct MissionSpawn {
+    pub entry_point: i32,
+    pub spawn_flags: u64,        // mtef_*
+    pub alter_flags: u64,        // af_*
+    pub ai_flags: u64,           // aif_*
+    pub troop_count: i32,
+    pub item_overrides: Vec<i32>, // 最多 MISSION_ITEM_OVERRIDES 个物品编号
+}
+
+// 任务模板（战斗、城镇闲逛等）
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct MissionTemplate {
+    pub id: String,
+    pub name: String,
+    pub flags: u64,          // mtf_*
+    pub mission_type: i32,
+    pub description: String,
+    pub spawns: Vec<MissionSpawn>,
+    pub triggers: Vec<Trigger>,
+}
+
+impl MissionTemplate {
+    // 使用指定入口点的出生点记录
+    pub fn spawns_at(&self, entry_point: i32) -> impl Iterator<Item = &MissionSpawn> {
+        self.spawns.iter().filter(move |spawn| spawn.entry_point == entry_point)
+    }
+}
+
+// 对话行（conversation.txt）
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct Dialog {
+    pub id: String,
+    pub partner: u64,       // 对话对象：低 12 位为兵种编号，高位为 plyr/party_tpl 等标志
+    pub input_state: i32,   // dialog_states.txt 中的状态编号
+    pub conditions: Vec<Operation>,
+    pub text: String,
+    pub output_state: i32,
+    pub consequences: Vec<Operation>,
+    pub voice_over: Option<String>,
+}
+
+impl Dialog {
+    pub const ANYONE: u64 = 0x00000fff;
+    pub const PLYR: u64 = 0x00010000;
+    pub const PARTY_TPL: u64 = 0x00020000;
+    pub const AUTO_PROCEED: u64 = 0x00040000;
+    pub const MULTI_LINE: u64 = 0x00080000;
+
+    // 是否为玩家说的话
+    pub fn is_player_line(&self) -> bool {
+        self.partner & Self::PLYR != 0
+    }
+
+    // 对话对象的兵种编号（任何人时为 None）
+    pub fn partner_troop(&self) -> Option<i32> {
+        match self.partner & Self::ANYONE {
+            Self::ANYONE => None,
+            troop => Some(troop as i32),
+        }
+    }
+}
+
+// 字符串（strings.txt）
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct GameString {
+    pub id: String,
+    pub text: String,
+}
+
+// 任务（quests.txt）
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct Quest {
+    pub id: String,
+    pub name: String,
+    pub flags: u64,
+    pub description: String,
+}
+
+impl Quest {
+    pub const QF_SHOW_PROGRESSION: u64 = 0x00000001;
+    pub const QF_RANDOM_QUEST: u64 = 0x00000002;
+
+    pub fn is_random(&self) -> bool {
+        self.flags & Self::QF_RANDOM_QUEST != 0
+    }
+}
+
+// 百科页面（info_pages.txt）
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct InfoPage {
+    pub id: String,
+    pub name: String,
+    pub text: String,
+}
+
+// 技能（skills.txt）
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct Skill {
+    pub id: String,
+    pub name: String,
+    pub flags: u64,
+    pub max_level: i32,
+    pub description: String,
+}
+
+impl Skill {
+    pub const SF_BASE_ATT_MASK: u64 = 0x00f;
+    pub const SF_EFFECTS_PARTY: u64 = 0x010;
+    pub const SF_INACTIVE: u64 = 0x100;
+
+    // 关联属性名称
+    pub fn base_attribute(&self) -> &'static str {
+        match self.flags & Self::SF_BASE_ATT_MASK {
+            0 => "strength",
+            1 => "agility",
+            2 => "intelligence",
+            3 => "charisma",
+            _ => "unknown",
+        }
+    }
+
+    pub fn is_party_skill(&self) -> bool {
+        self.flags & Self::SF_EFFECTS_PARTY != 0
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.flags & Self::SF_INACTIVE == 0
+    }
+}
+
+// 皮肤的面部滑块
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct SkinFaceKey {
+    pub id: String,
+    pub key: i32,
+    pub param: i32,
+    pub min: f64,
+    pub max: f64,
+    pub name: String,
+}
+
+// 面部贴图及可用的发色材质
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct FaceTexture {
+    pub texture: String,
+    pub color: u64,
+    pub hair_materials: Vec<String>,
+    pub hair_colors: Vec<u64>,
+}
+
+// 皮肤语音（voice_* 类型与声音名称）
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct SkinVoice {
+    pub kind: i32,
+    pub sound: String,
+}
+
+impl SkinVoice {
+    pub fn kind_name(&self) -> &'static str {
+        match self.kind {
+            0 => "die",
+            1 => "hit",
+            2 => "grunt",
+            3 => "grunt_long",
+            4 => "yell",
+            5 => "warcry",
+            6 => "victory",
+            7 => "stun",
+            _ => "unknown",
+        }
+    }
+}
+
+// 面部滑块之间的约束
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct SkinConstraint {
+    pub value: f64,
+    pub comparison: i32,
+    pub pairs: Vec<(f64, i32)>, // (系数, 滑块编号)
+}
+
+// 皮肤（skins.txt）
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct Skin {
+    pub id: String,
+    pub flags: u64,
+    pub body_mesh: String,
+    pub calf_mesh: String,
+    pub hand_mesh: String,
+    pub head_mesh: String,
+    pub face_keys: Vec<SkinFaceKey>,
+    pub hair_meshes: Vec<String>,
+    pub beard_meshes: Vec<String>,
+    pub hair_textures: Vec<String>,
+    pub beard_textures: Vec<String>,
+    pub face_textures: Vec<FaceTexture>,
+    pub voices: Vec<SkinVoice>,
+    pub skeleton: String,
+    pub scale: f64,
+    pub blood_particles: [i32; 2],
+    pub constraints: Vec<SkinConstraint>,
+}
+
+// 声音使用的采样文件（变体标志为 0 时随机播放）
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct SoundSample {
+    pub file: String,
+    pub variation: i32,
+}
+
+// sounds.txt 开头的全局采样列表中的一项
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct SampleFile {
+    pub file: String,
+    pub flags: u64,
+}
+
+// 声音（sounds.txt），采样文件在文件中按全局列表编号引用
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct Sound {
+    pub id: String,
+    pub flags: u64,
+    pub samples: Vec<SoundSample>,
+}
+
+impl Sound {
+    pub const SF_2D: u64 = 0x00000001;
+    pub const SF_LOOPING: u64 = 0x00000002;
+    pub const SF_START_AT_RANDOM_POS: u64 = 0x00000004;
+    pub const SF_STREAM_FROM_HD: u64 = 0x00000008;
+    pub const SF_PRIORITY_MASK: u64 = 0x000000f0;
+    pub const SF_VOL_MASK: u64 = 0x00000f00;
+    pub const SF_ALWAYS_SEND_VIA_NETWORK: u64 = 0x00100000;
+
+    // 标志名称（模块系统中的 sf_* 写法）
+    pub fn flag_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = [
+            (Self::SF_2D, "sf_2d"),
+            (Self::SF_LOOPING, "sf_looping"),
+            (Self::SF_START_AT_RANDOM_POS, "sf_start_at_random_pos"),
+            (Self::SF_STREAM_FROM_HD, "sf_stream_from_hd"),
+            (Self::SF_ALWAYS_SEND_VIA_NETWORK, "sf_always_send_via_network"),
+        ]
+        .iter()
+        .filter(|(flag, _)| self.flags & flag != 0)
+        .map(|(_, name)| name.to_string())
+        .collect();
+
+        let priority = (self.flags & Self::SF_PRIORITY_MASK) >> 4;
+        if priority != 0 {
+            names.push(format!("sf_priority_{}", priority));
+        }
+        let volume = (self.flags & Self::SF_VOL_MASK) >> 8;
+        if volume != 0 {
+            names.push(format!("sf_vol_{}", volume));
+        }
+        names
+    }
+}
+
+// 音乐（music.txt），文件中没有 id，按序号引用
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct MusicTrack {
+    pub file: String,
+    pub flags: u64,
+    pub continue_flags: u64, // 文件中保存为 flags | continue_flags
+}
+
+impl MusicTrack {
+    pub const MTF_CULTURE_ALL: u64 = 0x0000003f;
+    pub const MTF_MODULE_TRACK: u64 = 0x10000000;
+
+    // 标志名称（模块系统中的 mtf_* 写法）
+    pub fn flag_names(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        if self.flags & Self::MTF_CULTURE_ALL == Self::MTF_CULTURE_ALL {
+            names.push("mtf_culture_all".to_string());
+        } else {
+            for culture in 0..6 {
+                if self.flags & (1 << culture) != 0 {
+                    names.push(format!("mtf_culture_{}", culture + 1));
+                }
+            }
+        }
+        const FLAGS: [(u64, &str); 20] = [
+            (0x00000040, "mtf_looping"),
+            (0x00000080, "mtf_start_immediately"),
+            (0x00000100, "mtf_persist_until_finished"),
+            (0x00000200, "mtf_sit_tavern"),
+            (0x00000400, "mtf_sit_fight"),
+            (0x00000800, "mtf_sit_multiplayer_fight"),
+            (0x00001000, "mtf_sit_ambushed"),
+            (0x00002000, "mtf_sit_town"),
+            (0x00004000, "mtf_sit_town_infiltrate"),
+            (0x00008000, "mtf_sit_killed"),
+            (0x00010000, "mtf_sit_travel"),
+            (0x00020000, "mtf_sit_arena"),
+            (0x00040000, "mtf_sit_siege"),
+            (0x00080000, "mtf_sit_night"),
+            (0x00100000, "mtf_sit_day"),
+            (0x00200000, "mtf_sit_encounter_hostile"),
+            (0x00400000, "mtf_sit_main_title"),
+            (0x00800000, "mtf_sit_victorious"),
+            (0x01000000, "mtf_sit_feast"),
+            (MusicTrack::MTF_MODULE_TRACK, "mtf_module_track"),
+        ];
+        names.extend(
+            FLAGS.iter()
+                .filter(|(flag, _)| self.flags & flag != 0)
+                .map(|(_, name)| name.to_string()),
+        );
+        names
+    }
+}
+
+// 脚本（scripts.txt）
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct Script {
+    pub name: String,
+    pub flags: Option<f64>, // 文件中 -1 表示无标志
+    pub operations: Vec<Operation>,
+}
+
+// 数据文件（文件头版本号 + 记录列表）
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct DataFile<T> {
+    pub version: u32,
+    pub records: Vec<T>,
+    #[serde(default)]
+    pub warnings: Vec<ParseWarning>, // 宽松模式下收集的问题
+}
+
+// Modules 下的一个模块（id 为目录名）
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
 pub struct Module {
     pub id: String,
     pub name: String,
     pub path: String,
     pub is_native: bool,
+    pub compatible_with_warband: bool, // module.ini 中的 compatible_with_warband
+    pub data_files: Vec<String>,       // 存在的已知数据文件
+    pub has_main_bmp: bool,            // 是否有启动画面 main.bmp
+    pub stats: Option<ModuleStats>,    // 目录统计，需要遍历整个目录，列出模块时不计算
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+// 模块目录的统计信息
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+pub struct ModuleStats {
+    pub size: u64,                               // 目录总大小（字节）
+    pub modified: Option<std::time::SystemTime>, // 目录中最近修改的文件时间
+}
+
+impl Module {
+    // 模块列表中显示的文字
+    pub fn display_label(&self) -> String {
+        let mut label = if self.name == self.id {
+            self.name.clone()
+        } else {
+            format!("{} ({})", self.name, self.id)
+        };
+        label.push_str(&format!(" - {} 个数据文件", self.data_files.len()));
+        if let Some(stats) = &self.stats {
+            label.push_str(&format!(", {:.1} MB", stats.size as f64 / (1024.0 * 1024.0)));
+        }
+        if !self.compatible_with_warband {
+            label.push_str(", 非 Warband 模块");
+        }
+        label
+    }
+}
+
+// 游戏数据集合
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct GameData {
     pub items: Vec<Item>,
     pub troops: Vec<Troop>,
     pub factions: Vec<Faction>,
+    pub parties: Vec<Party>,
+    pub party_templates: Vec<PartyTemplate>,
+    pub scenes: Vec<Scene>,
+    pub map_icons: Vec<MapIcon>,
+    pub scripts: Vec<Script>,
+    pub simple_triggers: Vec<SimpleTrigger>,
+    pub triggers: Vec<Trigger>,
+    pub mission_templates: Vec<MissionTemplate>,
+    pub dialogs: Vec<Dialog>,
+    pub dialog_states: Vec<String>, // 按编号排列的对话状态名
+    pub strings: Vec<GameString>,
+    pub quests: Vec<Quest>,
+    pub info_pages: Vec<InfoPage>,
+    pub skills: Vec<Skill>,
+    pub skins: Vec<Skin>,
+    pub sounds: Vec<Sound>,
+    #[serde(default)]
+    pub sound_samples: Vec<SampleFile>, // 全局采样列表，包括没有声音引用的采样
+    pub music: Vec<MusicTrack>,
+    pub scene_props: Vec<SceneProp>,
+    pub particle_systems: Vec<ParticleSystem>,
+    pub menus: Vec<GameMenu>,
+    pub presentations: Vec<Presentation>,
+    pub tableau_materials: Vec<TableauMaterial>,
+    pub postfx: Vec<PostFx>,
+    pub quick_strings: Vec<QuickString>,
+    pub variables: Vec<String>, // 全局变量名，按编号排列
+    pub actions: Vec<Action>,
+    pub tag_uses: Vec<TagUse>,
+    // 编辑器不认识的模块文本文件，保存时不会修改
+    #[serde(default)]
+    pub unknown_files: BTreeMap<String, Vec<u8>>,
     pub modules: Vec<Module>,
+    pub file_versions: HashMap<String, u32>, // 文件名 -> 文件头版本号
+    // 解析时使用的游戏版本，决定按版本区分的记录格式
+    #[serde(default)]
+    pub edition: GameEdition,
+    // 加载时不存在的已知数据文件
+    #[serde(default)]
+    pub missing_files: BTreeSet<String>,
+    // 已知数据文件的原始内容，用于判断重新生成的文件是否有变化
+    #[serde(skip)]
+    pub original_files: BTreeMap<String, Vec<u8>>,
+    // 加载后修改过的数据文件，保存时只重新生成这些文件
+    #[serde(default)]
+    pub changed_files: BTreeSet<String>,
+    // 宽松模式下有内容按默认值替换或被跳过的数据文件，重新生成会改写原数据，不允许保存
+    #[serde(default)]
+    pub lossy_files: BTreeSet<String>,
+    #[serde(default)]
+    pub warnings: Vec<ParseWarning>,
+}
+
+impl GameData {
+    // 标记数据文件已修改
+    pub fn mark_changed(&mut self, file_name: &str) {
+        self.changed_files.insert(file_name.to_string());
+    }
 }
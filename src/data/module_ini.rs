@@ -0,0 +1,441 @@
+// module.ini 读取与编辑（保留注释、未知键、行顺序和原始编码）
+
+use anyhow::Result;
+use std::fmt;
+use std::path::Path;
+use super::writer::Writer;
+
+// 已知键的值类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IniValueKind {
+    Bool,  // 0 或 1
+    Int,
+    Float,
+    Text,
+}
+
+// 按类型解析后的值
+#[derive(Debug, Clone, PartialEq)]
+pub enum IniValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Text(String),
+}
+
+impl IniValue {
+    // 按类型解析文本，格式不符时返回 None
+    pub fn parse(kind: IniValueKind, text: &str) -> Option<Self> {
+        let text = text.trim();
+        match kind {
+            IniValueKind::Bool => match text {
+                "0" => Some(Self::Bool(false)),
+                "1" => Some(Self::Bool(true)),
+                _ => None,
+            },
+            IniValueKind::Int => text.parse().ok().map(Self::Int),
+            IniValueKind::Float => text.parse().ok().map(Self::Float),
+            IniValueKind::Text => Some(Self::Text(text.to_string())),
+        }
+    }
+
+    pub fn kind(&self) -> IniValueKind {
+        match self {
+            Self::Bool(_) => IniValueKind::Bool,
+            Self::Int(_) => IniValueKind::Int,
+            Self::Float(_) => IniValueKind::Float,
+            Self::Text(_) => IniValueKind::Text,
+        }
+    }
+}
+
+impl fmt::Display for IniValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Bool(value) => write!(f, "{}", *value as i32),
+            Self::Int(value) => write!(f, "{}", value),
+            Self::Float(value) => write!(f, "{}", value),
+            Self::Text(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+// 模块显示名称（引擎不读取，没有时使用目录名）
+pub const MODULE_NAME: &str = "module_name";
+
+// 可重复出现、按顺序组成列表的键
+pub const LOAD_RESOURCE: &str = "load_resource";
+pub const LOAD_MOD_RESOURCE: &str = "load_mod_resource";
+
+// 已知键及其类型，不在表中的键按文本处理
+pub const MODULE_INI_KEYS: &[(&str, IniValueKind)] = &[
+    // 模块信息
+    (MODULE_NAME, IniValueKind::Text),
+    // 兼容性
+    ("compatible_with_warband", IniValueKind::Bool),
+    ("compatible_savegame_module", IniValueKind::Text),
+    ("operation_set_version", IniValueKind::Int),
+    ("works_with_version_min", IniValueKind::Int),
+    ("works_with_version_max", IniValueKind::Int),
+    // 资源
+    (LOAD_RESOURCE, IniValueKind::Text),
+    (LOAD_MOD_RESOURCE, IniValueKind::Text),
+    ("scan_module_textures", IniValueKind::Bool),
+    ("scan_module_sounds", IniValueKind::Bool),
+    // 战斗
+    ("battle_size_min", IniValueKind::Int),
+    ("battle_size_max", IniValueKind::Int),
+    ("hide_blood", IniValueKind::Bool),
+    ("can_crouch", IniValueKind::Bool),
+    ("can_run_faster_with_skills", IniValueKind::Bool),
+    ("use_crossbow_as_firearm", IniValueKind::Bool),
+    ("disable_moveable_flag_optimization", IniValueKind::Bool),
+    // 技能与属性上限
+    ("attribute_required_per_skill_level", IniValueKind::Int),
+    ("skill_leadership_bonus", IniValueKind::Int),
+    ("skill_prisoner_management_bonus", IniValueKind::Int),
+    ("base_companion_limit", IniValueKind::Int),
+    ("player_wounded_treshold", IniValueKind::Int),
+    ("hero_wounded_treshold", IniValueKind::Int),
+    ("player_xp_multiplier", IniValueKind::Float),
+    ("hero_xp_multiplier", IniValueKind::Float),
+    ("regulars_xp_multiplier", IniValueKind::Float),
+    // 大地图
+    ("map_min_x", IniValueKind::Float),
+    ("map_max_x", IniValueKind::Float),
+    ("map_min_y", IniValueKind::Float),
+    ("map_max_y", IniValueKind::Float),
+    ("time_multiplier", IniValueKind::Float),
+    ("seeing_range", IniValueKind::Float),
+    ("track_spacing", IniValueKind::Float),
+    ("track_spacing_scale", IniValueKind::Float),
+    // 界面
+    ("has_tutorial", IniValueKind::Bool),
+    ("show_faction_color", IniValueKind::Bool),
+    ("show_party_ids_instead_of_names", IniValueKind::Bool),
+    ("limit_hair_colors", IniValueKind::Bool),
+    ("disable_food_slot", IniValueKind::Bool),
+    ("num_hints", IniValueKind::Int),
+];
+
+// 键的类型，未知键为文本
+pub fn module_ini_key_kind(key: &str) -> IniValueKind {
+    MODULE_INI_KEYS.iter()
+        .find(|(known, _)| *known == key)
+        .map_or(IniValueKind::Text, |(_, kind)| *kind)
+}
+
+// 文件中的一行，raw 为原始字节（mod 的 module.ini 常用 GBK、CP1251 等非 UTF-8 编码）
+#[derive(Debug, Clone, PartialEq)]
+pub enum IniLine {
+    // 键值行，键和值按 UTF-8 解码用于读取和编辑，值被修改后按 "键 = 值" 重新生成
+    Entry { key: String, value: String, raw: Vec<u8> },
+    // 注释、空行和无法识别的行，原样保存
+    Other(Vec<u8>),
+}
+
+impl IniLine {
+    fn parse(raw: &[u8]) -> Self {
+        let line = String::from_utf8_lossy(raw);
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            return Self::Other(raw.to_vec());
+        }
+        match trimmed.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() => Self::Entry {
+                key: key.trim().to_string(),
+                value: value.trim().to_string(),
+                raw: raw.to_vec(),
+            },
+            _ => Self::Other(raw.to_vec()),
+        }
+    }
+
+    fn entry(key: &str, value: String) -> Self {
+        Self::Entry { key: key.to_string(), raw: format!("{} = {}", key, value).into_bytes(), value }
+    }
+
+    fn key(&self) -> Option<&str> {
+        match self {
+            Self::Entry { key, .. } => Some(key),
+            Self::Other(_) => None,
+        }
+    }
+
+    fn raw(&self) -> &[u8] {
+        match self {
+            Self::Entry { raw, .. } | Self::Other(raw) => raw,
+        }
+    }
+}
+
+// module.ini 文件
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ModuleIni {
+    pub lines: Vec<IniLine>,
+    crlf: bool,
+    trailing_newline: bool,
+}
+
+impl ModuleIni {
+    pub fn parse(content: &str) -> Self {
+        Self::parse_bytes(content.as_bytes())
+    }
+
+    // 按行解析原始字节，未修改的行保存时原样写回
+    pub fn parse_bytes(content: &[u8]) -> Self {
+        let mut lines: Vec<&[u8]> = content.split(|&byte| byte == b'\n').collect();
+        if content.is_empty() || content.ends_with(b"\n") {
+            lines.pop();
+        }
+        Self {
+            lines: lines.into_iter()
+                .map(|line| IniLine::parse(line.strip_suffix(b"\r").unwrap_or(line)))
+                .collect(),
+            crlf: content.windows(2).any(|pair| pair == b"\r\n"),
+            trailing_newline: content.ends_with(b"\n"),
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path)
+            .map_err(|e| anyhow::anyhow!("无法读取 {}: {}", path.display(), e))?;
+        Ok(Self::parse_bytes(&bytes))
+    }
+
+    // 先写临时文件再替换，避免写到一半时损坏原文件
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        Writer::save_file(path, self.to_bytes())
+            .map_err(|e| anyhow::anyhow!("无法写入 {}: {}", path.display(), e))
+    }
+
+    // 文件内容，未修改的行保持原始字节
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let newline: &[u8] = if self.crlf { b"\r\n" } else { b"\n" };
+        let mut out = Vec::new();
+        for (index, line) in self.lines.iter().enumerate() {
+            if index > 0 {
+                out.extend_from_slice(newline);
+            }
+            out.extend_from_slice(line.raw());
+        }
+        if self.trailing_newline {
+            out.extend_from_slice(newline);
+        }
+        out
+    }
+
+    // 所有键值（按文件顺序）
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.lines.iter().filter_map(|line| match line {
+            IniLine::Entry { key, value, .. } => Some((key.as_str(), value.as_str())),
+            IniLine::Other(_) => None,
+        })
+    }
+
+    // 键的原始值，重复出现时以最后一次为准（与引擎按顺序读取一致）
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries()
+            .filter(|(k, _)| *k == key)
+            .last()
+            .map(|(_, value)| value)
+    }
+
+    // 按已知类型解析的值
+    pub fn get_value(&self, key: &str) -> Option<IniValue> {
+        self.get(key).and_then(|value| IniValue::parse(module_ini_key_kind(key), value))
+    }
+
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        match IniValue::parse(IniValueKind::Bool, self.get(key)?)? {
+            IniValue::Bool(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_int(&self, key: &str) -> Option<i64> {
+        self.get(key)?.trim().parse().ok()
+    }
+
+    pub fn get_float(&self, key: &str) -> Option<f64> {
+        self.get(key)?.trim().parse().ok()
+    }
+
+    // 设置键值：修改最后一次出现的行，不存在时追加到末尾
+    pub fn set(&mut self, key: &str, value: impl fmt::Display) {
+        let value = value.to_string();
+        match self.lines.iter().rposition(|line| line.key() == Some(key)) {
+            Some(index) => {
+                if let IniLine::Entry { value: old, .. } = &self.lines[index] {
+                    if *old == value {
+                        return;
+                    }
+                }
+                self.lines[index] = IniLine::entry(key, value);
+            }
+            None => self.lines.push(IniLine::entry(key, value)),
+        }
+    }
+
+    // 设置值并检查是否符合已知类型
+    pub fn set_text(&mut self, key: &str, text: &str) -> Result<()> {
+        let kind = module_ini_key_kind(key);
+        let value = IniValue::parse(kind, text)
+            .ok_or_else(|| anyhow::anyhow!("{} 的值 {} 不是有效的 {:?}", key, text.trim(), kind))?;
+        self.set(key, value);
+        Ok(())
+    }
+
+    // 删除键的所有行
+    pub fn remove(&mut self, key: &str) {
+        self.lines.retain(|line| line.key() != Some(key));
+    }
+
+    // 可重复键组成的列表
+    pub fn get_list(&self, key: &str) -> Vec<String> {
+        self.entries()
+            .filter(|(k, _)| *k == key)
+            .map(|(_, value)| value.to_string())
+            .collect()
+    }
+
+    // 设置列表：依次改写已有的行，多出的行删除，不足的接在最后一行之后
+    pub fn set_list(&mut self, key: &str, values: &[String]) {
+        let positions: Vec<usize> = self.lines.iter()
+            .enumerate()
+            .filter(|(_, line)| line.key() == Some(key))
+            .map(|(index, _)| index)
+            .collect();
+
+        for (&index, value) in positions.iter().zip(values) {
+            if self.get_line_value(index) != Some(value.as_str()) {
+                self.lines[index] = IniLine::entry(key, value.clone());
+            }
+        }
+
+        if values.len() < positions.len() {
+            for &index in positions[values.len()..].iter().rev() {
+                self.lines.remove(index);
+            }
+        } else {
+            let insert_at = positions.last().map_or(self.lines.len(), |index| index + 1);
+            let added = values[positions.len()..].iter().map(|value| IniLine::entry(key, value.clone()));
+            self.lines.splice(insert_at..insert_at, added);
+        }
+    }
+
+    fn get_line_value(&self, index: usize) -> Option<&str> {
+        match &self.lines[index] {
+            IniLine::Entry { value, .. } => Some(value),
+            IniLine::Other(_) => None,
+        }
+    }
+
+    // 常用设置
+
+    pub fn compatible_with_warband(&self) -> bool {
+        self.get_bool("compatible_with_warband").unwrap_or(false)
+    }
+
+    pub fn battle_size_range(&self) -> (Option<i64>, Option<i64>) {
+        (self.get_int("battle_size_min"), self.get_int("battle_size_max"))
+    }
+
+    pub fn load_resources(&self) -> Vec<String> {
+        self.get_list(LOAD_RESOURCE)
+    }
+
+    pub fn load_mod_resources(&self) -> Vec<String> {
+        self.get_list(LOAD_MOD_RESOURCE)
+    }
+}
+
+// 按 UTF-8 显示，非 UTF-8 的字节显示为替换字符（保存使用 to_bytes）
+impl fmt::Display for ModuleIni {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&String::from_utf8_lossy(&self.to_bytes()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::test_util::TempDir;
+
+    const SAMPLE: &str = concat!(
+        "# Native module.ini\n",
+        "compatible_with_warband = 1\n",
+        "battle_size_min = 30\n",
+        "battle_size_max=1000\n",
+        "\n",
+        "load_resource = costumes_a\n",
+        "load_resource = costumes_b\n",
+        "load_mod_resource = my_meshes\n",
+        "custom_wse_flag = yes\n",
+        "time_multiplier = 0.25\n",
+    );
+
+    #[test]
+    fn test_typed_values_and_round_trip() {
+        let ini = ModuleIni::parse(SAMPLE);
+        assert!(ini.compatible_with_warband());
+        assert_eq!(ini.battle_size_range(), (Some(30), Some(1000)));
+        assert_eq!(ini.load_resources(), vec!["costumes_a", "costumes_b"]);
+        assert_eq!(ini.load_mod_resources(), vec!["my_meshes"]);
+        assert_eq!(ini.get_value("time_multiplier"), Some(IniValue::Float(0.25)));
+        assert_eq!(ini.get_value("custom_wse_flag"), Some(IniValue::Text("yes".into())));
+        assert_eq!(ini.to_string(), SAMPLE);
+    }
+
+    #[test]
+    fn test_edit_keeps_order() {
+        let mut ini = ModuleIni::parse(SAMPLE);
+        ini.set("battle_size_max", IniValue::Int(300));
+        ini.set_list(LOAD_RESOURCE, &["costumes_a".into(), "costumes_b".into(), "costumes_c".into()]);
+        ini.set("hide_blood", IniValue::Bool(true));
+        assert!(ini.set_text("battle_size_min", "many").is_err());
+
+        assert_eq!(ini.to_string(), concat!(
+            "# Native module.ini\n",
+            "compatible_with_warband = 1\n",
+            "battle_size_min = 30\n",
+            "battle_size_max = 300\n",
+            "\n",
+            "load_resource = costumes_a\n",
+            "load_resource = costumes_b\n",
+            "load_resource = costumes_c\n",
+            "load_mod_resource = my_meshes\n",
+            "custom_wse_flag = yes\n",
+            "time_multiplier = 0.25\n",
+            "hide_blood = 1\n",
+        ));
+
+        ini.set_list(LOAD_RESOURCE, &["costumes_b".into()]);
+        assert_eq!(ini.load_resources(), vec!["costumes_b"]);
+        assert_eq!(ini.lines.len(), 10);
+    }
+
+    #[test]
+    fn test_non_utf8_round_trip() {
+        // GBK 编码的注释和值
+        let content: &[u8] = b"# \xc4\xa3\xbf\xe9\xc9\xe8\xd6\xc3\r\nmodule_name = \xd6\xd0\xce\xc4\r\nbattle_size_max = 100\r\n";
+        let dir = TempDir::new("module_ini_bytes");
+        dir.write("module.ini", content);
+        let path = dir.path().join("module.ini");
+
+        let mut ini = ModuleIni::load(&path).unwrap();
+        ini.save(&path).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), content);
+
+        // 只有修改的行重新生成，其余行保持原始字节
+        ini.set("battle_size_max", IniValue::Int(200));
+        ini.set("module_name", ini.get("module_name").unwrap().to_string());
+        ini.save(&path).unwrap();
+        assert_eq!(
+            std::fs::read(&path).unwrap(),
+            b"# \xc4\xa3\xbf\xe9\xc9\xe8\xd6\xc3\r\nmodule_name = \xd6\xd0\xce\xc4\r\nbattle_size_max = 200\r\n"
+        );
+    }
+}
@@ -1,33 +1,145 @@
 // 游戏数据解析器
 
 use anyhow::Result;
-use std::path::Path;
-use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::sync::{Arc, RwLock};
-use std::io::{BufRead, BufReader};
-use std::fs::File;
 use super::models::*;
+use super::reader::TokenReader;
+use super::diagnostics::{ParseError, ParseMode, ParseWarning};
+use super::edition::GameEdition;
 
-// 解析缓存
+// 解析缓存，每种数据文件一张表（路径 -> 解析结果）
+macro_rules! parse_cache {
+    ($($field:ident: $ty:ty),* $(,)?) => {
+        #[derive(Default)]
+        struct ParseCache {
+            $($field: HashMap<String, DataFile<$ty>>,)*
+            file_timestamps: HashMap<String, std::time::SystemTime>,
+            edition: Option<GameEdition>, // 缓存结果按该版本的记录格式解析
+        }
+        
+        impl ParseCache {
+            fn clear(&mut self) {
+                $(self.$field.clear();)*
+                self.file_timestamps.clear();
+                self.edition = None;
+            }
+            
+            fn stats(&self) -> Vec<(&'static str, usize)> {
+                vec![$((stringify!($field), self.$field.len()),)*]
+            }
+        }
+    };
+}
+
+parse_cache! {
+    items: Item,
+    troops: Troop,
+    factions: Faction,
+    parties: Party,
+    party_templates: PartyTemplate,
+    scenes: Scene,
+    map_icons: MapIcon,
+    scripts: Script,
+    simple_triggers: SimpleTrigger,
+    triggers: Trigger,
+    mission_templates: MissionTemplate,
+    dialogs: Dialog,
+    dialog_states: String,
+    strings: GameString,
+    quests: Quest,
+    info_pages: InfoPage,
+    skills: Skill,
+    skins: Skin,
+    sounds: Sound,
+    sound_samples: SampleFile,
+    music: MusicTrack,
+    scene_props: SceneProp,
+    particle_systems: ParticleSystem,
+    menus: GameMenu,
+    presentations: Presentation,
+    tableau_materials: TableauMaterial,
+    postfx: PostFx,
+    quick_strings: QuickString,
+    variables: String,
+    actions: Action,
+    tag_uses: TagUse,
+}
+
+// 汇总各数据文件的版本号和解析问题
 #[derive(Default)]
-struct ParseCache {
-    items: HashMap<String, Vec<Item>>,
-    troops: HashMap<String, Vec<Troop>>,
-    factions: HashMap<String, Vec<Faction>>,
-    file_timestamps: HashMap<String, std::time::SystemTime>,
+struct LoadedFiles {
+    versions: HashMap<String, u32>,
+    warnings: Vec<ParseWarning>,
+    missing: BTreeSet<String>,
+    lossy: BTreeSet<String>,
+    originals: BTreeMap<String, Vec<u8>>,
+}
+
+impl LoadedFiles {
+    // 读取模块目录中的一个数据文件，文件不存在时记录下来并按空文件处理
+    fn load<T>(
+        &mut self,
+        module_dir: &Path,
+        file_name: &str,
+        parse: impl FnOnce(PathBuf) -> Result<DataFile<T>>,
+    ) -> Result<Vec<T>> {
+        let path = module_dir.join(file_name);
+        if !path.is_file() {
+            self.missing.insert(file_name.to_string());
+            self.warnings.push(ParseWarning(ParseError {
+                file: path.display().to_string(),
+                line: 0,
+                column: 0,
+                record: None,
+                field: "文件".to_string(),
+                message: "文件不存在，按空文件处理".to_string(),
+            }));
+            return Ok(Vec::new());
+        }
+        self.originals.insert(file_name.to_string(), std::fs::read(&path)?);
+        let file = parse(path)?;
+        self.versions.insert(file_name.to_string(), file.version);
+        if !file.warnings.is_empty() {
+            self.lossy.insert(file_name.to_string());
+        }
+        self.warnings.extend(file.warnings);
+        Ok(file.records)
+    }
 }
 
 pub struct Parser {
     cache: Arc<RwLock<ParseCache>>,
+    mode: ParseMode,
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Parser {
     pub fn new() -> Self {
         Self {
             cache: Arc::new(RwLock::new(ParseCache::default())),
+            mode: ParseMode::default(),
+        }
+    }
+    
+    // 设置解析模式，缓存的结果按旧模式解析，需要清空
+    pub fn set_mode(&mut self, mode: ParseMode) {
+        if self.mode != mode {
+            self.mode = mode;
+            self.clear_cache();
         }
     }
     
+    pub fn mode(&self) -> ParseMode {
+        self.mode
+    }
+    
     // 检查文件是否需要重新解析
     fn needs_reparse(&self, path: &Path) -> bool {
         let cache = self.cache.read().unwrap();
@@ -57,204 +169,1855 @@ impl Parser {
         }
     }
     
-    // 解析游戏数据
-    pub fn parse_game_data<P: AsRef<Path>>(&self, game_path: P) -> Result<GameData> {
-        let game_path = game_path.as_ref();
+    // 解析模块目录（Modules/<模块名>）中的全部数据文件，按游戏版本选择记录格式
+    pub fn parse_game_data<P: AsRef<Path>>(&self, module_dir: P, edition: GameEdition) -> Result<GameData> {
+        let module_dir = module_dir.as_ref();
+        let mut files = LoadedFiles::default();
+        
+        // 缓存按其他版本解析时需要清空
+        {
+            let mut cache = self.cache.write().unwrap();
+            if cache.edition != Some(edition) {
+                cache.clear();
+                cache.edition = Some(edition);
+            }
+        }
         
-        let _items = self.parse_items(game_path.join("Modules/Native/item_kinds1.txt"))?;
-        let _troops = self.parse_troops(game_path.join("Modules/Native/troops.txt"))?;
-        let _factions = self.parse_factions(game_path.join("Modules/Native/factions.txt"))?;
+        let items = files.load(module_dir, "item_kinds1.txt", |path| self.parse_items(path, edition))?;
+        let troops = files.load(module_dir, "troops.txt", |path| self.parse_troops(path))?;
+        let factions = files.load(module_dir, "factions.txt", |path| self.parse_factions(path))?;
+        let parties = files.load(module_dir, "parties.txt", |path| self.parse_parties(path))?;
+        let party_templates = files.load(module_dir, "party_templates.txt", |path| self.parse_party_templates(path))?;
+        let scenes = files.load(module_dir, "scenes.txt", |path| self.parse_scenes(path))?;
+        let map_icons = files.load(module_dir, "map_icons.txt", |path| self.parse_map_icons(path))?;
+        let scripts = files.load(module_dir, "scripts.txt", |path| self.parse_scripts(path))?;
+        let simple_triggers = files.load(module_dir, "simple_triggers.txt", |path| self.parse_simple_triggers(path))?;
+        let triggers = files.load(module_dir, "triggers.txt", |path| self.parse_triggers(path))?;
+        let mission_templates = files.load(module_dir, "mission_templates.txt", |path| self.parse_mission_templates(path))?;
+        let dialogs = files.load(module_dir, "conversation.txt", |path| self.parse_dialogs(path))?;
+        let dialog_states = files.load(module_dir, "dialog_states.txt", |path| self.parse_dialog_states(path))?;
+        let strings = files.load(module_dir, "strings.txt", |path| self.parse_strings(path))?;
+        let quests = files.load(module_dir, "quests.txt", |path| self.parse_quests(path))?;
+        let info_pages = files.load(module_dir, "info_pages.txt", |path| self.parse_info_pages(path))?;
+        let skills = files.load(module_dir, "skills.txt", |path| self.parse_skills(path))?;
+        let skins = files.load(module_dir, "skins.txt", |path| self.parse_skins(path))?;
+        let sounds = files.load(module_dir, "sounds.txt", |path| self.parse_sounds(path))?;
+        // 采样列表中的问题已随声音一起记录
+        let sound_samples = match files.missing.contains("sounds.txt") {
+            true => Vec::new(),
+            false => self.parse_sound_samples(module_dir.join("sounds.txt"))?.records,
+        };
+        let music = files.load(module_dir, "music.txt", |path| self.parse_music(path))?;
+        let scene_props = files.load(module_dir, "scene_props.txt", |path| self.parse_scene_props(path))?;
+        let particle_systems = files.load(module_dir, "particle_systems.txt", |path| self.parse_particle_systems(path))?;
+        let menus = files.load(module_dir, "menus.txt", |path| self.parse_menus(path))?;
+        let presentations = files.load(module_dir, "presentations.txt", |path| self.parse_presentations(path))?;
+        let tableau_materials = files.load(module_dir, "tableau_materials.txt", |path| self.parse_tableau_materials(path))?;
+        let postfx = files.load(module_dir, "postfx.txt", |path| self.parse_postfx(path))?;
+        let quick_strings = files.load(module_dir, "quick_strings.txt", |path| self.parse_quick_strings(path))?;
+        let variables = files.load(module_dir, "variables.txt", |path| self.parse_variables(path))?;
+        let actions = files.load(module_dir, "actions.txt", |path| self.parse_actions(path))?;
+        let tag_uses = files.load(module_dir, "tag_uses.txt", |path| self.parse_tag_uses(path))?;
+        let unknown_files = Self::read_unknown_files(module_dir, &files.versions)?;
         
         Ok(GameData {
-            items: _items,
-            troops: _troops,
-            factions: _factions,
+            items,
+            troops,
+            factions,
+            parties,
+            party_templates,
+            scenes,
+            map_icons,
+            scripts,
+            simple_triggers,
+            triggers,
+            mission_templates,
+            dialogs,
+            dialog_states,
+            strings,
+            quests,
+            info_pages,
+            skills,
+            skins,
+            sounds,
+            sound_samples,
+            music,
+            scene_props,
+            particle_systems,
+            menus,
+            presentations,
+            tableau_materials,
+            postfx,
+            quick_strings,
+            variables,
+            actions,
+            tag_uses,
+            unknown_files,
             modules: Vec::new(),
+            file_versions: files.versions,
+            edition,
+            missing_files: files.missing,
+            lossy_files: files.lossy,
+            original_files: files.originals,
+            changed_files: BTreeSet::new(),
+            warnings: files.warnings,
         })
     }
     
-    // 解析物品文件
-    fn parse_items<P: AsRef<Path>>(&self, path: P) -> Result<Vec<Item>> {
-        let path = path.as_ref();
+    // 读取模块目录中其余的 .txt 文件原文
+    fn read_unknown_files(module_dir: &Path, known: &HashMap<String, u32>) -> Result<BTreeMap<String, Vec<u8>>> {
+        let mut files = BTreeMap::new();
+        for entry in std::fs::read_dir(module_dir)? {
+            let path = entry?.path();
+            let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            if path.is_file() && name.ends_with(".txt") && !known.contains_key(name) {
+                files.insert(name.to_string(), std::fs::read(&path)?);
+            }
+        }
+        Ok(files)
+    }
+    
+    // 读取并解析数据文件，文件未修改时直接使用缓存
+    fn parse_file<T: Clone>(
+        &self,
+        path: &Path,
+        cached: fn(&mut ParseCache) -> &mut HashMap<String, DataFile<T>>,
+        read: impl FnOnce(&mut TokenReader) -> Result<DataFile<T>>,
+    ) -> Result<DataFile<T>> {
         let path_str = path.to_string_lossy().to_string();
         
         // 检查缓存
         if !self.needs_reparse(path) {
-            let cache = self.cache.read().unwrap();
-            if let Some(cached_items) = cache.items.get(&path_str) {
-                return Ok(cached_items.clone());
+            let mut cache = self.cache.write().unwrap();
+            if let Some(file) = cached(&mut cache).get(&path_str) {
+                return Ok(file.clone());
             }
         }
         
-        // 流式解析文件
-        let file = File::open(path)?;
-        let reader = BufReader::new(file);
-        let mut items = Vec::new();
-        
-        for line_result in reader.lines() {
-            let line = line_result?;
-            if line.trim().is_empty() || line.starts_with('#') {
-                continue;
-            }
-            
-            if let Some(item) = self.parse_item_line(&line) {
-                items.push(item);
-            }
-        }
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))?;
+        let mut reader = TokenReader::with_file(path.display().to_string(), &content)
+            .with_mode(self.mode);
+        let file = read(&mut reader)?;
         
         // 更新缓存
         {
             let mut cache = self.cache.write().unwrap();
-            cache.items.insert(path_str, items.clone());
+            cached(&mut cache).insert(path_str, file.clone());
         }
         self.update_timestamp(path);
         
-        Ok(items)
+        Ok(file)
     }
     
-    // 解析单个物品行
-    fn parse_item_line(&self, line: &str) -> Option<Item> {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.len() < 7 {
-            return None;
+    // 解析物品文件
+    fn parse_items<P: AsRef<Path>>(&self, path: P, edition: GameEdition) -> Result<DataFile<Item>> {
+        self.parse_file(path.as_ref(), |cache| &mut cache.items, |reader| Self::read_items(reader, edition))
+    }
+    
+    // 解析物品文件（文件头 + 数量 + 物品记录），记录格式由游戏版本决定
+    // 文件头版本与游戏版本不符时（模块来自其他版本或版本检测有误）按文件头的格式读取
+    pub fn read_items(reader: &mut TokenReader, edition: GameEdition) -> Result<DataFile<Item>> {
+        let version = reader.header("itemsfile")?;
+        let mut with_factions = edition.has_item_factions();
+        if version != edition.items_version() {
+            let message = format!(
+                "{}的物品文件应为 version {}，实际为 version {}，按文件头的格式读取",
+                edition.display_name(), edition.items_version(), version
+            );
+            reader.warn("文件头", message)?;
+            with_factions = version >= ITEM_FACTIONS_VERSION;
         }
+        let count = reader.read_count("物品数量")?;
         
-        Some(Item {
-            id: parts[0].to_string(),
-            name: parts[1].replace('_', " "),
-            item_type: "Other".to_string(),
-            price: parts[2].parse().unwrap_or(0),
-            weight: parts[3].parse().unwrap_or(0.0),
-            damage: parts[4].parse().unwrap_or(0),
-            armor: parts[5].parse().unwrap_or(0),
-        })
+        let items = Self::read_records(reader, count, |reader| Self::read_item(reader, with_factions))?;
+        
+        Ok(DataFile { version, records: items, warnings: reader.take_warnings() })
+    }
+    
+    // 解析单个物品记录
+    fn read_item(reader: &mut TokenReader, with_factions: bool) -> Result<Item> {
+        let id = reader.word("id")?.to_string();
+        reader.begin_record(&id);
+        
+        let name = reader.read_name("name")?;
+        let plural_name = reader.read_name("plural_name")?;
+        
+        let mesh_count = reader.read_count("mesh_count")?;
+        let mut meshes = Vec::with_capacity(mesh_count);
+        for _ in 0..mesh_count {
+            meshes.push(ItemMesh {
+                name: reader.word("mesh_name")?.to_string(),
+                modifier_bits: reader.read_u64("mesh_modifier_bits")?,
+            });
+        }
+        
+        let mut item = Item {
+            name,
+            plural_name,
+            meshes,
+            flags: reader.read_u64("flags")?,
+            capabilities: reader.read_u64("capabilities")?,
+            price: reader.read_i32("price")?,
+            modifier_bits: reader.read_u64("modifier_bits")?,
+            weight: reader.read_f64("weight")?,
+            abundance: reader.read_i32("abundance")?,
+            head_armor: reader.read_i32("head_armor")?,
+            body_armor: reader.read_i32("body_armor")?,
+            leg_armor: reader.read_i32("leg_armor")?,
+            difficulty: reader.read_i32("difficulty")?,
+            hit_points: reader.read_i32("hit_points")?,
+            speed_rating: reader.read_i32("speed_rating")?,
+            missile_speed: reader.read_i32("missile_speed")?,
+            weapon_length: reader.read_i32("weapon_length")?,
+            max_ammo: reader.read_i32("max_ammo")?,
+            thrust_damage: reader.read_i32("thrust_damage")?,
+            swing_damage: reader.read_i32("swing_damage")?,
+            ..Default::default()
+        };
+        
+        // 初代没有物品派系列表
+        if with_factions {
+            let faction_count = reader.read_count("faction_count")?;
+            for _ in 0..faction_count {
+                item.factions.push(reader.read_i32("faction")?);
+            }
+        }
+        
+        item.triggers = Self::read_simple_triggers(reader)?;
+        item.id = id;
+        Ok(item)
+    }
+    
+    // 按数量读取记录，宽松模式下记录损坏时保留已读取的部分
+    fn read_records<'a, T>(
+        reader: &mut TokenReader<'a>,
+        count: usize,
+        mut read: impl FnMut(&mut TokenReader<'a>) -> Result<T>,
+    ) -> Result<Vec<T>> {
+        let mut records = Vec::with_capacity(count);
+        for _ in 0..count {
+            let result = read(reader);
+            match reader.recover(result)? {
+                Some(record) => records.push(record),
+                None => {
+                    let message = format!("应有 {} 条记录，仅读取到 {} 条", count, records.len());
+                    reader.warn("记录数量", message)?;
+                    break;
+                }
+            }
+        }
+        Ok(records)
+    }
+    
+    // 解析简单触发器列表
+    fn read_simple_triggers(reader: &mut TokenReader) -> Result<Vec<SimpleTrigger>> {
+        let count = reader.read_count("trigger_count")?;
+        let mut triggers = Vec::with_capacity(count);
+        for _ in 0..count {
+            triggers.push(SimpleTrigger {
+                interval: reader.read_f64("trigger_interval")?,
+                operations: Self::read_operations(reader)?,
+            });
+        }
+        Ok(triggers)
+    }
+    
+    // 解析操作块（数量 + [操作码 参数数量 参数...]）
+    fn read_operations(reader: &mut TokenReader) -> Result<Vec<Operation>> {
+        let count = reader.read_count("operation_count")?;
+        let mut operations = Vec::with_capacity(count);
+        for _ in 0..count {
+            let opcode = reader.read_i64("opcode")?;
+            let operand_count = reader.read_count("operand_count")?;
+            let mut operands = Vec::with_capacity(operand_count);
+            for _ in 0..operand_count {
+                operands.push(reader.read_i64("operand")?);
+            }
+            operations.push(Operation { opcode, operands });
+        }
+        Ok(operations)
     }
     
     // 解析兵种文件
-    fn parse_troops<P: AsRef<Path>>(&self, path: P) -> Result<Vec<Troop>> {
-        let path = path.as_ref();
-        let path_str = path.to_string_lossy().to_string();
+    fn parse_troops<P: AsRef<Path>>(&self, path: P) -> Result<DataFile<Troop>> {
+        self.parse_file(path.as_ref(), |cache| &mut cache.troops, Self::read_troops)
+    }
+    
+    // 解析兵种文件
+    pub fn read_troops(reader: &mut TokenReader) -> Result<DataFile<Troop>> {
+        let version = reader.header("troopsfile")?;
+        let count = reader.read_count("兵种数量")?;
         
-        // 检查缓存
-        if !self.needs_reparse(path) {
-            let cache = self.cache.read().unwrap();
-            if let Some(cached_troops) = cache.troops.get(&path_str) {
-                return Ok(cached_troops.clone());
+        let troops = Self::read_records(reader, count, Self::read_troop)?;
+        
+        Ok(DataFile { version, records: troops, warnings: reader.take_warnings() })
+    }
+    
+    // 解析单个兵种记录（跨多行：基本信息、装备、属性、熟练度、技能、面部特征码）
+    fn read_troop(reader: &mut TokenReader) -> Result<Troop> {
+        let id = reader.word("id")?.to_string();
+        reader.begin_record(&id);
+        
+        let mut troop = Troop {
+            name: reader.read_name("name")?,
+            plural_name: reader.read_name("plural_name")?,
+            image: reader.word("image")?.to_string(),
+            flags: reader.read_u64("flags")?,
+            scene: reader.parse("scene")?,
+            reserved: reader.parse("reserved")?,
+            faction: reader.read_i32("faction")?,
+            upgrade_1: reader.read_i32("upgrade_1")?,
+            upgrade_2: reader.read_i32("upgrade_2")?,
+            ..Default::default()
+        };
+        
+        for slot in troop.inventory.iter_mut() {
+            slot.item = reader.read_i32("inventory_item")?;
+            slot.modifier = reader.parse("inventory_modifier")?;
+        }
+        
+        troop.strength = reader.read_i32("strength")?;
+        troop.agility = reader.read_i32("agility")?;
+        troop.intelligence = reader.read_i32("intelligence")?;
+        troop.charisma = reader.read_i32("charisma")?;
+        troop.level = reader.read_i32("level")?;
+        
+        for proficiency in troop.proficiencies.iter_mut() {
+            *proficiency = reader.parse("proficiency")?;
+        }
+        for word in troop.skill_words.iter_mut() {
+            *word = reader.parse("skill_word")?;
+        }
+        for face_key in troop.face_keys.iter_mut() {
+            for word in face_key.words.iter_mut() {
+                *word = reader.read_u64("face_key")?;
             }
         }
         
-        // 流式解析文件
-        let file = File::open(path)?;
-        let reader = BufReader::new(file);
-        let mut troops = Vec::new();
+        troop.id = id;
+        Ok(troop)
+    }
+    
+    // 解析派系文件
+    fn parse_factions<P: AsRef<Path>>(&self, path: P) -> Result<DataFile<Faction>> {
+        self.parse_file(path.as_ref(), |cache| &mut cache.factions, Self::read_factions)
+    }
+    
+    // 解析派系文件
+    pub fn read_factions(reader: &mut TokenReader) -> Result<DataFile<Faction>> {
+        let version = reader.header("factionsfile")?;
+        let count = reader.read_count("派系数量")?;
+        
+        let factions = Self::read_records(reader, count, |reader| Self::read_faction(reader, count))?;
+        
+        Ok(DataFile { version, records: factions, warnings: reader.take_warnings() })
+    }
+    
+    // 解析单个派系记录（关系行包含与全部派系的关系值）
+    fn read_faction(reader: &mut TokenReader, faction_count: usize) -> Result<Faction> {
+        let id = reader.word("id")?.to_string();
+        reader.begin_record(&id);
+        
+        let name = reader.read_name("name")?;
+        let flags = FactionFlags { bits: reader.read_u64("flags")? };
+        
+        // 一致性以 %f 输出，颜色以 %d 输出，据此判断是否带一致性字段
+        let coherence = if reader.peek().is_some_and(|word| word.contains('.')) {
+            Some(reader.read_f64("coherence")?)
+        } else {
+            None
+        };
+        let color = reader.parse("color")?;
+        
+        let mut relations = Vec::with_capacity(faction_count);
+        for _ in 0..faction_count {
+            relations.push(reader.read_f64("relation")?);
+        }
+        
+        let rank_count = reader.read_count("rank_count")?;
+        let mut ranks = Vec::with_capacity(rank_count);
+        for _ in 0..rank_count {
+            ranks.push(reader.read_name("rank")?);
+        }
+        
+        Ok(Faction {
+            id,
+            name,
+            flags,
+            coherence,
+            color,
+            relations,
+            ranks,
+        })
+    }
+    
+    // 解析队伍文件
+    fn parse_parties<P: AsRef<Path>>(&self, path: P) -> Result<DataFile<Party>> {
+        self.parse_file(path.as_ref(), |cache| &mut cache.parties, Self::read_parties)
+    }
+    
+    // 解析队伍文件（数量写了两次）
+    pub fn read_parties(reader: &mut TokenReader) -> Result<DataFile<Party>> {
+        let version = reader.header("partiesfile")?;
+        let count = reader.read_count("队伍数量")?;
+        reader.read_count("队伍数量")?;
+        
+        let parties = Self::read_records(reader, count, Self::read_party)?;
+        
+        Ok(DataFile { version, records: parties, warnings: reader.take_warnings() })
+    }
+    
+    // 解析单个队伍记录（个性和 AI 目标在文件中重复保存，只取第一份）
+    fn read_party(reader: &mut TokenReader) -> Result<Party> {
+        reader.read_i32("enabled")?;
+        reader.read_i32("index")?;
+        reader.read_i32("index")?;
         
-        for line_result in reader.lines() {
-            let line = line_result?;
-            if line.trim().is_empty() || line.starts_with('#') {
+        let id = reader.word("id")?.to_string();
+        reader.begin_record(&id);
+        
+        let mut party = Party {
+            name: reader.read_name("name")?,
+            flags: reader.read_u64("flags")?,
+            menu: reader.read_i32("menu")?,
+            template: reader.read_i32("template")?,
+            faction: reader.read_i32("faction")?,
+            personality: reader.read_i32("personality")?,
+            ..Default::default()
+        };
+        reader.read_i32("personality")?;
+        party.ai_behavior = reader.read_i32("ai_behavior")?;
+        party.ai_target = reader.read_i32("ai_target")?;
+        reader.read_i32("ai_target")?;
+        
+        // 默认行为位置、AI 目标位置、当前位置
+        party.behavior_position = [reader.read_f64("behavior_x")?, reader.read_f64("behavior_y")?];
+        party.target_position = [reader.read_f64("target_x")?, reader.read_f64("target_y")?];
+        party.position = [reader.read_f64("x")?, reader.read_f64("y")?];
+        party.position_z = reader.read_f64("z")?;
+        
+        let stack_count = reader.read_count("stack_count")?;
+        for _ in 0..stack_count {
+            let troop = reader.read_i32("stack_troop")?;
+            let count = reader.read_i32("stack_count")?;
+            reader.read_i32("stack_reserved")?;
+            let flags = reader.parse("stack_flags")?;
+            party.stacks.push(PartyStack { troop, count, flags });
+        }
+        
+        party.bearing = reader.read_f64("bearing")?;
+        party.id = id;
+        Ok(party)
+    }
+    
+    // 解析队伍模板文件
+    fn parse_party_templates<P: AsRef<Path>>(&self, path: P) -> Result<DataFile<PartyTemplate>> {
+        self.parse_file(path.as_ref(), |cache| &mut cache.party_templates, Self::read_party_templates)
+    }
+    
+    // 解析队伍模板文件
+    pub fn read_party_templates(reader: &mut TokenReader) -> Result<DataFile<PartyTemplate>> {
+        let version = reader.header("partytemplatesfile")?;
+        let count = reader.read_count("队伍模板数量")?;
+        
+        let templates = Self::read_records(reader, count, Self::read_party_template)?;
+        
+        Ok(DataFile { version, records: templates, warnings: reader.take_warnings() })
+    }
+    
+    // 解析单个队伍模板（固定 6 个兵种栏位，空栏位为 -1）
+    fn read_party_template(reader: &mut TokenReader) -> Result<PartyTemplate> {
+        let id = reader.word("id")?.to_string();
+        reader.begin_record(&id);
+        
+        let mut template = PartyTemplate {
+            name: reader.read_name("name")?,
+            flags: reader.read_u64("flags")?,
+            menu: reader.read_i32("menu")?,
+            faction: reader.read_i32("faction")?,
+            personality: reader.read_i32("personality")?,
+            ..Default::default()
+        };
+        
+        for _ in 0..PARTY_TEMPLATE_STACKS {
+            let troop = reader.read_i32("stack_troop")?;
+            if troop < 0 {
                 continue;
             }
-            
-            if let Some(troop) = self.parse_troop_line(&line) {
-                troops.push(troop);
-            }
+            template.stacks.push(PartyTemplateStack {
+                troop,
+                min: reader.read_i32("stack_min")?,
+                max: reader.read_i32("stack_max")?,
+                flags: reader.parse("stack_flags")?,
+            });
         }
         
-        // 更新缓存
-        {
-            let mut cache = self.cache.write().unwrap();
-            cache.troops.insert(path_str, troops.clone());
-        }
-        self.update_timestamp(path);
+        template.id = id;
+        Ok(template)
+    }
+    
+    // 解析场景文件
+    fn parse_scenes<P: AsRef<Path>>(&self, path: P) -> Result<DataFile<Scene>> {
+        self.parse_file(path.as_ref(), |cache| &mut cache.scenes, Self::read_scenes)
+    }
+    
+    // 解析场景文件
+    pub fn read_scenes(reader: &mut TokenReader) -> Result<DataFile<Scene>> {
+        let version = reader.header("scenesfile")?;
+        let count = reader.read_count("场景数量")?;
+        
+        let scenes = Self::read_records(reader, count, Self::read_scene)?;
         
-        Ok(troops)
+        Ok(DataFile { version, records: scenes, warnings: reader.take_warnings() })
     }
     
-    // 解析单个兵种行
-    fn parse_troop_line(&self, line: &str) -> Option<Troop> {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.len() < 8 {
-            return None;
+    // 解析单个场景记录（基本信息、通道列表、储物箱兵种列表、外部地形）
+    fn read_scene(reader: &mut TokenReader) -> Result<Scene> {
+        let id = reader.word("id")?.to_string();
+        reader.begin_record(&id);
+        
+        let mut scene = Scene {
+            name: reader.read_name("name")?,
+            flags: reader.read_u64("flags")?,
+            mesh_name: reader.word("mesh_name")?.to_string(),
+            body_name: reader.word("body_name")?.to_string(),
+            min_pos: [reader.read_f64("min_x")?, reader.read_f64("min_y")?],
+            max_pos: [reader.read_f64("max_x")?, reader.read_f64("max_y")?],
+            water_level: reader.read_f64("water_level")?,
+            terrain_code: reader.word("terrain_code")?.to_string(),
+            ..Default::default()
+        };
+        
+        let passage_count = reader.read_count("passage_count")?;
+        for _ in 0..passage_count {
+            scene.passages.push(reader.read_i32("passage")?);
+        }
+        let chest_count = reader.read_count("chest_troop_count")?;
+        for _ in 0..chest_count {
+            scene.chest_troops.push(reader.read_i32("chest_troop")?);
         }
+        scene.outer_terrain = reader.word("outer_terrain")?.to_string();
+        
+        scene.id = id;
+        Ok(scene)
+    }
+    
+    // 解析地图图标文件
+    fn parse_map_icons<P: AsRef<Path>>(&self, path: P) -> Result<DataFile<MapIcon>> {
+        self.parse_file(path.as_ref(), |cache| &mut cache.map_icons, Self::read_map_icons)
+    }
+    
+    // 解析地图图标文件
+    pub fn read_map_icons(reader: &mut TokenReader) -> Result<DataFile<MapIcon>> {
+        let version = reader.header("map_icons_file")?;
+        let count = reader.read_count("地图图标数量")?;
         
-        Some(Troop {
-            id: parts[0].to_string(),
-            name: parts[1].replace('_', " "),
-            level: parts[2].parse().unwrap_or(1),
-            faction: parts[3].to_string(),
-            troop_class: parts.get(8).unwrap_or(&"Infantry").to_string(),
-            strength: parts[4].parse().unwrap_or(10),
-            agility: parts[5].parse().unwrap_or(10),
-            intelligence: parts[6].parse().unwrap_or(10),
-            charisma: parts[7].parse().unwrap_or(10),
+        let icons = Self::read_records(reader, count, Self::read_map_icon)?;
+        
+        Ok(DataFile { version, records: icons, warnings: reader.take_warnings() })
+    }
+    
+    // 解析单个地图图标记录
+    fn read_map_icon(reader: &mut TokenReader) -> Result<MapIcon> {
+        let id = reader.word("id")?.to_string();
+        reader.begin_record(&id);
+        
+        let icon = MapIcon {
+            flags: reader.read_u64("flags")?,
+            mesh_name: reader.word("mesh_name")?.to_string(),
+            scale: reader.read_f64("scale")?,
+            sound: reader.read_i32("sound")?,
+            offset: [
+                reader.read_f64("offset_x")?,
+                reader.read_f64("offset_y")?,
+                reader.read_f64("offset_z")?,
+            ],
+            triggers: Self::read_simple_triggers(reader)?,
+            id,
+        };
+        Ok(icon)
+    }
+    
+    // 解析脚本文件
+    fn parse_scripts<P: AsRef<Path>>(&self, path: P) -> Result<DataFile<Script>> {
+        self.parse_file(path.as_ref(), |cache| &mut cache.scripts, Self::read_scripts)
+    }
+    
+    // 解析脚本文件
+    pub fn read_scripts(reader: &mut TokenReader) -> Result<DataFile<Script>> {
+        let version = reader.header("scriptsfile")?;
+        let count = reader.read_count("脚本数量")?;
+        
+        let scripts = Self::read_records(reader, count, Self::read_script)?;
+        
+        Ok(DataFile { version, records: scripts, warnings: reader.take_warnings() })
+    }
+    
+    // 解析单个脚本（名称 + 标志 + 操作块），标志 -1 表示无
+    fn read_script(reader: &mut TokenReader) -> Result<Script> {
+        let name = reader.word("name")?.to_string();
+        reader.begin_record(&name);
+        
+        let flags = if reader.peek() == Some("-1") {
+            reader.word("flags")?;
+            None
+        } else {
+            Some(reader.read_f64("flags")?)
+        };
+        
+        Ok(Script {
+            name,
+            flags,
+            operations: Self::read_operations(reader)?,
         })
     }
     
-    // 解析派系文件
-    fn parse_factions<P: AsRef<Path>>(&self, path: P) -> Result<Vec<Faction>> {
-        let path = path.as_ref();
-        let path_str = path.to_string_lossy().to_string();
+    // 解析简单触发器文件
+    fn parse_simple_triggers<P: AsRef<Path>>(&self, path: P) -> Result<DataFile<SimpleTrigger>> {
+        self.parse_file(path.as_ref(), |cache| &mut cache.simple_triggers, Self::read_simple_triggers_file)
+    }
+    
+    // 解析简单触发器文件（每条为间隔 + 操作块）
+    pub fn read_simple_triggers_file(reader: &mut TokenReader) -> Result<DataFile<SimpleTrigger>> {
+        let version = reader.header("simple_triggers_file")?;
+        let count = reader.read_count("简单触发器数量")?;
         
-        // 检查缓存
-        if !self.needs_reparse(path) {
-            let cache = self.cache.read().unwrap();
-            if let Some(cached_factions) = cache.factions.get(&path_str) {
-                return Ok(cached_factions.clone());
+        let mut index = 0;
+        let triggers = Self::read_records(reader, count, |reader| {
+            reader.begin_record(&format!("#{}", index));
+            index += 1;
+            Ok(SimpleTrigger {
+                interval: reader.read_f64("interval")?,
+                operations: Self::read_operations(reader)?,
+            })
+        })?;
+        
+        Ok(DataFile { version, records: triggers, warnings: reader.take_warnings() })
+    }
+    
+    // 解析触发器文件
+    fn parse_triggers<P: AsRef<Path>>(&self, path: P) -> Result<DataFile<Trigger>> {
+        self.parse_file(path.as_ref(), |cache| &mut cache.triggers, Self::read_triggers_file)
+    }
+    
+    // 解析触发器文件
+    pub fn read_triggers_file(reader: &mut TokenReader) -> Result<DataFile<Trigger>> {
+        let version = reader.header("triggersfile")?;
+        let count = reader.read_count("触发器数量")?;
+        
+        let mut index = 0;
+        let triggers = Self::read_records(reader, count, |reader| {
+            reader.begin_record(&format!("#{}", index));
+            index += 1;
+            Self::read_trigger(reader)
+        })?;
+        
+        Ok(DataFile { version, records: triggers, warnings: reader.take_warnings() })
+    }
+    
+    // 解析触发器（检查间隔、延迟、重置间隔、条件块、结果块）
+    fn read_trigger(reader: &mut TokenReader) -> Result<Trigger> {
+        Ok(Trigger {
+            check_interval: reader.read_f64("check_interval")?,
+            delay_interval: reader.read_f64("delay_interval")?,
+            rearm_interval: reader.read_f64("rearm_interval")?,
+            conditions: Self::read_operations(reader)?,
+            consequences: Self::read_operations(reader)?,
+        })
+    }
+    
+    // 解析任务模板文件
+    fn parse_mission_templates<P: AsRef<Path>>(&self, path: P) -> Result<DataFile<MissionTemplate>> {
+        self.parse_file(path.as_ref(), |cache| &mut cache.mission_templates, Self::read_mission_templates)
+    }
+    
+    // 解析任务模板文件
+    pub fn read_mission_templates(reader: &mut TokenReader) -> Result<DataFile<MissionTemplate>> {
+        let version = reader.header("missionsfile")?;
+        let count = reader.read_count("任务模板数量")?;
+        
+        let templates = Self::read_records(reader, count, Self::read_mission_template)?;
+        
+        Ok(DataFile { version, records: templates, warnings: reader.take_warnings() })
+    }
+    
+    // 解析单个任务模板（基本信息、描述、出生点记录、触发器）
+    fn read_mission_template(reader: &mut TokenReader) -> Result<MissionTemplate> {
+        let id = reader.word("id")?.to_string();
+        reader.begin_record(&id);
+        
+        let mut template = MissionTemplate {
+            name: reader.word("name")?.to_string(),
+            flags: reader.read_u64("flags")?,
+            mission_type: reader.read_i32("mission_type")?,
+            description: reader.read_name("description")?,
+            ..Default::default()
+        };
+        
+        let spawn_count = reader.read_count("spawn_count")?;
+        for _ in 0..spawn_count {
+            let mut spawn = MissionSpawn {
+                entry_point: reader.read_i32("entry_point")?,
+                spawn_flags: reader.read_u64("spawn_flags")?,
+                alter_flags: reader.read_u64("alter_flags")?,
+                ai_flags: reader.read_u64("ai_flags")?,
+                troop_count: reader.read_i32("troop_count")?,
+                ..Default::default()
+            };
+            let override_count = reader.read_count("item_override_count")?;
+            if override_count > MISSION_ITEM_OVERRIDES {
+                reader.warn("item_override_count", format!("物品替换数量 {} 超过上限 {}", override_count, MISSION_ITEM_OVERRIDES))?;
+            }
+            for _ in 0..override_count {
+                spawn.item_overrides.push(reader.read_i32("item_override")?);
             }
+            template.spawns.push(spawn);
         }
         
-        // 流式解析文件
-        let file = File::open(path)?;
-        let reader = BufReader::new(file);
-        let mut factions = Vec::new();
+        let trigger_count = reader.read_count("trigger_count")?;
+        for _ in 0..trigger_count {
+            template.triggers.push(Self::read_trigger(reader)?);
+        }
         
-        for line_result in reader.lines() {
-            let line = line_result?;
-            if line.trim().is_empty() || line.starts_with('#') {
-                continue;
+        template.id = id;
+        Ok(template)
+    }
+    
+    // 解析对话文件
+    fn parse_dialogs<P: AsRef<Path>>(&self, path: P) -> Result<DataFile<Dialog>> {
+        self.parse_file(path.as_ref(), |cache| &mut cache.dialogs, Self::read_dialogs)
+    }
+    
+    // 解析对话文件
+    pub fn read_dialogs(reader: &mut TokenReader) -> Result<DataFile<Dialog>> {
+        let version = reader.header("dialogsfile")?;
+        let count = reader.read_count("对话数量")?;
+        
+        let dialogs = Self::read_records(reader, count, Self::read_dialog)?;
+        
+        Ok(DataFile { version, records: dialogs, warnings: reader.take_warnings() })
+    }
+    
+    // 解析单条对话（空文本写作 NO_TEXT，无配音写作 NO_VOICEOVER）
+    fn read_dialog(reader: &mut TokenReader) -> Result<Dialog> {
+        let id = reader.word("id")?.to_string();
+        reader.begin_record(&id);
+        
+        let partner = reader.read_u64("partner")?;
+        let input_state = reader.read_i32("input_state")?;
+        let conditions = Self::read_operations(reader)?;
+        let text = match reader.word("text")? {
+            "NO_TEXT" => String::new(),
+            text => text.replace('_', " "),
+        };
+        let output_state = reader.read_i32("output_state")?;
+        let consequences = Self::read_operations(reader)?;
+        let voice_over = match reader.word("voice_over")? {
+            "NO_VOICEOVER" => None,
+            voice_over => Some(voice_over.to_string()),
+        };
+        
+        Ok(Dialog {
+            id,
+            partner,
+            input_state,
+            conditions,
+            text,
+            output_state,
+            consequences,
+            voice_over,
+        })
+    }
+    
+    // 解析对话状态文件
+    fn parse_dialog_states<P: AsRef<Path>>(&self, path: P) -> Result<DataFile<String>> {
+        self.parse_file(path.as_ref(), |cache| &mut cache.dialog_states, Self::read_dialog_states)
+    }
+    
+    // 解析对话状态文件（无文件头，每行一个状态名，版本号记为 0）
+    pub fn read_dialog_states(reader: &mut TokenReader) -> Result<DataFile<String>> {
+        let mut states = Vec::new();
+        while !reader.is_eof() {
+            states.push(reader.word("state")?.to_string());
+        }
+        Ok(DataFile { version: 0, records: states, warnings: reader.take_warnings() })
+    }
+    
+    // 解析字符串文件
+    fn parse_strings<P: AsRef<Path>>(&self, path: P) -> Result<DataFile<GameString>> {
+        self.parse_file(path.as_ref(), |cache| &mut cache.strings, Self::read_strings)
+    }
+    
+    // 解析字符串文件
+    pub fn read_strings(reader: &mut TokenReader) -> Result<DataFile<GameString>> {
+        let version = reader.header("stringsfile")?;
+        let count = reader.read_count("字符串数量")?;
+        
+        let strings = Self::read_records(reader, count, |reader| {
+            let id = reader.word("id")?.to_string();
+            reader.begin_record(&id);
+            let text = reader.read_name("text")?;
+            Ok(GameString { id, text })
+        })?;
+        
+        Ok(DataFile { version, records: strings, warnings: reader.take_warnings() })
+    }
+    
+    // 解析任务文件
+    fn parse_quests<P: AsRef<Path>>(&self, path: P) -> Result<DataFile<Quest>> {
+        self.parse_file(path.as_ref(), |cache| &mut cache.quests, Self::read_quests)
+    }
+    
+    // 解析任务文件
+    pub fn read_quests(reader: &mut TokenReader) -> Result<DataFile<Quest>> {
+        let version = reader.header("questsfile")?;
+        let count = reader.read_count("任务数量")?;
+        
+        let quests = Self::read_records(reader, count, |reader| {
+            let id = reader.word("id")?.to_string();
+            reader.begin_record(&id);
+            Ok(Quest {
+                id,
+                name: reader.read_name("name")?,
+                flags: reader.read_u64("flags")?,
+                description: reader.read_name("description")?,
+            })
+        })?;
+        
+        Ok(DataFile { version, records: quests, warnings: reader.take_warnings() })
+    }
+    
+    // 解析百科页面文件
+    fn parse_info_pages<P: AsRef<Path>>(&self, path: P) -> Result<DataFile<InfoPage>> {
+        self.parse_file(path.as_ref(), |cache| &mut cache.info_pages, Self::read_info_pages)
+    }
+    
+    // 解析百科页面文件
+    pub fn read_info_pages(reader: &mut TokenReader) -> Result<DataFile<InfoPage>> {
+        let version = reader.header("infopagesfile")?;
+        let count = reader.read_count("页面数量")?;
+        
+        let pages = Self::read_records(reader, count, |reader| {
+            let id = reader.word("id")?.to_string();
+            reader.begin_record(&id);
+            Ok(InfoPage {
+                id,
+                name: reader.read_name("name")?,
+                text: reader.read_name("text")?,
+            })
+        })?;
+        
+        Ok(DataFile { version, records: pages, warnings: reader.take_warnings() })
+    }
+    
+    // 解析技能文件
+    fn parse_skills<P: AsRef<Path>>(&self, path: P) -> Result<DataFile<Skill>> {
+        self.parse_file(path.as_ref(), |cache| &mut cache.skills, Self::read_skills)
+    }
+    
+    // 解析技能文件（无文件头，版本号记为 0）
+    pub fn read_skills(reader: &mut TokenReader) -> Result<DataFile<Skill>> {
+        let count = reader.read_count("技能数量")?;
+        
+        let skills = Self::read_records(reader, count, |reader| {
+            let id = reader.word("id")?.to_string();
+            reader.begin_record(&id);
+            Ok(Skill {
+                id,
+                name: reader.read_name("name")?,
+                flags: reader.read_u64("flags")?,
+                max_level: reader.read_i32("max_level")?,
+                description: reader.read_name("description")?,
+            })
+        })?;
+        
+        Ok(DataFile { version: 0, records: skills, warnings: reader.take_warnings() })
+    }
+    
+    // 解析皮肤文件
+    fn parse_skins<P: AsRef<Path>>(&self, path: P) -> Result<DataFile<Skin>> {
+        self.parse_file(path.as_ref(), |cache| &mut cache.skins, Self::read_skins)
+    }
+    
+    // 解析皮肤文件
+    pub fn read_skins(reader: &mut TokenReader) -> Result<DataFile<Skin>> {
+        let version = reader.header("skins_file")?;
+        let count = reader.read_count("皮肤数量")?;
+        
+        let skins = Self::read_records(reader, count, Self::read_skin)?;
+        
+        Ok(DataFile { version, records: skins, warnings: reader.take_warnings() })
+    }
+    
+    // 解析单个皮肤
+    fn read_skin(reader: &mut TokenReader) -> Result<Skin> {
+        let id = reader.word("id")?.to_string();
+        reader.begin_record(&id);
+        
+        let flags = reader.read_u64("flags")?;
+        let body_mesh = reader.word("body_mesh")?.to_string();
+        let calf_mesh = reader.word("calf_mesh")?.to_string();
+        let hand_mesh = reader.word("hand_mesh")?.to_string();
+        let head_mesh = reader.word("head_mesh")?.to_string();
+        
+        let key_count = reader.read_count("face_keys")?;
+        let mut face_keys = Vec::with_capacity(key_count);
+        for _ in 0..key_count {
+            face_keys.push(SkinFaceKey {
+                id: reader.word("face_key")?.to_string(),
+                key: reader.read_i32("face_key")?,
+                param: reader.read_i32("face_key")?,
+                min: reader.read_f64("face_key")?,
+                max: reader.read_f64("face_key")?,
+                name: reader.read_name("face_key")?,
+            });
+        }
+        
+        let hair_meshes = Self::read_words(reader, "hair_meshes")?;
+        let beard_meshes = Self::read_words(reader, "beard_meshes")?;
+        let hair_textures = Self::read_words(reader, "hair_textures")?;
+        let beard_textures = Self::read_words(reader, "beard_textures")?;
+        
+        let texture_count = reader.read_count("face_textures")?;
+        let mut face_textures = Vec::with_capacity(texture_count);
+        for _ in 0..texture_count {
+            let texture = reader.word("face_texture")?.to_string();
+            let color = reader.read_u64("face_texture")?;
+            let material_count = reader.read_count("hair_materials")?;
+            let color_count = reader.read_count("hair_colors")?;
+            let mut hair_materials = Vec::with_capacity(material_count);
+            for _ in 0..material_count {
+                hair_materials.push(reader.read_name("hair_materials")?);
             }
-            
-            if let Some(faction) = self.parse_faction_line(&line) {
-                factions.push(faction);
+            let mut hair_colors = Vec::with_capacity(color_count);
+            for _ in 0..color_count {
+                hair_colors.push(reader.read_u64("hair_colors")?);
             }
+            face_textures.push(FaceTexture { texture, color, hair_materials, hair_colors });
         }
         
-        // 更新缓存
-        {
-            let mut cache = self.cache.write().unwrap();
-            cache.factions.insert(path_str, factions.clone());
+        let voice_count = reader.read_count("voices")?;
+        let mut voices = Vec::with_capacity(voice_count);
+        for _ in 0..voice_count {
+            voices.push(SkinVoice {
+                kind: reader.read_i32("voices")?,
+                sound: reader.word("voices")?.to_string(),
+            });
         }
-        self.update_timestamp(path);
         
-        Ok(factions)
+        let skeleton = reader.word("skeleton")?.to_string();
+        let scale = reader.read_f64("scale")?;
+        let blood_particles = [reader.read_i32("blood_particles")?, reader.read_i32("blood_particles")?];
+        
+        let constraint_count = reader.read_count("constraints")?;
+        let mut constraints = Vec::with_capacity(constraint_count);
+        for _ in 0..constraint_count {
+            let value = reader.read_f64("constraints")?;
+            let comparison = reader.read_i32("constraints")?;
+            let pair_count = reader.read_count("constraints")?;
+            let mut pairs = Vec::with_capacity(pair_count);
+            for _ in 0..pair_count {
+                pairs.push((reader.read_f64("constraints")?, reader.read_i32("constraints")?));
+            }
+            constraints.push(SkinConstraint { value, comparison, pairs });
+        }
+        
+        Ok(Skin {
+            id,
+            flags,
+            body_mesh,
+            calf_mesh,
+            hand_mesh,
+            head_mesh,
+            face_keys,
+            hair_meshes,
+            beard_meshes,
+            hair_textures,
+            beard_textures,
+            face_textures,
+            voices,
+            skeleton,
+            scale,
+            blood_particles,
+            constraints,
+        })
     }
     
-    // 清空缓存
-    pub fn clear_cache(&self) {
-        let mut cache = self.cache.write().unwrap();
-        cache.items.clear();
-        cache.troops.clear();
-        cache.factions.clear();
-        cache.file_timestamps.clear();
+    // 读取 "数量 + 名称列表"
+    fn read_words(reader: &mut TokenReader, field: &str) -> Result<Vec<String>> {
+        let count = reader.read_count(field)?;
+        (0..count).map(|_| Ok(reader.word(field)?.to_string())).collect()
     }
     
-    // 获取缓存统计信息
-    pub fn cache_stats(&self) -> (usize, usize, usize) {
-        let cache = self.cache.read().unwrap();
-        (cache.items.len(), cache.troops.len(), cache.factions.len())
+    // 解析声音文件
+    fn parse_sounds<P: AsRef<Path>>(&self, path: P) -> Result<DataFile<Sound>> {
+        self.parse_file(path.as_ref(), |cache| &mut cache.sounds, Self::read_sounds)
+    }
+    
+    // 解析声音文件中的全局采样列表
+    fn parse_sound_samples<P: AsRef<Path>>(&self, path: P) -> Result<DataFile<SampleFile>> {
+        self.parse_file(path.as_ref(), |cache| &mut cache.sound_samples, Self::read_sound_samples)
     }
     
-    // 解析单个派系行
-    fn parse_faction_line(&self, line: &str) -> Option<Faction> {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.len() < 3 {
-            return None;
+    // 只读取文件头和全局采样列表
+    pub fn read_sound_samples(reader: &mut TokenReader) -> Result<DataFile<SampleFile>> {
+        let version = reader.header("soundsfile")?;
+        let samples = Self::read_sample_table(reader)?;
+        Ok(DataFile { version, records: samples, warnings: reader.take_warnings() })
+    }
+    
+    fn read_sample_table(reader: &mut TokenReader) -> Result<Vec<SampleFile>> {
+        let count = reader.read_count("采样数量")?;
+        let mut samples = Vec::with_capacity(count);
+        for _ in 0..count {
+            samples.push(SampleFile {
+                file: reader.word("sample")?.to_string(),
+                flags: reader.read_u64("sample_flags")?,
+            });
         }
+        Ok(samples)
+    }
+    
+    // 解析声音文件：先是全局采样列表，再是引用采样编号的声音列表
+    pub fn read_sounds(reader: &mut TokenReader) -> Result<DataFile<Sound>> {
+        let version = reader.header("soundsfile")?;
+        let sample_files: Vec<String> = Self::read_sample_table(reader)?
+            .into_iter()
+            .map(|sample| sample.file)
+            .collect();
+        
+        let count = reader.read_count("声音数量")?;
+        let sounds = Self::read_records(reader, count, |reader| {
+            let id = reader.word("id")?.to_string();
+            reader.begin_record(&id);
+            let flags = reader.read_u64("flags")?;
+            let sample_count = reader.read_count("samples")?;
+            let mut samples = Vec::with_capacity(sample_count);
+            for _ in 0..sample_count {
+                let sample_no = reader.read_i32("samples")?;
+                let variation = reader.read_i32("samples")?;
+                let file = match usize::try_from(sample_no).ok().and_then(|no| sample_files.get(no)) {
+                    Some(file) => file.clone(),
+                    None => {
+                        reader.warn("samples", format!("采样编号越界: {}", sample_no))?;
+                        continue;
+                    }
+                };
+                samples.push(SoundSample { file, variation });
+            }
+            Ok(Sound { id, flags, samples })
+        })?;
+        
+        Ok(DataFile { version, records: sounds, warnings: reader.take_warnings() })
+    }
+    
+    // 解析音乐文件
+    fn parse_music<P: AsRef<Path>>(&self, path: P) -> Result<DataFile<MusicTrack>> {
+        self.parse_file(path.as_ref(), |cache| &mut cache.music, Self::read_music)
+    }
+    
+    // 解析音乐文件（无文件头，版本号记为 0）
+    pub fn read_music(reader: &mut TokenReader) -> Result<DataFile<MusicTrack>> {
+        let count = reader.read_count("音乐数量")?;
+        
+        let tracks = Self::read_records(reader, count, |reader| {
+            let file = reader.word("file")?.to_string();
+            reader.begin_record(&file);
+            let flags = reader.read_u64("flags")?;
+            let continue_flags = reader.read_u64("continue_flags")? & !flags;
+            Ok(MusicTrack { file, flags, continue_flags })
+        })?;
+        
+        Ok(DataFile { version: 0, records: tracks, warnings: reader.take_warnings() })
+    }
+    
+    // 解析场景物品文件
+    fn parse_scene_props<P: AsRef<Path>>(&self, path: P) -> Result<DataFile<SceneProp>> {
+        self.parse_file(path.as_ref(), |cache| &mut cache.scene_props, Self::read_scene_props)
+    }
+    
+    // 解析场景物品文件
+    pub fn read_scene_props(reader: &mut TokenReader) -> Result<DataFile<SceneProp>> {
+        let version = reader.header("scene_propsfile")?;
+        let count = reader.read_count("场景物品数量")?;
         
-        Some(Faction {
-            id: parts[0].to_string(),
-            name: parts[1].replace('_', " "),
-            color: parts[2].to_string(),
-            culture: parts.get(3).unwrap_or(&"Default").to_string(),
+        let props = Self::read_records(reader, count, |reader| {
+            let id = reader.word("id")?.to_string();
+            reader.begin_record(&id);
+            Ok(SceneProp {
+                flags: reader.read_u64("flags")?,
+                hit_points: reader.read_i32("hit_points")?,
+                mesh_name: reader.word("mesh_name")?.to_string(),
+                physics_object: reader.word("physics_object")?.to_string(),
+                triggers: Self::read_simple_triggers(reader)?,
+                id,
+            })
+        })?;
+        
+        Ok(DataFile { version, records: props, warnings: reader.take_warnings() })
+    }
+    
+    // 解析粒子系统文件
+    fn parse_particle_systems<P: AsRef<Path>>(&self, path: P) -> Result<DataFile<ParticleSystem>> {
+        self.parse_file(path.as_ref(), |cache| &mut cache.particle_systems, Self::read_particle_systems)
+    }
+    
+    // 解析粒子系统文件
+    pub fn read_particle_systems(reader: &mut TokenReader) -> Result<DataFile<ParticleSystem>> {
+        let version = reader.header("particle_systemsfile")?;
+        let count = reader.read_count("粒子系统数量")?;
+        
+        let systems = Self::read_records(reader, count, Self::read_particle_system)?;
+        
+        Ok(DataFile { version, records: systems, warnings: reader.take_warnings() })
+    }
+    
+    // 解析单个粒子系统
+    fn read_particle_system(reader: &mut TokenReader) -> Result<ParticleSystem> {
+        let id = reader.word("id")?.to_string();
+        reader.begin_record(&id);
+        
+        let flags = reader.read_u64("flags")?;
+        let mesh_name = reader.word("mesh_name")?.to_string();
+        let particles_per_second = reader.read_i32("particles_per_second")?;
+        let life = reader.read_f64("life")?;
+        let damping = reader.read_f64("damping")?;
+        let gravity_strength = reader.read_f64("gravity_strength")?;
+        let turbulence_size = reader.read_f64("turbulence_size")?;
+        let turbulence_strength = reader.read_f64("turbulence_strength")?;
+        
+        // 每条曲线依次保存为 起点时间 起点数值 终点时间 终点数值
+        let mut curve = |field: &str| -> Result<ParticleCurve> {
+            Ok(ParticleCurve {
+                start: Keyframe { time: reader.read_f64(field)?, value: reader.read_f64(field)? },
+                end: Keyframe { time: reader.read_f64(field)?, value: reader.read_f64(field)? },
+            })
+        };
+        let alpha = curve("alpha")?;
+        let red = curve("red")?;
+        let green = curve("green")?;
+        let blue = curve("blue")?;
+        let scale = curve("scale")?;
+        
+        let emit_box_size = [
+            reader.read_f64("emit_box_size")?,
+            reader.read_f64("emit_box_size")?,
+            reader.read_f64("emit_box_size")?,
+        ];
+        let emit_velocity = [
+            reader.read_f64("emit_velocity")?,
+            reader.read_f64("emit_velocity")?,
+            reader.read_f64("emit_velocity")?,
+        ];
+        
+        Ok(ParticleSystem {
+            id,
+            flags,
+            mesh_name,
+            particles_per_second,
+            life,
+            damping,
+            gravity_strength,
+            turbulence_size,
+            turbulence_strength,
+            alpha,
+            red,
+            green,
+            blue,
+            scale,
+            emit_box_size,
+            emit_velocity,
+            emit_dir_randomness: reader.read_f64("emit_dir_randomness")?,
+            rotation_speed: reader.read_f64("rotation_speed")?,
+            rotation_damping: reader.read_f64("rotation_damping")?,
         })
     }
+    
+    // 解析菜单文件
+    fn parse_menus<P: AsRef<Path>>(&self, path: P) -> Result<DataFile<GameMenu>> {
+        self.parse_file(path.as_ref(), |cache| &mut cache.menus, Self::read_menus)
+    }
+    
+    // 解析菜单文件
+    pub fn read_menus(reader: &mut TokenReader) -> Result<DataFile<GameMenu>> {
+        let version = reader.header("menusfile")?;
+        let count = reader.read_count("菜单数量")?;
+        
+        let menus = Self::read_records(reader, count, Self::read_menu)?;
+        
+        Ok(DataFile { version, records: menus, warnings: reader.take_warnings() })
+    }
+    
+    // 解析单个菜单及其选项
+    fn read_menu(reader: &mut TokenReader) -> Result<GameMenu> {
+        let id = reader.word("id")?.to_string();
+        reader.begin_record(&id);
+        
+        let flags = reader.read_u64("flags")?;
+        let text = reader.read_name("text")?;
+        let mesh_name = reader.word("mesh_name")?.to_string();
+        let operations = Self::read_operations(reader)?;
+        
+        let option_count = reader.read_count("options")?;
+        let mut options = Vec::with_capacity(option_count);
+        for _ in 0..option_count {
+            options.push(GameMenuOption {
+                id: reader.word("option_id")?.to_string(),
+                conditions: Self::read_operations(reader)?,
+                text: reader.read_name("option_text")?,
+                consequences: Self::read_operations(reader)?,
+                door_name: reader.read_name("door_name")?,
+            });
+        }
+        
+        Ok(GameMenu { id, flags, text, mesh_name, operations, options })
+    }
+    
+    // 解析界面文件
+    fn parse_presentations<P: AsRef<Path>>(&self, path: P) -> Result<DataFile<Presentation>> {
+        self.parse_file(path.as_ref(), |cache| &mut cache.presentations, Self::read_presentations)
+    }
+    
+    // 解析界面文件
+    pub fn read_presentations(reader: &mut TokenReader) -> Result<DataFile<Presentation>> {
+        let version = reader.header("presentationsfile")?;
+        let count = reader.read_count("界面数量")?;
+        
+        let presentations = Self::read_records(reader, count, |reader| {
+            let id = reader.word("id")?.to_string();
+            reader.begin_record(&id);
+            Ok(Presentation {
+                flags: reader.read_u64("flags")?,
+                mesh: reader.read_i32("mesh")?,
+                triggers: Self::read_simple_triggers(reader)?,
+                id,
+            })
+        })?;
+        
+        Ok(DataFile { version, records: presentations, warnings: reader.take_warnings() })
+    }
+    
+    // 解析动态贴图材质文件
+    fn parse_tableau_materials<P: AsRef<Path>>(&self, path: P) -> Result<DataFile<TableauMaterial>> {
+        self.parse_file(path.as_ref(), |cache| &mut cache.tableau_materials, Self::read_tableau_materials)
+    }
+    
+    // 解析动态贴图材质文件（无文件头，版本号记为 0）
+    pub fn read_tableau_materials(reader: &mut TokenReader) -> Result<DataFile<TableauMaterial>> {
+        let count = reader.read_count("材质数量")?;
+        
+        let materials = Self::read_records(reader, count, |reader| {
+            let id = reader.word("id")?.to_string();
+            reader.begin_record(&id);
+            Ok(TableauMaterial {
+                flags: reader.read_u64("flags")?,
+                sample_material: reader.word("sample_material")?.to_string(),
+                width: reader.read_i32("width")?,
+                height: reader.read_i32("height")?,
+                mesh_bounds: [
+                    reader.read_i32("mesh_bounds")?,
+                    reader.read_i32("mesh_bounds")?,
+                    reader.read_i32("mesh_bounds")?,
+                    reader.read_i32("mesh_bounds")?,
+                ],
+                operations: Self::read_operations(reader)?,
+                id,
+            })
+        })?;
+        
+        Ok(DataFile { version: 0, records: materials, warnings: reader.take_warnings() })
+    }
+    
+    // 解析后期处理文件
+    fn parse_postfx<P: AsRef<Path>>(&self, path: P) -> Result<DataFile<PostFx>> {
+        self.parse_file(path.as_ref(), |cache| &mut cache.postfx, Self::read_postfx)
+    }
+    
+    // 解析后期处理文件
+    pub fn read_postfx(reader: &mut TokenReader) -> Result<DataFile<PostFx>> {
+        let version = reader.header("postfx_paramsfile")?;
+        let count = reader.read_count("参数数量")?;
+        
+        let postfx = Self::read_records(reader, count, |reader| {
+            let id = reader.word("id")?.to_string();
+            reader.begin_record(&id);
+            let flags = reader.read_u64("flags")?;
+            let tonemap_operator = reader.read_i32("tonemap_operator")?;
+            let mut params = [[0.0; 4]; 3];
+            for group in params.iter_mut() {
+                for value in group.iter_mut() {
+                    *value = reader.read_f64("params")?;
+                }
+            }
+            Ok(PostFx { id, flags, tonemap_operator, params })
+        })?;
+        
+        Ok(DataFile { version, records: postfx, warnings: reader.take_warnings() })
+    }
+    
+    // 解析快速字符串文件
+    fn parse_quick_strings<P: AsRef<Path>>(&self, path: P) -> Result<DataFile<QuickString>> {
+        self.parse_file(path.as_ref(), |cache| &mut cache.quick_strings, Self::read_quick_strings)
+    }
+    
+    // 解析快速字符串文件（无文件头，版本号记为 0）
+    pub fn read_quick_strings(reader: &mut TokenReader) -> Result<DataFile<QuickString>> {
+        let count = reader.read_count("字符串数量")?;
+        
+        let strings = Self::read_records(reader, count, |reader| {
+            let id = reader.word("id")?.to_string();
+            reader.begin_record(&id);
+            let text = reader.read_name("text")?;
+            Ok(QuickString { id, text })
+        })?;
+        
+        Ok(DataFile { version: 0, records: strings, warnings: reader.take_warnings() })
+    }
+    
+    // 解析全局变量文件
+    fn parse_variables<P: AsRef<Path>>(&self, path: P) -> Result<DataFile<String>> {
+        self.parse_file(path.as_ref(), |cache| &mut cache.variables, Self::read_variables)
+    }
+    
+    // 解析全局变量文件（无文件头，每行一个变量名，版本号记为 0）
+    pub fn read_variables(reader: &mut TokenReader) -> Result<DataFile<String>> {
+        let mut variables = Vec::new();
+        while !reader.is_eof() {
+            variables.push(reader.word("variable")?.to_string());
+        }
+        Ok(DataFile { version: 0, records: variables, warnings: reader.take_warnings() })
+    }
+    
+    // 解析动作文件
+    fn parse_actions<P: AsRef<Path>>(&self, path: P) -> Result<DataFile<Action>> {
+        self.parse_file(path.as_ref(), |cache| &mut cache.actions, Self::read_actions)
+    }
+    
+    // 解析动作文件（无文件头，版本号记为 0）
+    pub fn read_actions(reader: &mut TokenReader) -> Result<DataFile<Action>> {
+        let count = reader.read_count("动作数量")?;
+        
+        let actions = Self::read_records(reader, count, Self::read_action)?;
+        
+        Ok(DataFile { version: 0, records: actions, warnings: reader.take_warnings() })
+    }
+    
+    // 解析单个动作，未定义的动作只有 "none 0 0"，没有动画段数量
+    fn read_action(reader: &mut TokenReader) -> Result<Action> {
+        let name = reader.word("name")?.to_string();
+        reader.begin_record(&name);
+        
+        let flags = reader.read_u64("flags")?;
+        let master_flags = reader.read_u64("master_flags")?;
+        if name == Action::UNDEFINED {
+            return Ok(Action { name, flags, master_flags, sequences: Vec::new() });
+        }
+        
+        let count = reader.read_count("sequences")?;
+        let mut sequences = Vec::with_capacity(count);
+        for _ in 0..count {
+            sequences.push(ActionSequence {
+                duration: reader.read_f64("duration")?,
+                resource: reader.word("resource")?.to_string(),
+                start_frame: reader.read_i32("start_frame")?,
+                end_frame: reader.read_i32("end_frame")?,
+                flags: reader.read_u64("flags")?,
+                extra_flags: reader.read_u64("extra_flags")?,
+                position: [
+                    reader.read_f64("position")?,
+                    reader.read_f64("position")?,
+                    reader.read_f64("position")?,
+                ],
+                blend_in: reader.read_f64("blend_in")?,
+            });
+        }
+        
+        Ok(Action { name, flags, master_flags, sequences })
+    }
+    
+    // 解析标签使用文件
+    fn parse_tag_uses<P: AsRef<Path>>(&self, path: P) -> Result<DataFile<TagUse>> {
+        self.parse_file(path.as_ref(), |cache| &mut cache.tag_uses, Self::read_tag_uses)
+    }
+    
+    // 解析标签使用文件：记录以分号结尾，中间没有空白分隔
+    pub fn read_tag_uses(reader: &mut TokenReader) -> Result<DataFile<TagUse>> {
+        let mut text = String::new();
+        while !reader.is_eof() {
+            text.push_str(reader.word("tag_uses")?);
+            text.push(' ');
+        }
+        
+        let mut tag_uses = Vec::new();
+        for record in text.split(';').map(str::trim).filter(|record| !record.is_empty()) {
+            // 任一值无法解析时跳过整条记录并记录问题，不丢掉坏值继续拼出记录
+            let values: Result<Vec<i32>, &str> = record.split_whitespace()
+                .map(|value| value.parse().map_err(|_| value))
+                .collect();
+            match values.as_deref() {
+                Ok(&[tag, index, uses]) => tag_uses.push(TagUse { tag, index, uses }),
+                Ok(_) => reader.warn("tag_uses", format!("记录无效: {}", record))?,
+                Err(value) => reader.warn("tag_uses", format!("值无效: {}（记录: {}）", value, record))?,
+            }
+        }
+        
+        Ok(DataFile { version: 0, records: tag_uses, warnings: reader.take_warnings() })
+    }
+    
+    // 清空缓存
+    pub fn clear_cache(&self) {
+        self.cache.write().unwrap().clear();
+    }
+    
+    // 获取缓存统计信息（数据类型, 已缓存文件数）
+    pub fn cache_stats(&self) -> Vec<(&'static str, usize)> {
+        self.cache.read().unwrap().stats()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::test_util::TempDir;
+
+    const ITEMS: &str = "itemsfile version 3
+2
+ itm_no_item INVALID_ITEM INVALID_ITEM 1  invalid_item 0  0 0 1 0 3.000000 0 0 0 0 0 0 100 0 0 0 0 0
+ 0
+0
+
+ itm_torch Torch Torches 2  club 0  torch_fire 1152921504606846976  2 2 11 0 2.500000 100 0 0 0 0 0 95 0 95 0 11 523
+ 2
+ 3 4
+1
+-50.000000  2 1720 3 0 0 0 1971 1 1 
+";
+
+    #[test]
+    fn test_read_troops() {
+        let slots = format!("217 0 394 4 {}", "-1 0 ".repeat(62));
+        let content = format!(
+            "troopsfile version 2\n1 \ntrp_swadian_footman Swadian_Footman Swadian_Footmen 0 19922944 0 0 4 19 0\n  {}\n  13 12 4 4 10\n 120 95 95 40 40 40 0\n293601348 0 0 0 0 0 \n  0 0 0 0 0 0 0 0 \n",
+            slots
+        );
+        let file = Parser::read_troops(&mut TokenReader::new(&content)).unwrap();
+        assert_eq!(file.version, 2);
+
+        let troop = &file.records[0];
+        assert_eq!(troop.name, "Swadian Footman");
+        assert_eq!(troop.faction, 4);
+        assert_eq!(troop.upgrade_1, 19);
+        assert_eq!(troop.troop_class(), "cavalry");
+        assert_eq!(troop.inventory.len(), TROOP_INVENTORY_SLOTS);
+        assert_eq!(troop.inventory[1], TroopItem { item: 394, modifier: 4 });
+        assert_eq!(troop.equipped_items().count(), 2);
+        assert_eq!((troop.strength, troop.agility, troop.level), (13, 12, 10));
+        assert_eq!(troop.proficiencies[0], 120);
+        assert_eq!(troop.skill(0), 4);
+        assert_eq!(troop.skill(6), 1);
+    }
+
+    #[test]
+    fn test_read_items() {
+        let file = Parser::read_items(&mut TokenReader::new(ITEMS), GameEdition::Warband).unwrap();
+        assert_eq!(file.version, 3);
+        assert_eq!(file.records.len(), 2);
+
+        let torch = &file.records[1];
+        assert_eq!(torch.id, "itm_torch");
+        assert_eq!(torch.plural_name, "Torches");
+        assert_eq!(torch.meshes[1].modifier_bits, 0x1000000000000000);
+        assert_eq!(torch.item_type(), ItemType::OneHandedWeapon);
+        assert_eq!(torch.price, 11);
+        assert_eq!(torch.weight, 2.5);
+        assert_eq!(torch.speed_rating, 95);
+        assert_eq!(torch.weapon_length, 95);
+        assert_eq!(torch.damage(), 11);
+        assert_eq!(damage_type(torch.swing_damage), DamageType::Blunt);
+        assert_eq!(torch.factions, vec![3, 4]);
+        assert_eq!(torch.triggers[0].interval, -50.0);
+        assert_eq!(torch.triggers[0].operations[1], Operation { opcode: 1971, operands: vec![1] });
+    }
+
+    #[test]
+    fn test_read_items_truncated() {
+        let content = "itemsfile version 3\n2\n itm_a A A 0  2 x 11 0 1.0 0 0 0 0 0 0 0 0 0 0 0 0 0 0\n itm_b B B 0  2 2 11";
+
+        let mut reader = TokenReader::with_file("item_kinds1.txt", content).with_mode(ParseMode::Strict);
+        let err = Parser::read_items(&mut reader, GameEdition::Warband).unwrap_err();
+        assert_eq!(err.to_string(), "item_kinds1.txt:3:17: [itm_a] 字段 capabilities: 值无效: x");
+
+        let file = Parser::read_items(&mut TokenReader::with_file("item_kinds1.txt", content), GameEdition::Warband).unwrap();
+        assert_eq!(file.records.len(), 1);
+        assert_eq!(file.records[0].capabilities, 0);
+        let fields: Vec<_> = file.warnings.iter().map(|w| w.field.as_str()).collect();
+        assert_eq!(fields, vec!["capabilities", "modifier_bits", "记录数量"]);
+        assert_eq!(file.warnings[1].record.as_deref(), Some("itm_b"));
+    }
+
+    #[test]
+    fn test_read_factions() {
+        let content = "factionsfile version 1\n2\nfac_commoners Commoners 0 11184810 \n 0.000000 -0.300000\n0 fac_kingdom_1 Kingdom_of_Swadia 256 14518340 \n -0.300000 1.000000\n2  Knight  Count_of_Swadia ";
+        let file = Parser::read_factions(&mut TokenReader::new(content)).unwrap();
+        assert_eq!(file.records.len(), 2);
+
+        let swadia = &file.records[1];
+        assert_eq!(swadia.name, "Kingdom of Swadia");
+        assert_eq!(swadia.coherence, None);
+        assert_eq!(swadia.flags.max_player_rating(), 1);
+        assert_eq!(swadia.color_hex(), "#DD8844");
+        assert_eq!(swadia.relation_with(0), -0.3);
+        assert_eq!(swadia.ranks, vec!["Knight", "Count of Swadia"]);
+
+        let with_coherence = "factionsfile version 1\n1\nfac_player Player 0 0.900000 16777215 \n 1.000000\n0 ";
+        let file = Parser::read_factions(&mut TokenReader::new(with_coherence)).unwrap();
+        assert_eq!(file.records[0].coherence, Some(0.9));
+        assert_eq!(file.records[0].color, 0xFFFFFF);
+    }
+
+    #[test]
+    fn test_read_parties() {
+        let content = "partiesfile version 1\n2 2\n1 0 0 p_main_party Main_Party 330 0 0 1 0 0 0 0 0 17.000000 52.500000 17.000000 52.500000 17.000000 52.500000 0.0 1 0 1 0 0 \n0.000000\n1 1 1 p_town_1 Sargoth 9476 5 0 4 0 0 0 0 0 -17.600000 -19.100000 -17.600000 -19.100000 -17.600000 -19.100000 0.0 2 12 30 0 0 7 2 0 1 \n3.001966\n";
+        let file = Parser::read_parties(&mut TokenReader::new(content)).unwrap();
+        assert!(file.warnings.is_empty());
+        assert_eq!(file.records.len(), 2);
+
+        let town = &file.records[1];
+        assert_eq!(town.id, "p_town_1");
+        assert_eq!(town.party_kind(), "town");
+        assert_eq!(town.icon(), 4);
+        assert_eq!(town.faction, 4);
+        assert_eq!(town.position, [-17.6, -19.1]);
+        assert_eq!(town.stacks[1], PartyStack { troop: 7, count: 2, flags: 1 });
+        assert_eq!(town.troop_count(), 30);
+    }
+
+    #[test]
+    fn test_read_party_templates() {
+        let content = "partytemplatesfile version 1\n1\npt_steppe_bandits Steppe_Bandits 8396872 0 11 1 15 4 18 0 -1 -1 -1 -1 -1 \n";
+        let file = Parser::read_party_templates(&mut TokenReader::new(content)).unwrap();
+        let template = &file.records[0];
+        assert_eq!(template.name, "Steppe Bandits");
+        assert_eq!(template.faction, 11);
+        assert_eq!(template.stacks, vec![PartyTemplateStack { troop: 15, min: 4, max: 18, flags: 0 }]);
+
+        let mut broken = template.clone();
+        broken.stacks.push(PartyTemplateStack { troop: 99, min: 5, max: 2, flags: 0 });
+        assert_eq!(
+            broken.validate(20),
+            vec![
+                TemplateIssue::MissingTroop { stack: 1, troop: 99 },
+                TemplateIssue::InvalidRange { stack: 1, min: 5, max: 2 },
+            ]
+        );
+        assert!(template.validate(20).is_empty());
+    }
+
+    #[test]
+    fn test_read_scenes() {
+        let content = "scenesfile version 1\n 2\nscn_random_scene random_scene 768 none none 0.000000 0.000000 240.000000 240.000000 -0.500000 0x300028000003e8fa0000034e00004b34000059be \n  0 \n  0 \n 0 \nscn_town_1_center town_1_center 0 none none 0.000000 0.000000 100.000000 100.000000 -100.000000 0 \n  2  100000  3 \n  1  160 \n outer_terrain_town_1 \n";
+        let file = Parser::read_scenes(&mut TokenReader::new(content)).unwrap();
+        assert_eq!(file.records.len(), 2);
+        assert!(file.records[0].is_generated());
+        assert_eq!(file.records[0].terrain_code, "0x300028000003e8fa0000034e00004b34000059be");
+
+        let town = &file.records[1];
+        assert_eq!(town.name, "town 1 center");
+        assert_eq!(town.max_pos, [100.0, 100.0]);
+        assert_eq!(town.passages, vec![SCENE_PASSAGE_EXIT, 3]);
+        assert_eq!(town.chest_troops, vec![160]);
+        assert_eq!(town.outer_terrain, "outer_terrain_town_1");
+    }
+
+    #[test]
+    fn test_read_map_icons() {
+        let content = "map_icons_file version 1\n2\nplayer 0 player 0.150000 0 0.000000 0.000000 0.000000 0\n\n\ntown 1 map_town_a 0.350000 0 0.000000 -0.600000 0.000000 1\n-100.000000  1 1 1 1 \n\n\n";
+        let file = Parser::read_map_icons(&mut TokenReader::new(content)).unwrap();
+        assert_eq!(file.records.len(), 2);
+
+        let town = &file.records[1];
+        assert_eq!(town.mesh_name, "map_town_a");
+        assert_eq!(town.scale, 0.35);
+        assert_eq!(town.offset, [0.0, -0.6, 0.0]);
+        assert_eq!(town.triggers[0].interval, -100.0);
+
+        let party = Party { flags: 0x2501, ..Default::default() };
+        assert_eq!(party.icon_name(&file.records), Some("town"));
+    }
+
+    #[test]
+    fn test_read_scripts() {
+        let content = "scriptsfile version 1\n2\ngame_start -1\n 2 2133 2 144115188075855885 0 1 1 936748722493063168 \ngame_quick_start 1.000000\n 0 \n";
+        let file = Parser::read_scripts(&mut TokenReader::new(content)).unwrap();
+        assert_eq!(file.records.len(), 2);
+
+        let game_start = &file.records[0];
+        assert_eq!(game_start.name, "game_start");
+        assert_eq!(game_start.flags, None);
+        assert_eq!(game_start.operations[0], Operation { opcode: 2133, operands: vec![144115188075855885, 0] });
+        assert_eq!(game_start.operations[1].operands, vec![936748722493063168]);
+        assert_eq!(file.records[1].flags, Some(1.0));
+    }
+
+    #[test]
+    fn test_read_triggers() {
+        let content = "simple_triggers_file version 1\n2\n0.000000  1 2133 2 144115188075855885 0 \n24.000000  0 \n";
+        let file = Parser::read_simple_triggers_file(&mut TokenReader::new(content)).unwrap();
+        assert_eq!(file.records.len(), 2);
+        assert_eq!(file.records[1].interval, 24.0);
+
+        let content = "triggersfile version 1\n1\n1.000000 0.000000 100000000.000000  1 31 2 1 2  1 1 1 3 \n";
+        let file = Parser::read_triggers_file(&mut TokenReader::new(content)).unwrap();
+        let trigger = &file.records[0];
+        assert_eq!(trigger.rearm_interval, 100000000.0);
+        assert_eq!(trigger.conditions, vec![Operation { opcode: 31, operands: vec![1, 2] }]);
+        assert_eq!(trigger.consequences, vec![Operation { opcode: 1, operands: vec![3] }]);
+    }
+
+    #[test]
+    fn test_read_mission_templates() {
+        let content = "missionsfile version 1\n 1\nmst_town_default town_default 0  4\nDefault_town_visit \n\n2 2 0 65536 0 1 0  \n1 128 0 0 1 2  213 220 \n1\n-5.000000 0.000000 100000000.000000  1 31 2 1 2  0 \n\n\n";
+        let file = Parser::read_mission_templates(&mut TokenReader::new(content)).unwrap();
+        assert!(file.warnings.is_empty());
+
+        let template = &file.records[0];
+        assert_eq!(template.id, "mst_town_default");
+        assert_eq!(template.name, "town_default");
+        assert_eq!(template.mission_type, 4);
+        assert_eq!(template.description, "Default town visit");
+        assert_eq!(template.spawns.len(), 2);
+        assert_eq!(template.spawns[0].alter_flags, 0x10000);
+        assert_eq!(template.spawns_at(1).next().unwrap().item_overrides, vec![213, 220]);
+        assert_eq!(template.triggers[0].check_interval, -5.0);
+    }
+
+    #[test]
+    fn test_read_dialogs() {
+        let content = "dialogsfile version 2\n2\ndlga_start:close_window 69631 0  1 2133 2 1 2 Surrender_or_die!  6  0 NO_VOICEOVER \ndlga_member_chat:member_chat_2 4095 13  0  NO_TEXT  16  0 voice_01 \n";
+        let file = Parser::read_dialogs(&mut TokenReader::new(content)).unwrap();
+        assert_eq!(file.records.len(), 2);
+
+        let surrender = &file.records[0];
+        assert!(surrender.is_player_line());
+        assert_eq!(surrender.partner_troop(), None);
+        assert_eq!(surrender.text, "Surrender or die!");
+        assert_eq!(surrender.conditions.len(), 1);
+        assert_eq!(surrender.output_state, 6);
+        assert_eq!(surrender.voice_over, None);
+
+        let chat = &file.records[1];
+        assert_eq!(chat.text, "");
+        assert_eq!((chat.input_state, chat.output_state), (13, 16));
+        assert_eq!(chat.voice_over.as_deref(), Some("voice_01"));
+    }
+
+    #[test]
+    fn test_read_texts() {
+        let strings = "stringsfile version 1\n2\nstr_no_string NO_STRING!\nstr_hello Hello,_{s1}!\n";
+        let file = Parser::read_strings(&mut TokenReader::new(strings)).unwrap();
+        assert_eq!(file.records[1], GameString { id: "str_hello".into(), text: "Hello, {s1}!".into() });
+
+        let quests = "questsfile version 1\n1\nqst_deliver_message Deliver_Message_to_{s13} 3 {!}Lord_{s9}_wants_you. \n";
+        let file = Parser::read_quests(&mut TokenReader::new(quests)).unwrap();
+        assert!(file.records[0].is_random());
+        assert_eq!(file.records[0].name, "Deliver Message to {s13}");
+        assert_eq!(file.records[0].description, "{!}Lord {s9} wants you.");
+
+        let pages = "infopagesfile version 1\n1\nip_morale Party_Morale Morale_is_important.\n";
+        let file = Parser::read_info_pages(&mut TokenReader::new(pages)).unwrap();
+        assert_eq!(file.records[0].text, "Morale is important.");
+    }
+
+    #[test]
+    fn test_read_skills_and_skins() {
+        let skills = "2\nskl_trade Trade 19 10 Every_level_of_this_skill_reduces_your_trade_penalty_by_5%.\nskl_reserved_1 Reserved_Skill_1 257 10 This_is_a_reserved_skill.\n";
+        let file = Parser::read_skills(&mut TokenReader::new(skills)).unwrap();
+        assert_eq!(file.records.len(), 2);
+        assert_eq!(file.records[0].base_attribute(), "charisma");
+        assert!(file.records[0].is_party_skill());
+        assert!(!file.records[1].is_active());
+
+        let content = concat!(
+            "skins_file version 1\n1\nman 0\n man_body man_calf_l m_handL\n",
+            " male_head 1 skinkey_chin_size 20 0 0.700000 -0.600000 Chin_Size \n",
+            "2\n man_hair_s  man_hair_m \n 1\n  beard_e\n\n",
+            " 1  hair_blonde \n 1  beard_blonde \n",
+            " 1  manface_young_2 4291551456 1 1  hair_blonde  4294967295 \n",
+            " 2  0 snd_man_die  1 snd_man_hit \n",
+            " skel_human 1.000000 \n0 0\n1\n\n1.700000 -1 2  1.000000 42 1.000000 46\n",
+        );
+        let file = Parser::read_skins(&mut TokenReader::new(content)).unwrap();
+        let skin = &file.records[0];
+        assert_eq!(skin.face_keys[0].name, "Chin Size");
+        assert_eq!(skin.hair_meshes, vec!["man_hair_s", "man_hair_m"]);
+        assert_eq!(skin.face_textures[0].hair_colors, vec![0xffffffff]);
+        assert_eq!(skin.voices[1].kind_name(), "hit");
+        assert_eq!(skin.constraints[0].pairs, vec![(1.0, 42), (1.0, 46)]);
+    }
+
+    #[test]
+    fn test_read_sounds_and_music() {
+        let sounds = "soundsfile version 3\n2\n click.wav 1\n drum.ogg 2\n2\nsnd_click 1 1 0 0 \nsnd_drums 2 2 1 0 0 0 \n";
+        let file = Parser::read_sounds(&mut TokenReader::new(sounds)).unwrap();
+        assert_eq!(file.records[1].samples.iter().map(|s| s.file.as_str()).collect::<Vec<_>>(), vec!["drum.ogg", "click.wav"]);
+        assert_eq!(file.records[1].flag_names(), vec!["sf_looping"]);
+
+        let music = "2\ncalm_night_2.ogg 532480 532480\nfight_1.ogg 268436544 268436544\n";
+        let file = Parser::read_music(&mut TokenReader::new(music)).unwrap();
+        assert_eq!(file.records[0].flag_names(), vec!["mtf_sit_town", "mtf_sit_night"]);
+        assert!(file.records[1].flag_names().contains(&"mtf_module_track".to_string()));
+    }
+
+    #[test]
+    fn test_read_scene_props_and_particles() {
+        let props = "scene_propsfile version 1\n 1\nspr_barrier_box 8 0 barrier_box bo_barrier_box 0\n\n\n";
+        let file = Parser::read_scene_props(&mut TokenReader::new(props)).unwrap();
+        assert_eq!(file.records[0].physics_object, "bo_barrier_box");
+        assert!(file.records[0].has_physics());
+
+        let content = concat!(
+            "particle_systemsfile version 1\n1\n",
+            "psys_torch_fire 3 prt_mesh_fire_1  50 0.350000 0.200000 0.030000 10.000000 0.000000 \n",
+            "0.500000 0.800000 1.000000 0.000000\n0.500000 1.000000 1.000000 0.900000\n",
+            "0.500000 0.700000 1.000000 0.300000\n0.500000 0.200000 1.000000 0.000000\n",
+            "0.000000 0.150000 0.400000 0.400000\n",
+            "0.040000 0.040000 0.010000   0.000000 0.000000 0.500000   0.000000 \n",
+            "200.000000 0.500000 \n\n",
+        );
+        let file = Parser::read_particle_systems(&mut TokenReader::new(content)).unwrap();
+        let fire = &file.records[0];
+        assert_eq!(fire.particles_per_second, 50);
+        assert_eq!(fire.alpha.start, Keyframe { time: 0.5, value: 0.8 });
+        assert!((fire.scale.value_at(0.2) - 0.275).abs() < 1e-6);
+        assert_eq!(fire.emit_velocity, [0.0, 0.0, 0.5]);
+        assert_eq!(fire.rotation_speed, 200.0);
+    }
+
+    #[test]
+    fn test_read_menus_and_presentations() {
+        let menus = concat!(
+            "menusfile version 1\n 2\n",
+            "menu_start_game_0 0 Welcome_to_the_game none 1 2133 2 144115188075855885 0 2\n",
+            " mno_continue  0  Continue...  1 2060 1 144115188075855873  .  mno_go_back  0  Go_back  0  . \n",
+            "menu_town 16 {s10} town_mesh 0 0\n\n",
+        );
+        let file = Parser::read_menus(&mut TokenReader::new(menus)).unwrap();
+        let start = &file.records[0];
+        assert_eq!(start.text, "Welcome to the game");
+        assert_eq!(start.operations.len(), 1);
+        assert_eq!(start.options.len(), 2);
+        assert_eq!(start.options[0].consequences[0].opcode, 2060);
+        assert_eq!(start.options[1].text, "Go back");
+        assert_eq!(start.options[1].door_name, ".");
+        assert!(file.records[1].options.is_empty());
+
+        let presentations = "presentationsfile version 1\n 1\nprsnt_game_credits 2 0 1\n-60.000000  1 2133 2 1 2 \n\n\n";
+        let file = Parser::read_presentations(&mut TokenReader::new(presentations)).unwrap();
+        assert_eq!(file.records[0].flags, Presentation::PRSNTF_MANUAL_END_ONLY);
+        assert_eq!(file.records[0].triggers[0].interval, -60.0);
+    }
+
+    #[test]
+    fn test_read_remaining_module_files() {
+        let tableaus = "1\ntab_game_character_sheet 0 tableau_with_transparency 1024 1024 0 0 266 532 1 1116 1 720575940379279363 \n";
+        let file = Parser::read_tableau_materials(&mut TokenReader::new(tableaus)).unwrap();
+        assert_eq!(file.records[0].mesh_bounds, [0, 0, 266, 532]);
+        assert_eq!(file.records[0].operations.len(), 1);
+
+        let postfx = "postfx_paramsfile version 1\n1\npfx_default 0 1  0.030000 1.000000 0.800000 1.000000  0.650000 0.750000 0.600000 0.000000  1.000000 1.000000 1.000000 1.000000\n";
+        let file = Parser::read_postfx(&mut TokenReader::new(postfx)).unwrap();
+        assert_eq!(file.records[0].params[1], [0.65, 0.75, 0.6, 0.0]);
+
+        let actions = "3\n stand 0 0  2\n  3.000000 stand_man 0 30 1 0 0.000000 0.000000 0.000000  0.000000 \n  1.500000 stand_woman 0 15 0 5 0.000000 0.100000 0.000000  0.200000 \n none 0 0\n jump 2 16  0\n";
+        let file = Parser::read_actions(&mut TokenReader::new(actions)).unwrap();
+        assert_eq!(file.records.len(), 3);
+        assert_eq!(file.records[0].sequences[1].extra_flags, 5);
+        assert!(!file.records[1].is_defined());
+        assert_eq!(file.records[2].master_flags, 16);
+
+        let file = Parser::read_tag_uses(&mut TokenReader::new("1 0 5;1 1 3;12 4 2;\n")).unwrap();
+        assert_eq!(file.records[2], TagUse { tag: 12, index: 4, uses: 2 });
+        // 含坏值的记录不会被拼成其他记录
+        let file = Parser::read_tag_uses(&mut TokenReader::new("1 0 5;1 2 x 3;\n")).unwrap();
+        assert_eq!(file.records.len(), 1);
+        assert!(file.warnings[0].message.contains("x"));
+        assert!(Parser::read_tag_uses(&mut TokenReader::new("1 2 x 3;").with_mode(ParseMode::Strict)).is_err());
+    }
+
+    #[test]
+    fn test_unknown_files_are_kept() {
+        let dir = TempDir::new("unknown_files");
+        dir.write("troops.txt", "troopsfile version 2\n0 ");
+        dir.write("variable_uses.txt", b"1\n2\n");
+        dir.write("module.ini", "");
+
+        let known = HashMap::from([("troops.txt".to_string(), 2)]);
+        let files = Parser::read_unknown_files(dir.path(), &known).unwrap();
+        assert_eq!(files.keys().collect::<Vec<_>>(), vec!["variable_uses.txt"]);
+        assert_eq!(files["variable_uses.txt"], b"1\n2\n");
+    }
+
+    #[test]
+    fn test_parse_partial_module() {
+        let dir = TempDir::new("partial_module");
+        dir.write("item_kinds1.txt", ITEMS);
+        dir.write("troops.txt", "troopsfile version 2\n0 ");
+        dir.write("factions.txt", "factionsfile version 1\n0\n");
+
+        let mut data = Parser::new().parse_game_data(dir.path(), GameEdition::Warband).unwrap();
+        assert_eq!(data.items.len(), 2);
+        assert!(data.troops.is_empty() && data.parties.is_empty());
+        assert_eq!(data.missing_files.len(), 27);
+        assert!(data.missing_files.contains("parties.txt"));
+        assert!(!data.missing_files.contains("factions.txt"));
+        assert_eq!(data.warnings.len(), 27);
+        assert!(data.warnings[0].file.ends_with("parties.txt"));
+
+        // 未修改的缺失文件不会被创建，修改后才写入
+        crate::data::Writer::save_game_data(&mut data, dir.path()).unwrap();
+        data.mark_changed("parties.txt");
+        crate::data::Writer::save_game_data(&mut data, dir.path()).unwrap();
+        assert!(!data.missing_files.contains("parties.txt"));
+        let mut names: Vec<String> = std::fs::read_dir(dir.path()).unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["factions.txt", "item_kinds1.txt", "parties.txt", "troops.txt"]);
+    }
+
+    #[test]
+    fn test_read_items_without_factions() {
+        let content = "itemsfile version 2\n2\n itm_a A A 0  2 0 11 0 1.000000 0 0 0 0 0 0 0 0 0 0 0 0\n0\n\n itm_b B B 0  2 0 12 0 1.000000 0 0 0 0 0 0 0 0 0 0 0 0\n0\n\n";
+        let file = Parser::read_items(&mut TokenReader::new(content), GameEdition::MountAndBlade).unwrap();
+        assert_eq!(file.records.len(), 2);
+        assert_eq!(file.records[1].price, 12);
+        assert!(file.records[1].factions.is_empty());
+        assert!(file.warnings.is_empty());
+        assert_eq!(crate::data::Writer::write_items(file.version, &file.records).unwrap(), content);
+    }
+
+    #[test]
+    fn test_parse_items_by_edition() {
+        let dir = TempDir::new("items_by_edition");
+        dir.write("item_kinds1.txt", ITEMS);
+        let parser = Parser::new();
+
+        let warband = parser.parse_game_data(dir.path(), GameEdition::Warband).unwrap();
+        assert_eq!(warband.edition, GameEdition::Warband);
+        assert!(warband.lossy_files.is_empty());
+
+        // 同一文件按初代读取：文件头与版本不符，按文件头格式读取并记录问题，不允许保存
+        let original = parser.parse_game_data(dir.path(), GameEdition::MountAndBlade).unwrap();
+        assert_eq!(original.edition, GameEdition::MountAndBlade);
+        assert_eq!(original.items.len(), warband.items.len());
+        assert_eq!(original.items[0].factions, warband.items[0].factions);
+        assert!(original.lossy_files.contains("item_kinds1.txt"));
+        assert!(original.warnings.iter().any(|warning| warning.message.contains("version 2")));
+
+        let strict = Parser::read_items(&mut TokenReader::new(ITEMS).with_mode(ParseMode::Strict), GameEdition::MountAndBlade);
+        assert!(strict.is_err());
+    }
 }
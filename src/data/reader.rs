@@ -0,0 +1,300 @@
+// 带位置信息的词法读取器，对应原版编辑器的 GetWord()
+
+use anyhow::Result;
+use std::fmt;
+use std::str::FromStr;
+use super::diagnostics::{ParseError, ParseMode, ParseWarning};
+
+// 文本中的位置（行列均从 1 开始）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+// 以空白分隔的流式读取器，可跨行读取记录
+pub struct TokenReader<'a> {
+    file: String,
+    content: &'a str,
+    offset: usize,
+    line: usize,
+    column: usize,
+    // 最近一次读取的词的起始位置
+    last: Position,
+    mode: ParseMode,
+    // 当前正在读取的记录 id
+    record: Option<String>,
+    warnings: Vec<ParseWarning>,
+}
+
+impl<'a> TokenReader<'a> {
+    pub fn new(content: &'a str) -> Self {
+        Self::with_file("<内存>", content)
+    }
+
+    pub fn with_file(file: impl Into<String>, content: &'a str) -> Self {
+        Self {
+            file: file.into(),
+            content,
+            offset: 0,
+            line: 1,
+            column: 1,
+            last: Position { line: 1, column: 1 },
+            mode: ParseMode::default(),
+            record: None,
+            warnings: Vec::new(),
+        }
+    }
+
+    pub fn with_mode(mut self, mode: ParseMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn mode(&self) -> ParseMode {
+        self.mode
+    }
+
+    // 开始读取一条记录，之后的诊断信息都带上该记录 id
+    pub fn begin_record(&mut self, id: &str) {
+        self.record = Some(id.to_string());
+    }
+
+    // 取出已收集的警告
+    pub fn take_warnings(&mut self) -> Vec<ParseWarning> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    // 来源文件名
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    // 最近一次读取的词的位置
+    pub fn position(&self) -> Position {
+        self.last
+    }
+
+    // 下一个词的位置
+    pub fn next_position(&mut self) -> Position {
+        self.skip_whitespace();
+        Position { line: self.line, column: self.column }
+    }
+
+    // 是否已读到文件末尾（忽略尾部空白）
+    pub fn is_eof(&mut self) -> bool {
+        self.skip_whitespace();
+        self.offset >= self.content.len()
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.content[self.offset..];
+        for (index, ch) in rest.char_indices() {
+            if !ch.is_whitespace() {
+                self.offset += index;
+                return;
+            }
+            self.advance(ch);
+        }
+        self.offset = self.content.len();
+    }
+
+    fn advance(&mut self, ch: char) {
+        if ch == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+    }
+
+    // 在最近读取的位置生成诊断
+    pub fn diagnostic(&self, field: &str, message: impl fmt::Display) -> ParseError {
+        ParseError {
+            file: self.file.clone(),
+            line: self.last.line,
+            column: self.last.column,
+            record: self.record.clone(),
+            field: field.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    // 带位置的错误
+    pub fn error(&self, field: &str, message: impl fmt::Display) -> anyhow::Error {
+        self.diagnostic(field, message).into()
+    }
+
+    // 记录一个问题：宽松模式下收集为警告，严格模式下返回错误
+    pub fn warn(&mut self, field: &str, message: impl fmt::Display) -> Result<()> {
+        let diagnostic = self.diagnostic(field, message);
+        match self.mode {
+            ParseMode::Lenient => {
+                self.warnings.push(diagnostic.into());
+                Ok(())
+            }
+            ParseMode::Strict => Err(diagnostic.into()),
+        }
+    }
+
+    // 宽松模式下把解析错误转为警告并返回 None，其他错误照常返回
+    pub fn recover<T>(&mut self, result: Result<T>) -> Result<Option<T>> {
+        match result {
+            Ok(value) => Ok(Some(value)),
+            Err(e) if self.mode == ParseMode::Lenient => match e.downcast::<ParseError>() {
+                Ok(error) => {
+                    self.warnings.push(error.into());
+                    Ok(None)
+                }
+                Err(e) => Err(e),
+            },
+            Err(e) => Err(e),
+        }
+    }
+
+    // 查看下一个词但不消耗
+    pub fn peek(&mut self) -> Option<&'a str> {
+        self.skip_whitespace();
+        let rest = &self.content[self.offset..];
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        if end == 0 {
+            None
+        } else {
+            Some(&rest[..end])
+        }
+    }
+
+    // 读取下一个词
+    pub fn word(&mut self, field: &str) -> Result<&'a str> {
+        self.skip_whitespace();
+        self.last = Position { line: self.line, column: self.column };
+
+        let rest = &self.content[self.offset..];
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        if end == 0 {
+            return Err(self.error(field, "文件意外结束"));
+        }
+
+        let word = &rest[..end];
+        self.offset += end;
+        self.column += word.chars().count();
+        Ok(word)
+    }
+
+    // 读取并解析下一个词，宽松模式下无效值记为警告并使用默认值
+    pub fn parse<T: FromStr + Default>(&mut self, field: &str) -> Result<T> {
+        let word = self.word(field)?;
+        match word.parse() {
+            Ok(value) => Ok(value),
+            Err(_) => {
+                self.warn(field, format!("值无效: {}", word))?;
+                Ok(T::default())
+            }
+        }
+    }
+
+    pub fn read_i32(&mut self, field: &str) -> Result<i32> {
+        self.parse(field)
+    }
+
+    pub fn read_i64(&mut self, field: &str) -> Result<i64> {
+        self.parse(field)
+    }
+
+    pub fn read_u64(&mut self, field: &str) -> Result<u64> {
+        self.parse(field)
+    }
+
+    pub fn read_f64(&mut self, field: &str) -> Result<f64> {
+        self.parse(field)
+    }
+
+    // 读取数量字段，数量错误时后续内容无法对齐，两种模式下都视为错误
+    pub fn read_count(&mut self, field: &str) -> Result<usize> {
+        let word = self.word(field)?;
+        word.parse()
+            .map_err(|_| self.error(field, format!("数量无效: {}", word)))
+    }
+
+    // 读取十六进制标志（可带 0x 前缀）
+    pub fn read_hex(&mut self, field: &str) -> Result<u64> {
+        let word = self.word(field)?;
+        let digits = word
+            .strip_prefix("0x")
+            .or_else(|| word.strip_prefix("0X"))
+            .unwrap_or(word);
+        match u64::from_str_radix(digits, 16) {
+            Ok(value) => Ok(value),
+            Err(_) => {
+                self.warn(field, format!("不是有效的十六进制数: {}", word))?;
+                Ok(0)
+            }
+        }
+    }
+
+    // 读取名称，文件中的下划线还原为空格
+    pub fn read_name(&mut self, field: &str) -> Result<String> {
+        Ok(self.word(field)?.replace('_', " "))
+    }
+
+    // 读取文件头 "<magic> version <n>"
+    pub fn header(&mut self, magic: &str) -> Result<u32> {
+        let found = self.word("文件头")?;
+        if found != magic || self.word("文件头")? != "version" {
+            return Err(self.error("文件头", format!("应为 {} version <n>，实际为 {}", magic, found)));
+        }
+        let word = self.word("版本号")?;
+        word.parse()
+            .map_err(|_| self.error("版本号", format!("值无效: {}", word)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_positions_across_lines() {
+        let mut reader = TokenReader::with_file("troops.txt", "troopsfile version 2\n  7 \r\n0x1F  -1.5\n");
+        assert_eq!(reader.header("troopsfile").unwrap(), 2);
+        assert_eq!(reader.read_count("count").unwrap(), 7);
+        assert_eq!(reader.position(), Position { line: 2, column: 3 });
+        assert_eq!(reader.read_hex("flags").unwrap(), 0x1F);
+        assert_eq!(reader.position(), Position { line: 3, column: 1 });
+        assert_eq!(reader.read_f64("value").unwrap(), -1.5);
+        assert!(reader.is_eof());
+
+        let err = reader.read_i32("level").unwrap_err();
+        let err = err.downcast::<ParseError>().unwrap();
+        assert_eq!((err.line, err.column), (4, 1));
+        assert_eq!(err.field, "level");
+    }
+
+    #[test]
+    fn test_invalid_value_strict_and_lenient() {
+        let content = "itm_a  abc 7";
+
+        let mut reader = TokenReader::with_file("items.txt", content).with_mode(ParseMode::Strict);
+        assert_eq!(reader.peek(), Some("itm_a"));
+        let id = reader.word("id").unwrap();
+        reader.begin_record(id);
+        let err = reader.read_u64("flags").unwrap_err();
+        assert_eq!(err.to_string(), "items.txt:1:8: [itm_a] 字段 flags: 值无效: abc");
+
+        let mut reader = TokenReader::with_file("items.txt", content).with_mode(ParseMode::Lenient);
+        reader.word("id").unwrap();
+        assert_eq!(reader.read_u64("flags").unwrap(), 0);
+        assert_eq!(reader.read_i32("price").unwrap(), 7);
+        let warnings = reader.take_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].field, "flags");
+        assert_eq!((warnings[0].line, warnings[0].column), (1, 8));
+        assert_eq!(reader.read_hex("mask").unwrap_err().to_string(), "items.txt:1:13: 字段 mask: 文件意外结束");
+    }
+}
@@ -0,0 +1,1155 @@
+// 游戏数据写入器 - 按引擎导出格式输出文本数据文件
+
+use anyhow::Result;
+use std::fmt::{self, Write};
+use std::path::Path;
+use super::models::*;
+
+// 各数据文件的默认版本号（物品文件按游戏版本）
+const TROOPS_VERSION: u32 = 2;
+const FACTIONS_VERSION: u32 = 1;
+const PARTIES_VERSION: u32 = 1;
+const PARTY_TEMPLATES_VERSION: u32 = 1;
+const SCENES_VERSION: u32 = 1;
+const MAP_ICONS_VERSION: u32 = 1;
+const SCRIPTS_VERSION: u32 = 1;
+const SIMPLE_TRIGGERS_VERSION: u32 = 1;
+const TRIGGERS_VERSION: u32 = 1;
+const MISSION_TEMPLATES_VERSION: u32 = 1;
+const DIALOGS_VERSION: u32 = 2;
+const STRINGS_VERSION: u32 = 1;
+const QUESTS_VERSION: u32 = 1;
+const INFO_PAGES_VERSION: u32 = 1;
+const SKINS_VERSION: u32 = 1;
+const SOUNDS_VERSION: u32 = 3;
+const SCENE_PROPS_VERSION: u32 = 1;
+const PARTICLE_SYSTEMS_VERSION: u32 = 1;
+const MENUS_VERSION: u32 = 1;
+const PRESENTATIONS_VERSION: u32 = 1;
+const POSTFX_VERSION: u32 = 1;
+
+pub struct Writer;
+
+impl Writer {
+    // 保存游戏数据到模块目录
+    // 只重新生成修改过的文件，内容与原文件相同时不写入；未修改的文件和不认识的文件保持原样
+    pub fn save_game_data<P: AsRef<Path>>(data: &mut GameData, module_dir: P) -> Result<()> {
+        let module_dir = module_dir.as_ref();
+        // 在写入任何文件之前检查，避免只保存了一部分
+        let lossy: Vec<&str> = data.changed_files.intersection(&data.lossy_files).map(String::as_str).collect();
+        if !lossy.is_empty() {
+            return Err(anyhow::anyhow!(
+                "{} 解析时有内容无法识别（已按默认值处理或跳过），保存会改写这些数据，请修正文件后重新加载",
+                lossy.join(", ")
+            ));
+        }
+        let mut written: Vec<(String, String)> = Vec::new();
+        {
+            let data: &GameData = data;
+            let version = |file: &str, default: u32| data.file_versions.get(file).copied().unwrap_or(default);
+            let mut save = |file: &str, write: &dyn Fn() -> Result<String>| -> Result<()> {
+                if !data.changed_files.contains(file) {
+                    return Ok(());
+                }
+                let content = write()?;
+                if data.original_files.get(file).is_some_and(|original| *original == content.as_bytes()) {
+                    return Ok(());
+                }
+                Self::save_file(module_dir.join(file), &content)?;
+                written.push((file.to_string(), content));
+                Ok(())
+            };
+
+            save("item_kinds1.txt", &|| Self::write_items(version("item_kinds1.txt", data.edition.items_version()), &data.items))?;
+            save("troops.txt", &|| Self::write_troops(version("troops.txt", TROOPS_VERSION), &data.troops))?;
+            save("factions.txt", &|| Self::write_factions(version("factions.txt", FACTIONS_VERSION), &data.factions))?;
+            save("parties.txt", &|| Self::write_parties(version("parties.txt", PARTIES_VERSION), &data.parties))?;
+            save("party_templates.txt", &|| Self::write_party_templates(version("party_templates.txt", PARTY_TEMPLATES_VERSION), &data.party_templates))?;
+            save("scenes.txt", &|| Self::write_scenes(version("scenes.txt", SCENES_VERSION), &data.scenes))?;
+            save("map_icons.txt", &|| Self::write_map_icons(version("map_icons.txt", MAP_ICONS_VERSION), &data.map_icons))?;
+            save("scripts.txt", &|| Self::write_scripts(version("scripts.txt", SCRIPTS_VERSION), &data.scripts))?;
+            save("simple_triggers.txt", &|| Self::write_simple_triggers_file(version("simple_triggers.txt", SIMPLE_TRIGGERS_VERSION), &data.simple_triggers))?;
+            save("triggers.txt", &|| Self::write_triggers_file(version("triggers.txt", TRIGGERS_VERSION), &data.triggers))?;
+            save("mission_templates.txt", &|| Self::write_mission_templates(version("mission_templates.txt", MISSION_TEMPLATES_VERSION), &data.mission_templates))?;
+            save("conversation.txt", &|| Self::write_dialogs(version("conversation.txt", DIALOGS_VERSION), &data.dialogs))?;
+            save("dialog_states.txt", &|| Ok(Self::write_dialog_states(&data.dialog_states)))?;
+            save("strings.txt", &|| Self::write_strings(version("strings.txt", STRINGS_VERSION), &data.strings))?;
+            save("quests.txt", &|| Self::write_quests(version("quests.txt", QUESTS_VERSION), &data.quests))?;
+            save("info_pages.txt", &|| Self::write_info_pages(version("info_pages.txt", INFO_PAGES_VERSION), &data.info_pages))?;
+            save("skills.txt", &|| Self::write_skills(&data.skills))?;
+            save("skins.txt", &|| Self::write_skins(version("skins.txt", SKINS_VERSION), &data.skins))?;
+            save("sounds.txt", &|| Self::write_sounds(version("sounds.txt", SOUNDS_VERSION), &data.sound_samples, &data.sounds))?;
+            save("music.txt", &|| Self::write_music(&data.music))?;
+            save("scene_props.txt", &|| Self::write_scene_props(version("scene_props.txt", SCENE_PROPS_VERSION), &data.scene_props))?;
+            save("particle_systems.txt", &|| Self::write_particle_systems(version("particle_systems.txt", PARTICLE_SYSTEMS_VERSION), &data.particle_systems))?;
+            save("menus.txt", &|| Self::write_menus(version("menus.txt", MENUS_VERSION), &data.menus))?;
+            save("presentations.txt", &|| Self::write_presentations(version("presentations.txt", PRESENTATIONS_VERSION), &data.presentations))?;
+            save("tableau_materials.txt", &|| Self::write_tableau_materials(&data.tableau_materials))?;
+            save("postfx.txt", &|| Self::write_postfx(version("postfx.txt", POSTFX_VERSION), &data.postfx))?;
+            save("quick_strings.txt", &|| Self::write_quick_strings(&data.quick_strings))?;
+            save("variables.txt", &|| Ok(Self::write_variables(&data.variables)))?;
+            save("actions.txt", &|| Self::write_actions(&data.actions))?;
+            save("tag_uses.txt", &|| Self::write_tag_uses(&data.tag_uses))?;
+        }
+
+        for (file, content) in written {
+            data.missing_files.remove(&file);
+            data.original_files.insert(file, content.into_bytes());
+        }
+        data.changed_files.clear();
+        Ok(())
+    }
+
+    // 先写入临时文件再替换，避免写入中断损坏原文件
+    pub(crate) fn save_file<P: AsRef<Path>>(path: P, content: impl AsRef<[u8]>) -> Result<()> {
+        let path = path.as_ref();
+        let mut temp_path = path.as_os_str().to_owned();
+        temp_path.push(".tmp");
+        std::fs::write(&temp_path, content)?;
+        std::fs::rename(&temp_path, path)?;
+        tracing::info!("已写入: {}", path.display());
+        Ok(())
+    }
+
+    // 生成 item_kinds1.txt
+    pub fn write_items(version: u32, items: &[Item]) -> Result<String> {
+        let mut out = String::new();
+        writeln!(out, "itemsfile version {}", version)?;
+        writeln!(out, "{}", items.len())?;
+        // 初代没有物品派系列表
+        let with_factions = version >= ITEM_FACTIONS_VERSION;
+        for item in items {
+            Self::write_item(&mut out, item, with_factions)?;
+        }
+        Ok(out)
+    }
+
+    fn write_item(out: &mut String, item: &Item, with_factions: bool) -> fmt::Result {
+        write!(out, " {} {} {} {} ", item.id, replace_spaces(&item.name), replace_spaces(&item.plural_name), item.meshes.len())?;
+        for mesh in &item.meshes {
+            write!(out, " {} {} ", mesh.name, mesh.modifier_bits)?;
+        }
+        writeln!(
+            out,
+            " {} {} {} {} {:.6} {} {} {} {} {} {} {} {} {} {} {} {}",
+            item.flags, item.capabilities, item.price, item.modifier_bits, item.weight,
+            item.abundance, item.head_armor, item.body_armor, item.leg_armor,
+            item.difficulty, item.hit_points, item.speed_rating, item.missile_speed,
+            item.weapon_length, item.max_ammo, item.thrust_damage, item.swing_damage,
+        )?;
+
+        if with_factions {
+            if item.factions.is_empty() {
+                writeln!(out, " 0")?;
+            } else {
+                writeln!(out, " {}", item.factions.len())?;
+                for faction in &item.factions {
+                    write!(out, " {}", faction)?;
+                }
+                writeln!(out)?;
+            }
+        }
+
+        Self::write_simple_triggers(out, &item.triggers)
+    }
+
+    // 简单触发器列表（数量行 + 每个触发器一行 + 空行）
+    fn write_simple_triggers(out: &mut String, triggers: &[SimpleTrigger]) -> fmt::Result {
+        writeln!(out, "{}", triggers.len())?;
+        for trigger in triggers {
+            write!(out, "{:.6} ", trigger.interval)?;
+            Self::write_operations(out, &trigger.operations)?;
+            writeln!(out)?;
+        }
+        writeln!(out)
+    }
+
+    // 操作块
+    fn write_operations(out: &mut String, operations: &[Operation]) -> fmt::Result {
+        write!(out, " {} ", operations.len())?;
+        for operation in operations {
+            write!(out, "{} {} ", operation.opcode, operation.operands.len())?;
+            for operand in &operation.operands {
+                write!(out, "{} ", operand)?;
+            }
+        }
+        Ok(())
+    }
+
+    // 生成 troops.txt
+    pub fn write_troops(version: u32, troops: &[Troop]) -> Result<String> {
+        let mut out = String::new();
+        writeln!(out, "troopsfile version {}", version)?;
+        write!(out, "{} ", troops.len())?;
+        for troop in troops {
+            Self::write_troop(&mut out, troop)?;
+        }
+        Ok(out)
+    }
+
+    fn write_troop(out: &mut String, troop: &Troop) -> fmt::Result {
+        write!(
+            out,
+            "\n{} {} {} {} {} {} {} {} {} {}\n  ",
+            troop.id, replace_spaces(&troop.name), replace_spaces(&troop.plural_name), troop.image,
+            troop.flags, troop.scene, troop.reserved, troop.faction, troop.upgrade_1, troop.upgrade_2,
+        )?;
+
+        for slot in &troop.inventory {
+            write!(out, "{} {} ", slot.item, slot.modifier)?;
+        }
+        write!(out, "\n ")?;
+
+        writeln!(
+            out,
+            " {} {} {} {} {}",
+            troop.strength, troop.agility, troop.intelligence, troop.charisma, troop.level,
+        )?;
+        for proficiency in &troop.proficiencies {
+            write!(out, " {}", proficiency)?;
+        }
+        writeln!(out)?;
+        for word in &troop.skill_words {
+            write!(out, "{} ", word)?;
+        }
+        write!(out, "\n  ")?;
+        for face_key in &troop.face_keys {
+            for word in &face_key.words {
+                write!(out, "{} ", word)?;
+            }
+        }
+        writeln!(out)
+    }
+
+    // 生成 factions.txt
+    pub fn write_factions(version: u32, factions: &[Faction]) -> Result<String> {
+        let mut out = String::new();
+        writeln!(out, "factionsfile version {}", version)?;
+        writeln!(out, "{}", factions.len())?;
+        for faction in factions {
+            Self::write_faction(&mut out, faction, factions.len())?;
+        }
+        Ok(out)
+    }
+
+    fn write_faction(out: &mut String, faction: &Faction, faction_count: usize) -> fmt::Result {
+        write!(out, "{} {} {} ", faction.id, replace_spaces(&faction.name), faction.flags.bits)?;
+        if let Some(coherence) = faction.coherence {
+            write!(out, "{:.6} ", coherence)?;
+        }
+        writeln!(out, "{} ", faction.color)?;
+
+        // 关系行长度必须与派系数量一致
+        for faction_no in 0..faction_count {
+            write!(out, " {:.6}", faction.relation_with(faction_no))?;
+        }
+        writeln!(out)?;
+
+        write!(out, "{} ", faction.ranks.len())?;
+        for rank in &faction.ranks {
+            write!(out, " {} ", replace_spaces(rank))?;
+        }
+        Ok(())
+    }
+
+    // 生成 parties.txt
+    pub fn write_parties(version: u32, parties: &[Party]) -> Result<String> {
+        let mut out = String::new();
+        writeln!(out, "partiesfile version {}", version)?;
+        writeln!(out, "{} {}", parties.len(), parties.len())?;
+        for (index, party) in parties.iter().enumerate() {
+            Self::write_party(&mut out, index, party)?;
+        }
+        Ok(out)
+    }
+
+    // 个性和 AI 目标按导出脚本的格式重复写入
+    fn write_party(out: &mut String, index: usize, party: &Party) -> fmt::Result {
+        write!(out, "1 {} {} ", index, index)?;
+        write!(out, "{} {} {} ", party.id, replace_spaces(&party.name), party.flags)?;
+        write!(out, "{} ", party.menu)?;
+        write!(
+            out,
+            "{} {} {} {} {} ",
+            party.template, party.faction, party.personality, party.personality, party.ai_behavior,
+        )?;
+        write!(out, "{} {} ", party.ai_target, party.ai_target)?;
+
+        for [x, y] in [party.behavior_position, party.target_position, party.position] {
+            write!(out, "{:.6} {:.6} ", x, y)?;
+        }
+        if party.position_z == 0.0 {
+            write!(out, "0.0 ")?;
+        } else {
+            write!(out, "{:.6} ", party.position_z)?;
+        }
+
+        write!(out, "{} ", party.stacks.len())?;
+        for stack in &party.stacks {
+            write!(out, "{} {} 0 {} ", stack.troop, stack.count, stack.flags)?;
+        }
+        write!(out, "\n{:.6}\n", party.bearing)
+    }
+
+    // 生成 party_templates.txt
+    pub fn write_party_templates(version: u32, templates: &[PartyTemplate]) -> Result<String> {
+        let mut out = String::new();
+        writeln!(out, "partytemplatesfile version {}", version)?;
+        writeln!(out, "{}", templates.len())?;
+        for template in templates {
+            Self::write_party_template(&mut out, template)?;
+        }
+        Ok(out)
+    }
+
+    // 兵种组不足 6 个时以 -1 补齐
+    fn write_party_template(out: &mut String, template: &PartyTemplate) -> fmt::Result {
+        write!(
+            out,
+            "{} {} {} {} {} {} ",
+            template.id, replace_spaces(&template.name), template.flags,
+            template.menu, template.faction, template.personality,
+        )?;
+        for stack in template.stacks.iter().take(PARTY_TEMPLATE_STACKS) {
+            write!(out, "{} {} {} {} ", stack.troop, stack.min, stack.max, stack.flags)?;
+        }
+        for _ in template.stacks.len()..PARTY_TEMPLATE_STACKS {
+            write!(out, "-1 ")?;
+        }
+        writeln!(out)
+    }
+
+    // 生成 scenes.txt
+    pub fn write_scenes(version: u32, scenes: &[Scene]) -> Result<String> {
+        let mut out = String::new();
+        writeln!(out, "scenesfile version {}", version)?;
+        writeln!(out, " {}", scenes.len())?;
+        for scene in scenes {
+            Self::write_scene(&mut out, scene)?;
+        }
+        Ok(out)
+    }
+
+    fn write_scene(out: &mut String, scene: &Scene) -> fmt::Result {
+        write!(
+            out,
+            "{} {} {} {} {} {:.6} {:.6} {:.6} {:.6} {:.6} {} ",
+            scene.id, replace_spaces(&scene.name), scene.flags, scene.mesh_name, scene.body_name,
+            scene.min_pos[0], scene.min_pos[1], scene.max_pos[0], scene.max_pos[1],
+            scene.water_level, scene.terrain_code,
+        )?;
+
+        write!(out, "\n  {} ", scene.passages.len())?;
+        for passage in &scene.passages {
+            write!(out, " {} ", passage)?;
+        }
+        write!(out, "\n  {} ", scene.chest_troops.len())?;
+        for troop in &scene.chest_troops {
+            write!(out, " {} ", troop)?;
+        }
+        writeln!(out)?;
+        writeln!(out, " {} ", scene.outer_terrain)
+    }
+
+    // 生成 map_icons.txt
+    pub fn write_map_icons(version: u32, icons: &[MapIcon]) -> Result<String> {
+        let mut out = String::new();
+        writeln!(out, "map_icons_file version {}", version)?;
+        writeln!(out, "{}", icons.len())?;
+        for icon in icons {
+            Self::write_map_icon(&mut out, icon)?;
+        }
+        Ok(out)
+    }
+
+    fn write_map_icon(out: &mut String, icon: &MapIcon) -> fmt::Result {
+        write!(
+            out,
+            "{} {} {} {:.6} {} {:.6} {:.6} {:.6} ",
+            icon.id, icon.flags, icon.mesh_name, icon.scale, icon.sound,
+            icon.offset[0], icon.offset[1], icon.offset[2],
+        )?;
+        Self::write_simple_triggers(out, &icon.triggers)?;
+        writeln!(out)
+    }
+
+    // 生成 scripts.txt
+    pub fn write_scripts(version: u32, scripts: &[Script]) -> Result<String> {
+        let mut out = String::new();
+        writeln!(out, "scriptsfile version {}", version)?;
+        writeln!(out, "{}", scripts.len())?;
+        for script in scripts {
+            match script.flags {
+                Some(flags) => writeln!(out, "{} {:.6}", script.name, flags)?,
+                None => writeln!(out, "{} -1", script.name)?,
+            }
+            Self::write_operations(&mut out, &script.operations)?;
+            writeln!(out)?;
+        }
+        Ok(out)
+    }
+
+    // 生成 simple_triggers.txt（与物品中的简单触发器不同，末尾没有空行）
+    pub fn write_simple_triggers_file(version: u32, triggers: &[SimpleTrigger]) -> Result<String> {
+        let mut out = String::new();
+        writeln!(out, "simple_triggers_file version {}", version)?;
+        writeln!(out, "{}", triggers.len())?;
+        for trigger in triggers {
+            write!(out, "{:.6} ", trigger.interval)?;
+            Self::write_operations(&mut out, &trigger.operations)?;
+            writeln!(out)?;
+        }
+        Ok(out)
+    }
+
+    // 生成 triggers.txt
+    pub fn write_triggers_file(version: u32, triggers: &[Trigger]) -> Result<String> {
+        let mut out = String::new();
+        writeln!(out, "triggersfile version {}", version)?;
+        writeln!(out, "{}", triggers.len())?;
+        for trigger in triggers {
+            Self::write_trigger(&mut out, trigger)?;
+        }
+        Ok(out)
+    }
+
+    fn write_trigger(out: &mut String, trigger: &Trigger) -> fmt::Result {
+        write!(
+            out,
+            "{:.6} {:.6} {:.6} ",
+            trigger.check_interval, trigger.delay_interval, trigger.rearm_interval,
+        )?;
+        Self::write_operations(out, &trigger.conditions)?;
+        Self::write_operations(out, &trigger.consequences)?;
+        writeln!(out)
+    }
+
+    // 生成 mission_templates.txt
+    pub fn write_mission_templates(version: u32, templates: &[MissionTemplate]) -> Result<String> {
+        let mut out = String::new();
+        writeln!(out, "missionsfile version {}", version)?;
+        writeln!(out, " {}", templates.len())?;
+        for template in templates {
+            Self::write_mission_template(&mut out, template)?;
+        }
+        Ok(out)
+    }
+
+    fn write_mission_template(out: &mut String, template: &MissionTemplate) -> fmt::Result {
+        write!(out, "{} {} {} ", template.id, template.name, template.flags)?;
+        writeln!(out, " {}", template.mission_type)?;
+        writeln!(out, "{} ", replace_spaces(&template.description))?;
+
+        write!(out, "\n{} ", template.spawns.len())?;
+        for spawn in &template.spawns {
+            write!(
+                out,
+                "{} {} {} {} {} {}  ",
+                spawn.entry_point, spawn.spawn_flags, spawn.alter_flags,
+                spawn.ai_flags, spawn.troop_count, spawn.item_overrides.len(),
+            )?;
+            for item in &spawn.item_overrides {
+                write!(out, "{} ", item)?;
+            }
+            writeln!(out)?;
+        }
+
+        writeln!(out, "{}", template.triggers.len())?;
+        for trigger in &template.triggers {
+            Self::write_trigger(out, trigger)?;
+        }
+        writeln!(out)?;
+        writeln!(out)
+    }
+
+    // 生成 conversation.txt
+    pub fn write_dialogs(version: u32, dialogs: &[Dialog]) -> Result<String> {
+        let mut out = String::new();
+        writeln!(out, "dialogsfile version {}", version)?;
+        writeln!(out, "{}", dialogs.len())?;
+        for dialog in dialogs {
+            Self::write_dialog(&mut out, dialog)?;
+        }
+        Ok(out)
+    }
+
+    fn write_dialog(out: &mut String, dialog: &Dialog) -> fmt::Result {
+        write!(out, "{} {} {} ", dialog.id, dialog.partner, dialog.input_state)?;
+        Self::write_operations(out, &dialog.conditions)?;
+        write!(out, "{} ", replace_spaces(&dialog.text))?;
+        if dialog.text.is_empty() {
+            write!(out, "NO_TEXT ")?;
+        }
+        write!(out, " {} ", dialog.output_state)?;
+        Self::write_operations(out, &dialog.consequences)?;
+        write!(out, "{} ", dialog.voice_over.as_deref().unwrap_or("NO_VOICEOVER"))?;
+        writeln!(out)
+    }
+
+    // 生成 dialog_states.txt（每行一个状态名）
+    pub fn write_dialog_states(states: &[String]) -> String {
+        states.iter().map(|state| format!("{}\n", state)).collect()
+    }
+
+    // 生成 strings.txt
+    pub fn write_strings(version: u32, strings: &[GameString]) -> Result<String> {
+        let mut out = String::new();
+        writeln!(out, "stringsfile version {}", version)?;
+        writeln!(out, "{}", strings.len())?;
+        for string in strings {
+            writeln!(out, "{} {}", string.id, replace_spaces(&string.text))?;
+        }
+        Ok(out)
+    }
+
+    // 生成 quests.txt
+    pub fn write_quests(version: u32, quests: &[Quest]) -> Result<String> {
+        let mut out = String::new();
+        writeln!(out, "questsfile version {}", version)?;
+        writeln!(out, "{}", quests.len())?;
+        for quest in quests {
+            writeln!(
+                out,
+                "{} {} {} {} ",
+                quest.id,
+                replace_spaces(&quest.name),
+                quest.flags,
+                replace_spaces(&quest.description)
+            )?;
+        }
+        Ok(out)
+    }
+
+    // 生成 info_pages.txt
+    pub fn write_info_pages(version: u32, pages: &[InfoPage]) -> Result<String> {
+        let mut out = String::new();
+        writeln!(out, "infopagesfile version {}", version)?;
+        writeln!(out, "{}", pages.len())?;
+        for page in pages {
+            writeln!(out, "{} {} {}", page.id, replace_spaces(&page.name), replace_spaces(&page.text))?;
+        }
+        Ok(out)
+    }
+
+    // 生成 skills.txt（无文件头）
+    pub fn write_skills(skills: &[Skill]) -> Result<String> {
+        let mut out = String::new();
+        writeln!(out, "{}", skills.len())?;
+        for skill in skills {
+            write!(out, "{} {} ", skill.id, replace_spaces(&skill.name))?;
+            writeln!(out, "{} {} {}", skill.flags, skill.max_level, replace_spaces(&skill.description))?;
+        }
+        Ok(out)
+    }
+
+    // 生成 skins.txt
+    pub fn write_skins(version: u32, skins: &[Skin]) -> Result<String> {
+        let mut out = String::new();
+        writeln!(out, "skins_file version {}", version)?;
+        writeln!(out, "{}", skins.len())?;
+        for skin in skins {
+            Self::write_skin(&mut out, skin)?;
+        }
+        Ok(out)
+    }
+
+    fn write_skin(out: &mut String, skin: &Skin) -> fmt::Result {
+        write!(out, "{} {}\n {} {} {}\n", skin.id, skin.flags, skin.body_mesh, skin.calf_mesh, skin.hand_mesh)?;
+        write!(out, " {} {} ", skin.head_mesh, skin.face_keys.len())?;
+        for key in &skin.face_keys {
+            write!(
+                out,
+                "{} {} {} {:.6} {:.6} {} ",
+                key.id,
+                key.key,
+                key.param,
+                key.min,
+                key.max,
+                replace_spaces(&key.name)
+            )?;
+        }
+        write!(out, "\n{}\n", skin.hair_meshes.len())?;
+        for mesh in &skin.hair_meshes {
+            write!(out, " {} ", mesh)?;
+        }
+        write!(out, "\n {}\n", skin.beard_meshes.len())?;
+        for mesh in &skin.beard_meshes {
+            writeln!(out, "  {}", mesh)?;
+        }
+        writeln!(out)?;
+        Self::write_textures(out, &skin.hair_textures)?;
+        Self::write_textures(out, &skin.beard_textures)?;
+
+        write!(out, " {} ", skin.face_textures.len())?;
+        for texture in &skin.face_textures {
+            write!(
+                out,
+                " {} {} {} {} ",
+                texture.texture,
+                texture.color,
+                texture.hair_materials.len(),
+                texture.hair_colors.len()
+            )?;
+            for material in &texture.hair_materials {
+                write!(out, " {} ", replace_spaces(material))?;
+            }
+            for color in &texture.hair_colors {
+                write!(out, " {} ", color)?;
+            }
+        }
+        writeln!(out)?;
+
+        write!(out, " {} ", skin.voices.len())?;
+        for voice in &skin.voices {
+            write!(out, " {} {} ", voice.kind, voice.sound)?;
+        }
+        writeln!(out)?;
+
+        write!(out, " {} {:.6} ", skin.skeleton, skin.scale)?;
+        write!(out, "\n{} {}\n", skin.blood_particles[0], skin.blood_particles[1])?;
+        writeln!(out, "{}", skin.constraints.len())?;
+        for constraint in &skin.constraints {
+            write!(out, "\n{:.6} {} {} ", constraint.value, constraint.comparison, constraint.pairs.len())?;
+            for (weight, key) in &constraint.pairs {
+                write!(out, " {:.6} {}", weight, key)?;
+            }
+        }
+        writeln!(out)
+    }
+
+    fn write_textures(out: &mut String, textures: &[String]) -> fmt::Result {
+        write!(out, " {} ", textures.len())?;
+        for texture in textures {
+            write!(out, " {} ", texture)?;
+        }
+        writeln!(out)
+    }
+
+    // 生成 sounds.txt，保留原有的全局采样列表，新用到的采样追加在末尾（标志取首个使用它的声音）
+    pub fn write_sounds(version: u32, samples: &[SampleFile], sounds: &[Sound]) -> Result<String> {
+        let mut samples = samples.to_vec();
+        let mut sound_samples = Vec::with_capacity(sounds.len());
+        for sound in sounds {
+            let numbers: Vec<(usize, i32)> = sound.samples.iter()
+                .map(|sample| {
+                    let no = match samples.iter().position(|known| known.file == sample.file) {
+                        Some(no) => no,
+                        None => {
+                            samples.push(SampleFile { file: sample.file.clone(), flags: sound.flags });
+                            samples.len() - 1
+                        }
+                    };
+                    (no, sample.variation)
+                })
+                .collect();
+            sound_samples.push(numbers);
+        }
+
+        let mut out = String::new();
+        writeln!(out, "soundsfile version {}", version)?;
+        writeln!(out, "{}", samples.len())?;
+        for sample in &samples {
+            writeln!(out, " {} {}", sample.file, sample.flags)?;
+        }
+        writeln!(out, "{}", sounds.len())?;
+        for (sound, numbers) in sounds.iter().zip(&sound_samples) {
+            write!(out, "{} {} {} ", sound.id, sound.flags, numbers.len())?;
+            for (no, variation) in numbers {
+                write!(out, "{} {} ", no, variation)?;
+            }
+            writeln!(out)?;
+        }
+        Ok(out)
+    }
+
+    // 生成 music.txt（无文件头）
+    pub fn write_music(tracks: &[MusicTrack]) -> Result<String> {
+        let mut out = String::new();
+        writeln!(out, "{}", tracks.len())?;
+        for track in tracks {
+            writeln!(out, "{} {} {}", track.file, track.flags, track.flags | track.continue_flags)?;
+        }
+        Ok(out)
+    }
+
+    // 生成 scene_props.txt（数量前有一个空格）
+    pub fn write_scene_props(version: u32, props: &[SceneProp]) -> Result<String> {
+        let mut out = String::new();
+        writeln!(out, "scene_propsfile version {}", version)?;
+        writeln!(out, " {}", props.len())?;
+        for prop in props {
+            write!(
+                out,
+                "{} {} {} {} {} ",
+                prop.id, prop.flags, prop.hit_points, prop.mesh_name, prop.physics_object
+            )?;
+            Self::write_simple_triggers(&mut out, &prop.triggers)?;
+            writeln!(out)?;
+        }
+        Ok(out)
+    }
+
+    // 生成 particle_systems.txt
+    pub fn write_particle_systems(version: u32, systems: &[ParticleSystem]) -> Result<String> {
+        let mut out = String::new();
+        writeln!(out, "particle_systemsfile version {}", version)?;
+        writeln!(out, "{}", systems.len())?;
+        for system in systems {
+            Self::write_particle_system(&mut out, system)?;
+        }
+        Ok(out)
+    }
+
+    fn write_particle_system(out: &mut String, system: &ParticleSystem) -> fmt::Result {
+        write!(out, "{} {} {}  ", system.id, system.flags, system.mesh_name)?;
+        writeln!(
+            out,
+            "{} {:.6} {:.6} {:.6} {:.6} {:.6} ",
+            system.particles_per_second,
+            system.life,
+            system.damping,
+            system.gravity_strength,
+            system.turbulence_size,
+            system.turbulence_strength
+        )?;
+        for (_, curve) in system.curves() {
+            writeln!(
+                out,
+                "{:.6} {:.6} {:.6} {:.6}",
+                curve.start.time, curve.start.value, curve.end.time, curve.end.value
+            )?;
+        }
+        let [x, y, z] = system.emit_box_size;
+        write!(out, "{:.6} {:.6} {:.6}   ", x, y, z)?;
+        let [x, y, z] = system.emit_velocity;
+        write!(out, "{:.6} {:.6} {:.6}   ", x, y, z)?;
+        writeln!(out, "{:.6} ", system.emit_dir_randomness)?;
+        writeln!(out, "{:.6} {:.6} ", system.rotation_speed, system.rotation_damping)?;
+        writeln!(out)
+    }
+
+    // 生成 menus.txt
+    pub fn write_menus(version: u32, menus: &[GameMenu]) -> Result<String> {
+        let mut out = String::new();
+        writeln!(out, "menusfile version {}", version)?;
+        writeln!(out, " {}", menus.len())?;
+        for menu in menus {
+            Self::write_menu(&mut out, menu)?;
+        }
+        Ok(out)
+    }
+
+    fn write_menu(out: &mut String, menu: &GameMenu) -> fmt::Result {
+        write!(out, "{} {} {} {}", menu.id, menu.flags, replace_spaces(&menu.text), menu.mesh_name)?;
+        Self::write_operations(out, &menu.operations)?;
+        writeln!(out, "{}", menu.options.len())?;
+        for option in &menu.options {
+            write!(out, " {} ", option.id)?;
+            Self::write_operations(out, &option.conditions)?;
+            write!(out, " {} ", replace_spaces(&option.text))?;
+            Self::write_operations(out, &option.consequences)?;
+            write!(out, " {} ", replace_spaces(&option.door_name))?;
+        }
+        writeln!(out)
+    }
+
+    // 生成 presentations.txt
+    pub fn write_presentations(version: u32, presentations: &[Presentation]) -> Result<String> {
+        let mut out = String::new();
+        writeln!(out, "presentationsfile version {}", version)?;
+        writeln!(out, " {}", presentations.len())?;
+        for presentation in presentations {
+            write!(out, "{} {} {} ", presentation.id, presentation.flags, presentation.mesh)?;
+            Self::write_simple_triggers(&mut out, &presentation.triggers)?;
+            writeln!(out)?;
+        }
+        Ok(out)
+    }
+
+    // 生成 tableau_materials.txt（无文件头）
+    pub fn write_tableau_materials(materials: &[TableauMaterial]) -> Result<String> {
+        let mut out = String::new();
+        writeln!(out, "{}", materials.len())?;
+        for material in materials {
+            let [min_x, min_y, max_x, max_y] = material.mesh_bounds;
+            write!(
+                out,
+                "{} {} {} {} {} {} {} {} {}",
+                material.id, material.flags, material.sample_material,
+                material.width, material.height, min_x, min_y, max_x, max_y,
+            )?;
+            Self::write_operations(&mut out, &material.operations)?;
+            writeln!(out)?;
+        }
+        Ok(out)
+    }
+
+    // 生成 postfx.txt
+    pub fn write_postfx(version: u32, postfx: &[PostFx]) -> Result<String> {
+        let mut out = String::new();
+        writeln!(out, "postfx_paramsfile version {}", version)?;
+        writeln!(out, "{}", postfx.len())?;
+        for fx in postfx {
+            write!(out, "{} {} {}", fx.id, fx.flags, fx.tonemap_operator)?;
+            for [a, b, c, d] in &fx.params {
+                write!(out, "  {:.6} {:.6} {:.6} {:.6}", a, b, c, d)?;
+            }
+            writeln!(out)?;
+        }
+        Ok(out)
+    }
+
+    // 生成 quick_strings.txt（无文件头）
+    pub fn write_quick_strings(strings: &[QuickString]) -> Result<String> {
+        let mut out = String::new();
+        writeln!(out, "{}", strings.len())?;
+        for string in strings {
+            writeln!(out, "{} {}", string.id, replace_spaces(&string.text))?;
+        }
+        Ok(out)
+    }
+
+    // 生成 variables.txt（每行一个变量名）
+    pub fn write_variables(variables: &[String]) -> String {
+        variables.iter().map(|variable| format!("{}\n", variable)).collect()
+    }
+
+    // 生成 actions.txt（无文件头）
+    pub fn write_actions(actions: &[Action]) -> Result<String> {
+        let mut out = String::new();
+        writeln!(out, "{}", actions.len())?;
+        for action in actions {
+            if !action.is_defined() {
+                writeln!(out, " {} {} {}", action.name, action.flags, action.master_flags)?;
+                continue;
+            }
+            write!(out, " {} {} {} ", action.name, action.flags, action.master_flags)?;
+            writeln!(out, " {}", action.sequences.len())?;
+            for sequence in &action.sequences {
+                write!(
+                    out,
+                    "  {:.6} {} {} {} {} ",
+                    sequence.duration, sequence.resource, sequence.start_frame, sequence.end_frame, sequence.flags
+                )?;
+                write!(out, "{} ", sequence.extra_flags)?;
+                let [x, y, z] = sequence.position;
+                write!(out, "{:.6} {:.6} {:.6}  ", x, y, z)?;
+                writeln!(out, "{:.6} ", sequence.blend_in)?;
+            }
+        }
+        Ok(out)
+    }
+
+    // 生成 tag_uses.txt（所有记录在同一行，以分号结尾）
+    pub fn write_tag_uses(tag_uses: &[TagUse]) -> Result<String> {
+        let mut out = String::new();
+        for tag_use in tag_uses {
+            write!(out, "{} {} {};", tag_use.tag, tag_use.index, tag_use.uses)?;
+        }
+        writeln!(out)?;
+        Ok(out)
+    }
+}
+
+// 显示名称中的空格在文件中以下划线保存
+fn replace_spaces(text: &str) -> String {
+    text.replace(' ', "_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{GameEdition, Parser, TokenReader};
+    use crate::data::test_util::TempDir;
+
+    #[test]
+    fn test_items_round_trip() {
+        let content = concat!(
+            "itemsfile version 3\n2\n",
+            " itm_no_item INVALID_ITEM INVALID_ITEM 1  invalid_item 0  0 0 1 0 3.000000 0 0 0 0 0 0 100 0 0 0 0 0\n 0\n0\n\n",
+            " itm_torch Torch Torches 2  club 0  torch_fire 1152921504606846976  2 2 11 0 2.500000 100 0 0 0 0 0 95 0 95 0 11 523\n",
+            " 2\n 3 4\n1\n-50.000000  2 1720 3 0 0 0 1971 1 1 \n\n",
+        );
+        let file = Parser::read_items(&mut TokenReader::new(content), GameEdition::Warband).unwrap();
+        assert_eq!(Writer::write_items(file.version, &file.records).unwrap(), content);
+    }
+
+    #[test]
+    fn test_troops_round_trip() {
+        let slots = format!("217 0 394 4 {}", "-1 0 ".repeat(62));
+        let content = format!(
+            "troopsfile version 2\n1 \ntrp_swadian_footman Swadian_Footman Swadian_Footmen 0 19922944 0 0 4 19 0\n  {}\n  13 12 4 4 10\n 120 95 95 40 40 40 0\n293601348 0 0 0 0 0 \n  0 0 0 2305843009213693952 0 0 0 0 \n",
+            slots
+        );
+        let file = Parser::read_troops(&mut TokenReader::new(&content)).unwrap();
+        assert_eq!(Writer::write_troops(file.version, &file.records).unwrap(), content);
+    }
+
+    #[test]
+    fn test_factions_round_trip() {
+        let content = "factionsfile version 1\n2\nfac_commoners Commoners 0 11184810 \n 0.000000 -0.300000\n0 fac_kingdom_1 Kingdom_of_Swadia 256 14518340 \n -0.300000 1.000000\n2  Knight  Count_of_Swadia ";
+        let file = Parser::read_factions(&mut TokenReader::new(content)).unwrap();
+        assert_eq!(Writer::write_factions(file.version, &file.records).unwrap(), content);
+    }
+
+    #[test]
+    fn test_parties_round_trip() {
+        // 第三个队伍的行为坐标、目标坐标和当前坐标各不相同，z 不为 0
+        let content = concat!(
+            "partiesfile version 1\n3 3\n",
+            "1 0 0 p_main_party Main_Party 330 0 0 1 0 0 0 0 0 17.000000 52.500000 17.000000 52.500000 17.000000 52.500000 0.0 1 0 1 0 0 \n0.000000\n",
+            "1 1 1 p_town_1 Sargoth 9476 5 0 4 0 0 0 0 0 -17.600000 -19.100000 -17.600000 -19.100000 -17.600000 -19.100000 0.0 2 12 30 0 0 7 2 0 1 \n3.001966\n",
+            "1 2 2 p_zendar Zendar 9472 0 0 3 0 0 0 0 0 -2.000000 -4.500000 10.250000 20.000000 -123.456789 45.678912 0.500000 0 \n0.000000\n",
+        );
+        let file = Parser::read_parties(&mut TokenReader::new(content)).unwrap();
+        assert_eq!(file.records[2].behavior_position, [-2.0, -4.5]);
+        assert_eq!(file.records[2].position_z, 0.5);
+        assert_eq!(Writer::write_parties(file.version, &file.records).unwrap(), content);
+
+        let mut zendar = file.records[2].clone();
+        zendar.set_position([1.0, 2.0]);
+        assert_eq!(zendar.target_position, [10.25, 20.0]);
+        let mut town = file.records[1].clone();
+        town.set_position([1.0, 2.0]);
+        assert_eq!((town.behavior_position, town.target_position), ([1.0, 2.0], [1.0, 2.0]));
+    }
+
+    #[test]
+    fn test_party_templates_round_trip() {
+        let content = "partytemplatesfile version 1\n2\npt_none none 0 0 0 0 -1 -1 -1 -1 -1 -1 \npt_steppe_bandits Steppe_Bandits 8396872 0 11 1 15 4 18 0 16 0 2 1 -1 -1 -1 -1 \n";
+        let file = Parser::read_party_templates(&mut TokenReader::new(content)).unwrap();
+        assert_eq!(Writer::write_party_templates(file.version, &file.records).unwrap(), content);
+    }
+
+    #[test]
+    fn test_scenes_round_trip() {
+        let content = "scenesfile version 1\n 2\nscn_random_scene random_scene 768 none none 0.000000 0.000000 240.000000 240.000000 -0.500000 0x300028000003e8fa0000034e00004b34000059be \n  0 \n  0 \n 0 \nscn_town_1_center town_1_center 0 none none 0.000000 0.000000 100.000000 100.000000 -100.000000 0 \n  2  100000  3 \n  1  160 \n outer_terrain_town_1 \n";
+        let file = Parser::read_scenes(&mut TokenReader::new(content)).unwrap();
+        assert_eq!(Writer::write_scenes(file.version, &file.records).unwrap(), content);
+    }
+
+    #[test]
+    fn test_map_icons_round_trip() {
+        let content = concat!(
+            "map_icons_file version 1\n3\n",
+            "player 0 player 0.150000 0 0.000000 0.000000 0.000000 0\n\n\n",
+            "town 1 map_town_a 0.350000 0 0.000000 -0.600000 0.000000 1\n-100.000000  1 1 1 1 \n\n\n",
+            "banner 0 map_flag 0.123457 2 1234.567891 -123.456789 0.000001 0\n\n\n",
+        );
+        let file = Parser::read_map_icons(&mut TokenReader::new(content)).unwrap();
+        assert_eq!(Writer::write_map_icons(file.version, &file.records).unwrap(), content);
+    }
+
+    #[test]
+    fn test_scripts_round_trip() {
+        let content = "scriptsfile version 1\n2\ngame_start -1\n 2 2133 2 144115188075855885 0 1 1 936748722493063168 \ngame_quick_start 1.000000\n 0 \n";
+        let file = Parser::read_scripts(&mut TokenReader::new(content)).unwrap();
+        assert_eq!(Writer::write_scripts(file.version, &file.records).unwrap(), content);
+    }
+
+    #[test]
+    fn test_triggers_round_trip() {
+        let content = "simple_triggers_file version 1\n2\n0.000000  1 2133 2 144115188075855885 0 \n24.000000  0 \n";
+        let file = Parser::read_simple_triggers_file(&mut TokenReader::new(content)).unwrap();
+        assert_eq!(Writer::write_simple_triggers_file(file.version, &file.records).unwrap(), content);
+
+        let content = "triggersfile version 1\n1\n1.000000 0.000000 100000000.000000  1 31 2 1 2  1 1 1 3 \n";
+        let file = Parser::read_triggers_file(&mut TokenReader::new(content)).unwrap();
+        assert_eq!(Writer::write_triggers_file(file.version, &file.records).unwrap(), content);
+    }
+
+    #[test]
+    fn test_mission_templates_round_trip() {
+        let content = "missionsfile version 1\n 1\nmst_town_default town_default 0  4\nDefault_town_visit \n\n2 2 0 65536 0 1 0  \n1 128 0 0 1 2  213 220 \n1\n-5.000000 0.000000 100000000.000000  1 31 2 1 2  0 \n\n\n";
+        let file = Parser::read_mission_templates(&mut TokenReader::new(content)).unwrap();
+        assert_eq!(Writer::write_mission_templates(file.version, &file.records).unwrap(), content);
+    }
+
+    #[test]
+    fn test_dialogs_round_trip() {
+        let content = "dialogsfile version 2\n2\ndlga_start:close_window 69631 0  1 2133 2 1 2 Surrender_or_die!  6  0 NO_VOICEOVER \ndlga_member_chat:member_chat_2 4095 13  0  NO_TEXT  16  0 voice_01 \n";
+        let file = Parser::read_dialogs(&mut TokenReader::new(content)).unwrap();
+        assert_eq!(Writer::write_dialogs(file.version, &file.records).unwrap(), content);
+
+        let states = "start\nparty_encounter\nclose_window\n";
+        let file = Parser::read_dialog_states(&mut TokenReader::new(states)).unwrap();
+        assert_eq!(Writer::write_dialog_states(&file.records), states);
+    }
+
+    #[test]
+    fn test_texts_round_trip() {
+        let strings = "stringsfile version 1\n2\nstr_no_string NO_STRING!\nstr_hello Hello,_{s1}!\n";
+        let file = Parser::read_strings(&mut TokenReader::new(strings)).unwrap();
+        assert_eq!(Writer::write_strings(file.version, &file.records).unwrap(), strings);
+
+        let quests = "questsfile version 1\n1\nqst_deliver_message Deliver_Message_to_{s13} 3 {!}Lord_{s9}_wants_you. \n";
+        let file = Parser::read_quests(&mut TokenReader::new(quests)).unwrap();
+        assert_eq!(Writer::write_quests(file.version, &file.records).unwrap(), quests);
+
+        let pages = "infopagesfile version 1\n1\nip_morale Party_Morale Morale_is_important.\n";
+        let file = Parser::read_info_pages(&mut TokenReader::new(pages)).unwrap();
+        assert_eq!(Writer::write_info_pages(file.version, &file.records).unwrap(), pages);
+    }
+
+    #[test]
+    fn test_skills_and_skins_round_trip() {
+        let skills = "2\nskl_trade Trade 19 10 Every_level_of_this_skill_reduces_your_trade_penalty_by_5%.\nskl_reserved_1 Reserved_Skill_1 257 10 This_is_a_reserved_skill.\n";
+        let file = Parser::read_skills(&mut TokenReader::new(skills)).unwrap();
+        assert_eq!(Writer::write_skills(&file.records).unwrap(), skills);
+
+        let content = concat!(
+            "skins_file version 1\n1\nman 0\n man_body man_calf_l m_handL\n",
+            " male_head 1 skinkey_chin_size 20 0 0.700000 -0.600000 Chin_Size \n",
+            "2\n man_hair_s  man_hair_m \n 1\n  beard_e\n\n",
+            " 1  hair_blonde \n 1  beard_blonde \n",
+            " 1  manface_young_2 4291551456 1 1  hair_blonde  4294967295 \n",
+            " 2  0 snd_man_die  1 snd_man_hit \n",
+            " skel_human 1.000000 \n0 0\n1\n\n1.700000 -1 2  1.000000 42 1.000000 46\n",
+        );
+        let file = Parser::read_skins(&mut TokenReader::new(content)).unwrap();
+        assert_eq!(Writer::write_skins(file.version, &file.records).unwrap(), content);
+    }
+
+    #[test]
+    fn test_sounds_and_music_round_trip() {
+        // 采样标志与声音标志不同，unused.wav 没有声音引用
+        let sounds = "soundsfile version 3\n3\n click.wav 1\n drum.ogg 2560\n unused.wav 0\n2\nsnd_click 1 1 0 0 \nsnd_drums 2 2 1 0 0 0 \n";
+        let samples = Parser::read_sound_samples(&mut TokenReader::new(sounds)).unwrap();
+        let file = Parser::read_sounds(&mut TokenReader::new(sounds)).unwrap();
+        assert_eq!(Writer::write_sounds(file.version, &samples.records, &file.records).unwrap(), sounds);
+
+        // 新采样追加在列表末尾
+        let mut edited = file.records.clone();
+        edited[0].samples.push(SoundSample { file: "new.wav".into(), variation: 0 });
+        let output = Writer::write_sounds(file.version, &samples.records, &edited).unwrap();
+        assert!(output.starts_with("soundsfile version 3\n4\n click.wav 1\n drum.ogg 2560\n unused.wav 0\n new.wav 1\n"));
+        assert!(output.contains("snd_click 1 2 0 0 3 0 \n"));
+
+        let music = "2\ncalm_night_2.ogg 532480 532480\nfight_1.ogg 268436544 268436672\n";
+        let file = Parser::read_music(&mut TokenReader::new(music)).unwrap();
+        assert_eq!(file.records[1].continue_flags, 0x80);
+        assert_eq!(Writer::write_music(&file.records).unwrap(), music);
+    }
+
+    #[test]
+    fn test_scene_props_and_particles_round_trip() {
+        let props = concat!(
+            "scene_propsfile version 1\n 2\n",
+            "spr_barrier_box 8 0 barrier_box bo_barrier_box 0\n\n\n",
+            "spr_wall 40960 0 wall_a 0 1\n-42.000000  1 1 1 1 \n\n\n",
+        );
+        let file = Parser::read_scene_props(&mut TokenReader::new(props)).unwrap();
+        assert_eq!(Writer::write_scene_props(file.version, &file.records).unwrap(), props);
+
+        let content = concat!(
+            "particle_systemsfile version 1\n2\n",
+            "psys_torch_fire 3 prt_mesh_fire_1  50 0.350000 0.200000 0.030000 10.000000 0.000000 \n",
+            "0.500000 0.800000 1.000000 0.000000\n0.500000 1.000000 1.000000 0.900000\n",
+            "0.500000 0.700000 1.000000 0.300000\n0.500000 0.200000 1.000000 0.000000\n",
+            "0.000000 0.150000 0.400000 0.400000\n",
+            "0.040000 0.040000 0.010000   0.000000 0.000000 0.500000   0.000000 \n",
+            "200.000000 0.500000 \n\n",
+            "psys_dummy 0 prtl_none  0 0.000000 0.000000 0.000000 0.000000 0.000000 \n",
+            "0.000000 0.000000 0.000000 0.000000\n0.000000 0.000000 0.000000 0.000000\n",
+            "0.000000 0.000000 0.000000 0.000000\n0.000000 0.000000 0.000000 0.000000\n",
+            "0.000000 0.000000 0.000000 0.000000\n",
+            "0.000000 0.000000 0.000000   0.000000 0.000000 0.000000   0.000000 \n",
+            "0.000000 0.000000 \n\n",
+        );
+        let file = Parser::read_particle_systems(&mut TokenReader::new(content)).unwrap();
+        assert_eq!(Writer::write_particle_systems(file.version, &file.records).unwrap(), content);
+    }
+
+    #[test]
+    fn test_menus_and_presentations_round_trip() {
+        let menus = concat!(
+            "menusfile version 1\n 2\n",
+            "menu_start_game_0 0 Welcome_to_the_game none 1 2133 2 144115188075855885 0 2\n",
+            " mno_continue  0  Continue...  1 2060 1 144115188075855873  .  mno_go_back  0  Go_back  0  . \n",
+            "menu_town 16 {s10} town_mesh 0 0\n\n",
+        );
+        let file = Parser::read_menus(&mut TokenReader::new(menus)).unwrap();
+        assert_eq!(Writer::write_menus(file.version, &file.records).unwrap(), menus);
+
+        let presentations = "presentationsfile version 1\n 1\nprsnt_game_credits 2 0 1\n-60.000000  1 2133 2 1 2 \n\n\n";
+        let file = Parser::read_presentations(&mut TokenReader::new(presentations)).unwrap();
+        assert_eq!(Writer::write_presentations(file.version, &file.records).unwrap(), presentations);
+    }
+
+    #[test]
+    fn test_remaining_module_files_round_trip() {
+        let tableaus = "1\ntab_game_character_sheet 0 tableau_with_transparency 1024 1024 0 0 266 532 1 1116 1 720575940379279363 \n";
+        let file = Parser::read_tableau_materials(&mut TokenReader::new(tableaus)).unwrap();
+        assert_eq!(Writer::write_tableau_materials(&file.records).unwrap(), tableaus);
+
+        let postfx = "postfx_paramsfile version 1\n1\npfx_default 0 1  0.030000 1.000000 0.800000 1.000000  0.650000 0.750000 0.600000 0.000000  1.000000 1.000000 1.000000 1.000000\n";
+        let file = Parser::read_postfx(&mut TokenReader::new(postfx)).unwrap();
+        assert_eq!(Writer::write_postfx(file.version, &file.records).unwrap(), postfx);
+
+        let quick_strings = "2\nqstr_Ready! Ready!\nqstr_Go_on Go_on\n";
+        let file = Parser::read_quick_strings(&mut TokenReader::new(quick_strings)).unwrap();
+        assert_eq!(Writer::write_quick_strings(&file.records).unwrap(), quick_strings);
+
+        let variables = "player_party\nplayer_troop\n";
+        let file = Parser::read_variables(&mut TokenReader::new(variables)).unwrap();
+        assert_eq!(Writer::write_variables(&file.records), variables);
+
+        let actions = "3\n stand 0 0  2\n  3.000000 stand_man 0 30 1 0 0.000000 0.000000 0.000000  0.000000 \n  1.500000 stand_woman 0 15 0 5 0.000000 0.100000 0.000000  0.200000 \n none 0 0\n jump 2 16  0\n";
+        let file = Parser::read_actions(&mut TokenReader::new(actions)).unwrap();
+        assert_eq!(Writer::write_actions(&file.records).unwrap(), actions);
+
+        let tag_uses = "1 0 5;1 1 3;12 4 2;\n";
+        let file = Parser::read_tag_uses(&mut TokenReader::new(tag_uses)).unwrap();
+        assert_eq!(Writer::write_tag_uses(&file.records).unwrap(), tag_uses);
+    }
+
+    #[test]
+    fn test_save_only_changed_files() {
+        let dir = TempDir::new("save_changed");
+        // 手工编辑过的文件，格式与导出脚本不同
+        let troops = "troopsfile version 2\r\n0   \r\n";
+        let factions = "factionsfile version 1\n1\nfac_commoners Commoners 0 11184810 \n 0.000000\n0 ";
+        dir.write("troops.txt", troops);
+        dir.write("factions.txt", factions);
+        dir.write("strings.txt", "stringsfile version 1\n1\nstr_hello Hello!\n");
+        dir.write("variable_uses.txt", "1 2 3\n");
+
+        let mut data = Parser::new().parse_game_data(dir.path(), GameEdition::Warband).unwrap();
+        data.strings[0].text = "Hi there".to_string();
+        data.mark_changed("strings.txt");
+        data.mark_changed("factions.txt");
+        Writer::save_game_data(&mut data, dir.path()).unwrap();
+
+        let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap();
+        assert_eq!(read("troops.txt"), troops);
+        assert_eq!(read("factions.txt"), factions);
+        assert_eq!(read("strings.txt"), "stringsfile version 1\n1\nstr_hello Hi_there\n");
+        assert_eq!(read("variable_uses.txt"), "1 2 3\n");
+        assert!(data.changed_files.is_empty());
+        assert_eq!(data.original_files["strings.txt"], read("strings.txt").into_bytes());
+    }
+
+    #[test]
+    fn test_save_blocked_by_lenient_substitutions() {
+        let dir = TempDir::new("save_lossy");
+        let strings = "stringsfile version 1\n1\nstr_hello Hello!\n";
+        let troops = "troopsfile version 2\n1\ntrp_player Player Player 0 0 0 0 0 0 0 0\n";
+        dir.write("strings.txt", strings);
+        dir.write("troops.txt", troops);
+
+        // 兵种记录不完整，宽松模式下被跳过
+        let mut data = Parser::new().parse_game_data(dir.path(), GameEdition::Warband).unwrap();
+        assert!(data.lossy_files.contains("troops.txt"));
+
+        data.mark_changed("strings.txt");
+        data.mark_changed("troops.txt");
+        let err = Writer::save_game_data(&mut data, dir.path()).unwrap_err();
+        assert!(err.to_string().contains("troops.txt"));
+        assert_eq!(std::fs::read_to_string(dir.join("troops.txt")).unwrap(), troops);
+        assert_eq!(data.changed_files.len(), 2);
+
+        // 只修改没有问题的文件时可以保存
+        data.changed_files.remove("troops.txt");
+        Writer::save_game_data(&mut data, dir.path()).unwrap();
+    }
+}
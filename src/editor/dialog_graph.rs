@@ -0,0 +1,210 @@
+// 对话状态图分析（conversation.txt 中的输入/输出状态）
+
+use std::collections::VecDeque;
+use std::fmt;
+use crate::data::Dialog;
+
+// 引擎内置的对话状态，按编号排列（module system 中 dialog_states 的前 15 项）
+pub const BUILTIN_DIALOG_STATES: [&str; 15] = [
+    "start",
+    "party_encounter",
+    "prisoner_liberated",
+    "enemy_defeated",
+    "party_relieved",
+    "event_triggered",
+    "close_window",
+    "trade",
+    "exchange_members",
+    "trade_prisoners",
+    "buy_mercenaries",
+    "view_char",
+    "training",
+    "member_chat",
+    "prisoner_chat",
+];
+
+// 由引擎直接进入的状态（对话入口）
+const ENTRY_STATES: [&str; 8] = [
+    "start",
+    "party_encounter",
+    "prisoner_liberated",
+    "enemy_defeated",
+    "party_relieved",
+    "event_triggered",
+    "member_chat",
+    "prisoner_chat",
+];
+
+// 由引擎接管的状态（对话出口）
+const EXIT_STATES: [&str; 7] = [
+    "close_window",
+    "trade",
+    "exchange_members",
+    "trade_prisoners",
+    "buy_mercenaries",
+    "view_char",
+    "training",
+];
+
+// 孤立状态的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrphanKind {
+    NoIncoming, // 没有对话进入该状态
+    NoOutgoing, // 没有对话从该状态继续
+}
+
+// 孤立状态
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrphanState {
+    pub state: usize,
+    pub name: String,
+    pub kind: OrphanKind,
+}
+
+impl fmt::Display for OrphanState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            OrphanKind::NoIncoming => write!(f, "状态 {} 没有进入的对话", self.name),
+            OrphanKind::NoOutgoing => write!(f, "状态 {} 没有后续对话", self.name),
+        }
+    }
+}
+
+// 对话状态图：节点为状态，每条对话是一条从输入状态到输出状态的边
+#[derive(Debug, Clone, Default)]
+pub struct DialogGraph {
+    pub state_names: Vec<String>,
+    pub incoming: Vec<Vec<usize>>, // 按状态编号排列的进入对话序号
+    pub outgoing: Vec<Vec<usize>>, // 按状态编号排列的离开对话序号
+    // 状态编号无效（为负）的对话序号
+    pub invalid_dialogs: Vec<usize>,
+}
+
+impl DialogGraph {
+    // 构建状态图，没有 dialog_states.txt 时使用内置状态名
+    pub fn build(dialogs: &[Dialog], state_names: &[String]) -> Self {
+        let max_state = dialogs.iter()
+            .flat_map(|dialog| [dialog.input_state, dialog.output_state])
+            .max()
+            .map_or(0, |state| state.max(-1) + 1) as usize;
+        let count = max_state.max(state_names.len());
+
+        let mut graph = Self {
+            state_names: (0..count).map(|state| Self::default_name(state_names, state)).collect(),
+            incoming: vec![Vec::new(); count],
+            outgoing: vec![Vec::new(); count],
+            invalid_dialogs: Vec::new(),
+        };
+
+        for (index, dialog) in dialogs.iter().enumerate() {
+            if dialog.input_state < 0 || dialog.output_state < 0 {
+                graph.invalid_dialogs.push(index);
+                continue;
+            }
+            graph.outgoing[dialog.input_state as usize].push(index);
+            graph.incoming[dialog.output_state as usize].push(index);
+        }
+        graph
+    }
+
+    fn default_name(state_names: &[String], state: usize) -> String {
+        state_names.get(state)
+            .cloned()
+            .or_else(|| BUILTIN_DIALOG_STATES.get(state).map(|name| name.to_string()))
+            .unwrap_or_else(|| format!("#{}", state))
+    }
+
+    // 状态编号
+    pub fn state_index(&self, name: &str) -> Option<usize> {
+        self.state_names.iter().position(|state| state == name)
+    }
+
+    fn is_entry(&self, state: usize) -> bool {
+        ENTRY_STATES.contains(&self.state_names[state].as_str())
+    }
+
+    fn is_exit(&self, state: usize) -> bool {
+        EXIT_STATES.contains(&self.state_names[state].as_str())
+    }
+
+    // 孤立状态：非内置状态没有进入的对话，或出口以外没有后续对话
+    pub fn orphan_states(&self) -> Vec<OrphanState> {
+        let mut orphans = Vec::new();
+        for state in 0..self.state_names.len() {
+            let builtin = self.is_entry(state) || self.is_exit(state);
+            if self.incoming[state].is_empty() && !builtin {
+                orphans.push(self.orphan(state, OrphanKind::NoIncoming));
+            }
+            if self.outgoing[state].is_empty() && !self.is_exit(state) {
+                orphans.push(self.orphan(state, OrphanKind::NoOutgoing));
+            }
+        }
+        orphans
+    }
+
+    fn orphan(&self, state: usize, kind: OrphanKind) -> OrphanState {
+        OrphanState { state, name: self.state_names[state].clone(), kind }
+    }
+
+    // 从入口状态出发可以到达的状态
+    pub fn reachable_states(&self, dialogs: &[Dialog]) -> Vec<bool> {
+        let mut reached = vec![false; self.state_names.len()];
+        let mut queue: VecDeque<usize> = (0..self.state_names.len())
+            .filter(|&state| self.is_entry(state))
+            .collect();
+        for &state in &queue {
+            reached[state] = true;
+        }
+
+        while let Some(state) = queue.pop_front() {
+            for &index in &self.outgoing[state] {
+                let next = dialogs[index].output_state as usize;
+                if !reached[next] {
+                    reached[next] = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+        reached
+    }
+
+    // 无法到达的对话分支（输入状态不可达的对话序号）
+    pub fn unreachable_dialogs(&self, dialogs: &[Dialog]) -> Vec<usize> {
+        let reached = self.reachable_states(dialogs);
+        dialogs.iter()
+            .enumerate()
+            .filter(|(index, dialog)| {
+                !self.invalid_dialogs.contains(index) && !reached[dialog.input_state as usize]
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dialog(id: &str, input_state: i32, output_state: i32) -> Dialog {
+        Dialog { id: id.to_string(), input_state, output_state, ..Default::default() }
+    }
+
+    #[test]
+    fn test_orphans_and_unreachable() {
+        let mut states: Vec<String> = BUILTIN_DIALOG_STATES.iter().map(|s| s.to_string()).collect();
+        states.extend(["greeting", "dead_end", "island"].map(String::from));
+        let dialogs = vec![
+            dialog("dlga_start:greeting", 0, 15),
+            dialog("dlga_greeting:close_window", 15, 6),
+            dialog("dlga_greeting:dead_end", 15, 16),
+            dialog("dlga_island:close_window", 17, 6),
+        ];
+
+        let graph = DialogGraph::build(&dialogs, &states);
+        let orphans = graph.orphan_states();
+        assert!(orphans.contains(&OrphanState { state: 16, name: "dead_end".into(), kind: OrphanKind::NoOutgoing }));
+        assert!(orphans.contains(&OrphanState { state: 17, name: "island".into(), kind: OrphanKind::NoIncoming }));
+        assert!(!orphans.iter().any(|orphan| orphan.state == 0 || orphan.state == 6));
+        assert_eq!(graph.unreachable_dialogs(&dialogs), vec![3]);
+    }
+}
@@ -1,8 +1,14 @@
 // 编辑器核心功能模块
 
+pub mod dialog_graph;
+pub mod references;
+
+pub use dialog_graph::*;
+pub use references::*;
+
 use anyhow::Result;
 use std::sync::{Arc, RwLock};
-use crate::data::{GameManager, Item, Troop, Faction};
+use crate::data::{GameManager, GameData, GameCandidate, EditionInfo, Module, ModuleStats, Item, Troop, Faction, Party, PartyTemplate, TemplateIssue, Scene, MapIcon, Script, SimpleTrigger, Trigger, MissionTemplate, Dialog, GameString, Quest, InfoPage, Skill, Skin, Sound, MusicTrack, MissingAudio, SceneProp, ParticleSystem, GameMenu, Presentation, TableauMaterial, PostFx, QuickString, Action, ModuleIni, ParseMode, ParseWarning};
 
 #[derive(Clone)]
 pub struct Editor {
@@ -15,103 +21,409 @@ impl Editor {
             game_manager: Arc::new(RwLock::new(GameManager::new())),
         })
     }
-    
+
     // 检测游戏
     pub fn detect_game(&self) -> Result<Option<String>> {
-        match GameManager::detect_game()? {
+        let manager = self.game_manager.read().unwrap();
+        match manager.detect_game()? {
             Some(game) => Ok(Some(game.path.to_string_lossy().to_string())),
             None => Ok(None),
         }
     }
-    
-    // 加载游戏数据
-    pub fn load_game(&self, path: &str) -> Result<()> {
+
+    // 检测所有候选安装目录（已排序）
+    pub fn detect_games(&self) -> Vec<GameCandidate> {
+        let manager = self.game_manager.read().unwrap();
+        manager.detect_games()
+    }
+
+    // 设置用户配置的游戏搜索路径
+    pub fn set_search_paths(&self, paths: Vec<String>) {
         let mut manager = self.game_manager.write().unwrap();
-        manager.load_game(path)
+        manager.set_search_paths(paths.into_iter().map(Into::into).collect());
     }
-    
-    // 获取物品列表
-    pub fn get_items(&self) -> Vec<Item> {
+
+    // 列出游戏目录下的模块
+    pub fn list_modules(&self, game_path: &str) -> Result<Vec<Module>> {
+        GameManager::list_modules(game_path)
+    }
+
+    // 统计模块目录大小和修改时间（遍历整个目录，只在需要时调用）
+    pub fn module_stats(&self, module_path: &str) -> ModuleStats {
+        GameManager::module_stats(module_path)
+    }
+
+    // 加载游戏中指定模块的数据（模块为 Modules 下的目录名）
+    pub fn load_game(&self, path: &str, module: &str) -> Result<()> {
+        let mut manager = self.game_manager.write().unwrap();
+        manager.load_game(path, module)
+    }
+
+    // 当前打开的模块
+    pub fn current_module(&self) -> Option<String> {
         let manager = self.game_manager.read().unwrap();
-        manager.get_data()
-            .map(|data| data.items.clone())
-            .unwrap_or_default()
+        manager.get_game().map(|game| game.module.clone())
     }
-    
-    // 获取物品列表引用
-    pub fn with_items<R, F>(&self, f: F) -> R
-    where
-        F: FnOnce(&[Item]) -> R,
-    {
+
+    // 当前游戏的版本信息
+    pub fn get_edition(&self) -> Option<EditionInfo> {
         let manager = self.game_manager.read().unwrap();
-        match manager.get_data() {
-            Some(data) => f(&data.items),
-            None => f(&[]),
-        }
+        manager.get_game().map(|game| game.edition_info())
     }
-    
-    // 获取兵种列表
-    pub fn get_troops(&self) -> Vec<Troop> {
+
+    // 读取当前模块的 module.ini
+    pub fn load_module_ini(&self) -> Result<ModuleIni> {
         let manager = self.game_manager.read().unwrap();
-        manager.get_data()
-            .map(|data| data.troops.clone())
-            .unwrap_or_default()
+        manager.load_module_ini()
     }
-    
-    // 获取兵种列表引用
-    pub fn with_troops<R, F>(&self, f: F) -> R
-    where
-        F: FnOnce(&[Troop]) -> R,
-    {
+
+    // 写回当前模块的 module.ini
+    pub fn save_module_ini(&self, ini: &ModuleIni) -> Result<()> {
+        let manager = self.game_manager.read().unwrap();
+        manager.save_module_ini(ini)
+    }
+
+    // 设置解析模式
+    pub fn set_parse_mode(&self, mode: ParseMode) {
+        let mut manager = self.game_manager.write().unwrap();
+        manager.set_parse_mode(mode);
+    }
+
+    // 获取解析问题列表
+    pub fn get_warnings(&self) -> Vec<ParseWarning> {
+        let manager = self.game_manager.read().unwrap();
+        manager.get_warnings().to_vec()
+    }
+
+    // 校验所有队伍模板，返回 (模板 id, 问题) 列表
+    pub fn validate_party_templates(&self) -> Vec<(String, TemplateIssue)> {
+        let manager = self.game_manager.read().unwrap();
+        let Some(data) = manager.get_data() else {
+            return Vec::new();
+        };
+        data.party_templates.iter()
+            .flat_map(|template| {
+                template.validate(data.troops.len())
+                    .into_iter()
+                    .map(|issue| (template.id.clone(), issue))
+            })
+            .collect()
+    }
+
+    // 构建对话状态图
+    pub fn build_dialog_graph(&self) -> DialogGraph {
         let manager = self.game_manager.read().unwrap();
         match manager.get_data() {
-            Some(data) => f(&data.troops),
-            None => f(&[]),
+            Some(data) => DialogGraph::build(&data.dialogs, &data.dialog_states),
+            None => DialogGraph::default(),
         }
     }
-    
-    // 获取派系列表
-    pub fn get_factions(&self) -> Vec<Faction> {
+
+    // 查找孤立的对话状态（没有进入或没有后续的对话）
+    pub fn find_orphan_dialog_states(&self) -> Vec<OrphanState> {
+        self.build_dialog_graph().orphan_states()
+    }
+
+    // 查找无法到达的对话分支，返回对话 id
+    // 构建状态图和取对话 id 在同一个读锁内完成，避免中途数据被替换导致编号错位
+    pub fn find_unreachable_dialogs(&self) -> Vec<String> {
+        let manager = self.game_manager.read().unwrap();
+        let Some(data) = manager.get_data() else {
+            return Vec::new();
+        };
+        DialogGraph::build(&data.dialogs, &data.dialog_states)
+            .unreachable_dialogs(&data.dialogs)
+            .into_iter()
+            .map(|index| data.dialogs[index].id.clone())
+            .collect()
+    }
+
+    // 技能名称（按编号排列，找不到时显示编号）
+    pub fn skill_name(&self, skill_no: usize) -> String {
+        self.with_skills(|skills| {
+            skills.get(skill_no)
+                .map(|skill| skill.name.clone())
+                .unwrap_or_else(|| format!("#{}", skill_no))
+        })
+    }
+
+    // 皮肤 id 列表（按编号排列，用于选择兵种皮肤）
+    pub fn skin_names(&self) -> Vec<String> {
+        self.with_skins(|skins| skins.iter().map(|skin| skin.id.clone()).collect())
+    }
+
+    // 检查缺失的音频文件
+    pub fn find_missing_audio(&self) -> Vec<MissingAudio> {
+        let manager = self.game_manager.read().unwrap();
+        manager.find_missing_audio()
+    }
+
+    // 编辑器不认识、按原样保存的模块文件名
+    pub fn get_unknown_files(&self) -> Vec<String> {
         let manager = self.game_manager.read().unwrap();
         manager.get_data()
-            .map(|data| data.factions.clone())
+            .map(|data| data.unknown_files.keys().cloned().collect())
             .unwrap_or_default()
     }
-    
-    // 获取派系列表引用
-    pub fn with_factions<R, F>(&self, f: F) -> R
+
+    // 设置两个派系之间的关系（-1.0 ~ 1.0），关系矩阵保持对称
+    pub fn set_relation(&self, faction_no: usize, other_no: usize, value: f64) -> Result<()> {
+        let value = value.clamp(-1.0, 1.0);
+        self.update_file("factions.txt", |data| {
+            let faction_count = data.factions.len();
+            if faction_no >= faction_count || other_no >= faction_count {
+                return Err(anyhow::anyhow!("派系编号超出范围"));
+            }
+            for (row, column) in [(faction_no, other_no), (other_no, faction_no)] {
+                let relations = &mut data.factions[row].relations;
+                if relations.len() < faction_count {
+                    relations.resize(faction_count, 0.0);
+                }
+                relations[column] = value;
+            }
+            Ok(())
+        })
+    }
+
+    // 删除记录，记录被其他数据按编号引用时拒绝删除
+    pub fn remove_record(&self, kind: RecordKind, index: usize) -> Result<()> {
+        self.update_file(kind.file_name(), |data| {
+            check_removable(data, kind, index)?;
+            match kind {
+                RecordKind::Item => {
+                    data.items.remove(index);
+                }
+                RecordKind::Troop => {
+                    data.troops.remove(index);
+                }
+                RecordKind::Party => {
+                    data.parties.remove(index);
+                }
+                RecordKind::Scene => {
+                    data.scenes.remove(index);
+                }
+                RecordKind::Faction => {
+                    // 同时删除其他派系与它的关系
+                    data.factions.remove(index);
+                    for faction in &mut data.factions {
+                        if index < faction.relations.len() {
+                            faction.relations.remove(index);
+                        }
+                    }
+                }
+            }
+            Ok(())
+        })
+    }
+
+    // 按 id 修改一个物品，查找和写回在同一个写锁内完成，返回修改后的物品
+    pub fn update_item<F>(&self, id: &str, f: F) -> Result<Item>
     where
-        F: FnOnce(&[Faction]) -> R,
+        F: FnOnce(&mut Item),
     {
-        let manager = self.game_manager.read().unwrap();
-        match manager.get_data() {
-            Some(data) => f(&data.factions),
-            None => f(&[]),
-        }
+        self.update_file("item_kinds1.txt", |data| {
+            let item = data.items.iter_mut()
+                .find(|item| item.id == id)
+                .ok_or_else(|| anyhow::anyhow!("未找到物品 {}", id))?;
+            f(item);
+            Ok(item.clone())
+        })
     }
     
+    // 替换或新增派系，派系关系只通过 set_relation 修改，替换时保留编辑器中的关系以保持对称
+    pub fn put_faction(&self, index: Option<usize>, mut faction: Faction) -> Result<()> {
+        self.update_file("factions.txt", |data| {
+            if let Some(existing) = index.and_then(|index| data.factions.get(index)) {
+                faction.relations = existing.relations.clone();
+            }
+            put_record(&mut data.factions, index, faction, |faction| &faction.id)
+        })
+    }
+
+    // 修改已加载的游戏数据，成功后标记对应的数据文件需要重新生成
+    fn update_file<R, F>(&self, file_name: &str, f: F) -> Result<R>
+    where
+        F: FnOnce(&mut GameData) -> Result<R>,
+    {
+        let mut manager = self.game_manager.write().unwrap();
+        let data = manager.get_data_mut().ok_or_else(|| anyhow::anyhow!("没有加载的游戏数据"))?;
+        let result = f(data)?;
+        data.mark_changed(file_name);
+        Ok(result)
+    }
+
     // 保存数据
     pub fn save_data(&self) -> Result<()> {
-        let manager = self.game_manager.read().unwrap();
+        let mut manager = self.game_manager.write().unwrap();
         manager.save_data()
     }
-    
-    // 获取JSON格式的数据
-    pub fn get_items_json(&self) -> Result<String> {
-        self.with_items(|items| {
-            serde_json::to_string(items).map_err(|e| anyhow::anyhow!("序列化失败: {}", e))
-        })
+}
+
+// 替换编号为 index 的记录，index 为 None 时追加到末尾
+// 记录按编号被引用，所以按原编号定位而不是按 id；id 不能为空，也不能与其他记录重复
+fn put_record<T>(records: &mut Vec<T>, index: Option<usize>, record: T, id: impl Fn(&T) -> &str) -> Result<()> {
+    let record_id = id(&record);
+    if record_id.is_empty() {
+        return Err(anyhow::anyhow!("id 不能为空"));
     }
-    
-    pub fn get_troops_json(&self) -> Result<String> {
-        self.with_troops(|troops| {
-            serde_json::to_string(troops).map_err(|e| anyhow::anyhow!("序列化失败: {}", e))
-        })
+    if let Some(other) = records.iter().enumerate().position(|(i, r)| Some(i) != index && id(r) == record_id) {
+        return Err(anyhow::anyhow!("id {} 已被编号 {} 的记录使用", record_id, other));
     }
-    
-    pub fn get_factions_json(&self) -> Result<String> {
-        self.with_factions(|factions| {
-            serde_json::to_string(factions).map_err(|e| anyhow::anyhow!("序列化失败: {}", e))
-        })
+    match index {
+        Some(index) if index < records.len() => records[index] = record,
+        Some(index) => return Err(anyhow::anyhow!("编号 {} 超出范围（共 {} 条）", index, records.len())),
+        None => records.push(record),
+    }
+    Ok(())
+}
+
+// 按编号替换或新增单条记录，只修改这一条，不会用旧副本覆盖整张表
+macro_rules! record_writers {
+    ($($field:ident: $ty:ty, $file:literal => $put:ident;)*) => {
+        impl Editor {
+            $(
+                pub fn $put(&self, index: Option<usize>, record: $ty) -> Result<()> {
+                    self.update_file($file, |data| put_record(&mut data.$field, index, record, |record| &record.id))
+                }
+            )*
+        }
+    };
+}
+
+record_writers! {
+    items: Item, "item_kinds1.txt" => put_item;
+    troops: Troop, "troops.txt" => put_troop;
+    parties: Party, "parties.txt" => put_party;
+    scenes: Scene, "scenes.txt" => put_scene;
+    menus: GameMenu, "menus.txt" => put_menu;
+    presentations: Presentation, "presentations.txt" => put_presentation;
+    strings: GameString, "strings.txt" => put_string;
+    quests: Quest, "quests.txt" => put_quest;
+    info_pages: InfoPage, "info_pages.txt" => put_info_page;
+}
+
+// 各类数据的访问方法：get_x 返回副本，with_x 借用，set_x 整体替换，get_x_json 序列化
+macro_rules! data_accessors {
+    ($($field:ident: $ty:ty, $file:literal => $get:ident, $with:ident, $set:ident, $json:ident;)*) => {
+        impl Editor {
+            $(
+                pub fn $get(&self) -> Vec<$ty> {
+                    self.$with(|records| records.to_vec())
+                }
+
+                pub fn $with<R, F>(&self, f: F) -> R
+                where
+                    F: FnOnce(&[$ty]) -> R,
+                {
+                    let manager = self.game_manager.read().unwrap();
+                    match manager.get_data() {
+                        Some(data) => f(&data.$field),
+                        None => f(&[]),
+                    }
+                }
+
+                pub fn $set(&self, records: Vec<$ty>) -> Result<()> {
+                    self.update_file($file, |data| {
+                        data.$field = records;
+                        Ok(())
+                    })
+                }
+
+                pub fn $json(&self) -> Result<String> {
+                    self.$with(|records| {
+                        serde_json::to_string(records).map_err(|e| anyhow::anyhow!("序列化失败: {}", e))
+                    })
+                }
+            )*
+        }
+    };
+}
+
+data_accessors! {
+    // 物品
+    items: Item, "item_kinds1.txt" => get_items, with_items, set_items, get_items_json;
+    // 兵种
+    troops: Troop, "troops.txt" => get_troops, with_troops, set_troops, get_troops_json;
+    // 派系
+    factions: Faction, "factions.txt" => get_factions, with_factions, set_factions, get_factions_json;
+    // 队伍
+    parties: Party, "parties.txt" => get_parties, with_parties, set_parties, get_parties_json;
+    // 队伍模板
+    party_templates: PartyTemplate, "party_templates.txt" => get_party_templates, with_party_templates, set_party_templates, get_party_templates_json;
+    // 场景
+    scenes: Scene, "scenes.txt" => get_scenes, with_scenes, set_scenes, get_scenes_json;
+    // 地图图标
+    map_icons: MapIcon, "map_icons.txt" => get_map_icons, with_map_icons, set_map_icons, get_map_icons_json;
+    // 脚本
+    scripts: Script, "scripts.txt" => get_scripts, with_scripts, set_scripts, get_scripts_json;
+    // 简单触发器
+    simple_triggers: SimpleTrigger, "simple_triggers.txt" => get_simple_triggers, with_simple_triggers, set_simple_triggers, get_simple_triggers_json;
+    // 触发器
+    triggers: Trigger, "triggers.txt" => get_triggers, with_triggers, set_triggers, get_triggers_json;
+    // 任务模板
+    mission_templates: MissionTemplate, "mission_templates.txt" => get_mission_templates, with_mission_templates, set_mission_templates, get_mission_templates_json;
+    // 对话
+    dialogs: Dialog, "conversation.txt" => get_dialogs, with_dialogs, set_dialogs, get_dialogs_json;
+    // 字符串
+    strings: GameString, "strings.txt" => get_strings, with_strings, set_strings, get_strings_json;
+    // 任务
+    quests: Quest, "quests.txt" => get_quests, with_quests, set_quests, get_quests_json;
+    // 百科页面
+    info_pages: InfoPage, "info_pages.txt" => get_info_pages, with_info_pages, set_info_pages, get_info_pages_json;
+    // 技能
+    skills: Skill, "skills.txt" => get_skills, with_skills, set_skills, get_skills_json;
+    // 皮肤
+    skins: Skin, "skins.txt" => get_skins, with_skins, set_skins, get_skins_json;
+    // 声音
+    sounds: Sound, "sounds.txt" => get_sounds, with_sounds, set_sounds, get_sounds_json;
+    // 音乐
+    music: MusicTrack, "music.txt" => get_music, with_music, set_music, get_music_json;
+    // 场景物品
+    scene_props: SceneProp, "scene_props.txt" => get_scene_props, with_scene_props, set_scene_props, get_scene_props_json;
+    // 粒子系统
+    particle_systems: ParticleSystem, "particle_systems.txt" => get_particle_systems, with_particle_systems, set_particle_systems, get_particle_systems_json;
+    // 菜单
+    menus: GameMenu, "menus.txt" => get_menus, with_menus, set_menus, get_menus_json;
+    // 界面
+    presentations: Presentation, "presentations.txt" => get_presentations, with_presentations, set_presentations, get_presentations_json;
+    // 动态贴图材质
+    tableau_materials: TableauMaterial, "tableau_materials.txt" => get_tableau_materials, with_tableau_materials, set_tableau_materials, get_tableau_materials_json;
+    // 后期处理参数
+    postfx: PostFx, "postfx.txt" => get_postfx, with_postfx, set_postfx, get_postfx_json;
+    // 快速字符串
+    quick_strings: QuickString, "quick_strings.txt" => get_quick_strings, with_quick_strings, set_quick_strings, get_quick_strings_json;
+    // 动作
+    actions: Action, "actions.txt" => get_actions, with_actions, set_actions, get_actions_json;
+    // 全局变量名
+    variables: String, "variables.txt" => get_variables, with_variables, set_variables, get_variables_json;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn troop(id: &str) -> Troop {
+        Troop { id: id.to_string(), ..Troop::default() }
+    }
+
+    fn id(troop: &Troop) -> &str {
+        &troop.id
+    }
+
+    #[test]
+    fn test_put_record() {
+        let mut troops = vec![troop("trp_player"), troop("trp_a"), troop("trp_b")];
+
+        // 修改 id 后按原编号替换，不会追加重复记录
+        put_record(&mut troops, Some(1), troop("trp_renamed"), id).unwrap();
+        let ids: Vec<&str> = troops.iter().map(id).collect();
+        assert_eq!(ids, vec!["trp_player", "trp_renamed", "trp_b"]);
+        
+        assert!(put_record(&mut troops, Some(1), troop("trp_b"), id).unwrap_err().to_string().contains("编号 2"));
+        assert!(put_record(&mut troops, None, troop("trp_player"), id).is_err());
+        assert!(put_record(&mut troops, None, troop(""), id).is_err());
+        assert!(put_record(&mut troops, Some(5), troop("trp_c"), id).is_err());
+        put_record(&mut troops, None, troop("trp_c"), id).unwrap();
+        assert_eq!(troops.len(), 4);
     }
 }
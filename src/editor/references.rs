@@ -0,0 +1,217 @@
+// 数据表之间按编号的引用，用于检查记录能否删除
+
+use anyhow::Result;
+use crate::data::{GameData, SCENE_PASSAGE_EXIT};
+
+// 会被其他数据按编号引用的记录类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordKind {
+    Item,
+    Troop,
+    Faction,
+    Party,
+    Scene,
+}
+
+impl RecordKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            RecordKind::Item => "物品",
+            RecordKind::Troop => "兵种",
+            RecordKind::Faction => "派系",
+            RecordKind::Party => "队伍",
+            RecordKind::Scene => "场景",
+        }
+    }
+
+    // 记录所在的数据文件
+    pub fn file_name(self) -> &'static str {
+        match self {
+            RecordKind::Item => "item_kinds1.txt",
+            RecordKind::Troop => "troops.txt",
+            RecordKind::Faction => "factions.txt",
+            RecordKind::Party => "parties.txt",
+            RecordKind::Scene => "scenes.txt",
+        }
+    }
+
+    // 已加载的记录数
+    pub fn count(self, data: &GameData) -> usize {
+        match self {
+            RecordKind::Item => data.items.len(),
+            RecordKind::Troop => data.troops.len(),
+            RecordKind::Faction => data.factions.len(),
+            RecordKind::Party => data.parties.len(),
+            RecordKind::Scene => data.scenes.len(),
+        }
+    }
+}
+
+// 一处引用：引用方说明和被引用的编号
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordReference {
+    pub source: String,
+    pub index: usize,
+}
+
+// 收集数据表中对指定类型记录的引用
+// 脚本、对话、菜单中的操作数无法可靠区分类型，不在检查范围内
+pub fn find_references(data: &GameData, kind: RecordKind) -> Vec<RecordReference> {
+    let mut references = Vec::new();
+    let mut add = |source: String, index: i32| {
+        if index >= 0 {
+            references.push(RecordReference { source, index: index as usize });
+        }
+    };
+
+    match kind {
+        RecordKind::Item => {
+            for troop in &data.troops {
+                for slot in &troop.inventory {
+                    add(format!("兵种 {} 的装备", troop.id), slot.item);
+                }
+            }
+            for template in &data.mission_templates {
+                for spawn in &template.spawns {
+                    for &item in &spawn.item_overrides {
+                        add(format!("任务模板 {} 的出生点装备", template.id), item);
+                    }
+                }
+            }
+        }
+        RecordKind::Troop => {
+            for troop in &data.troops {
+                // 升级目标 0 表示不能升级
+                for upgrade in [troop.upgrade_1, troop.upgrade_2] {
+                    if upgrade > 0 {
+                        add(format!("兵种 {} 的升级目标", troop.id), upgrade);
+                    }
+                }
+            }
+            for party in &data.parties {
+                for stack in &party.stacks {
+                    add(format!("队伍 {} 的成员", party.id), stack.troop);
+                }
+            }
+            for template in &data.party_templates {
+                for stack in &template.stacks {
+                    add(format!("队伍模板 {} 的成员", template.id), stack.troop);
+                }
+            }
+            for scene in &data.scenes {
+                for &troop in &scene.chest_troops {
+                    add(format!("场景 {} 的储物箱", scene.id), troop);
+                }
+            }
+        }
+        RecordKind::Faction => {
+            for item in &data.items {
+                for &faction in &item.factions {
+                    add(format!("物品 {} 的派系列表", item.id), faction);
+                }
+            }
+            for troop in &data.troops {
+                add(format!("兵种 {}", troop.id), troop.faction);
+            }
+            for party in &data.parties {
+                add(format!("队伍 {}", party.id), party.faction);
+            }
+            for template in &data.party_templates {
+                add(format!("队伍模板 {}", template.id), template.faction);
+            }
+        }
+        RecordKind::Party => {
+            for party in &data.parties {
+                // AI 目标 0 表示没有目标
+                if party.ai_target > 0 {
+                    add(format!("队伍 {} 的 AI 目标", party.id), party.ai_target);
+                }
+            }
+        }
+        RecordKind::Scene => {
+            for scene in &data.scenes {
+                for &passage in &scene.passages {
+                    if passage > 0 && passage != SCENE_PASSAGE_EXIT {
+                        add(format!("场景 {} 的通道", scene.id), passage);
+                    }
+                }
+            }
+            for troop in &data.troops {
+                // 低 16 位为场景编号，高位为入口点，0 表示没有场景
+                let scene = (troop.scene & 0xffff) as i32;
+                if scene > 0 {
+                    add(format!("兵种 {} 所在的场景", troop.id), scene);
+                }
+            }
+        }
+    }
+    references
+}
+
+// 检查能否删除指定编号的记录
+// 删除后其后记录的编号都会前移，所以该记录或其后任一记录被引用时都不能删除
+pub fn check_removable(data: &GameData, kind: RecordKind, index: usize) -> Result<()> {
+    let count = kind.count(data);
+    if index >= count {
+        return Err(anyhow::anyhow!("{}编号 {} 超出范围（共 {} 个）", kind.label(), index, count));
+    }
+
+    match find_references(data, kind).into_iter().find(|reference| reference.index >= index) {
+        Some(reference) if reference.index == index => Err(anyhow::anyhow!(
+            "{}编号 {} 被{}引用，不能删除",
+            kind.label(), index, reference.source
+        )),
+        Some(reference) => Err(anyhow::anyhow!(
+            "删除后{}编号 {} 会前移，而它被{}引用，不能删除",
+            kind.label(), reference.index, reference.source
+        )),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Faction, Party, Troop};
+
+    fn troop(id: &str, faction: i32, upgrade: i32) -> Troop {
+        Troop {
+            id: id.to_string(),
+            faction,
+            upgrade_1: upgrade,
+            ..Troop::default()
+        }
+    }
+
+    #[test]
+    fn test_check_removable() {
+        let data = GameData {
+            factions: vec![Faction::default(), Faction::default(), Faction::default()],
+            troops: vec![troop("trp_player", 0, 0), troop("trp_a", 1, 0), troop("trp_b", 0, 1)],
+            ..GameData::default()
+        };
+
+        // 兵种 1 被 trp_b 作为升级目标引用
+        assert!(check_removable(&data, RecordKind::Troop, 1).unwrap_err().to_string().contains("trp_b"));
+        // 兵种 0 本身没被引用，但删除会让被引用的兵种 1 前移
+        assert!(check_removable(&data, RecordKind::Troop, 0).unwrap_err().to_string().contains("前移"));
+        assert!(check_removable(&data, RecordKind::Troop, 2).is_ok());
+
+        assert!(check_removable(&data, RecordKind::Faction, 1).is_err());
+        assert!(check_removable(&data, RecordKind::Faction, 2).is_ok());
+        assert!(check_removable(&data, RecordKind::Faction, 3).is_err());
+    }
+
+    #[test]
+    fn test_referenced_party_not_removable() {
+        let party = |id: &str, ai_target: i32| Party { id: id.to_string(), ai_target, ..Party::default() };
+        let data = GameData {
+            parties: vec![party("p_main_party", 0), party("p_town_1", 0), party("p_bandits", 1), party("p_temp", 0)],
+            ..GameData::default()
+        };
+
+        assert!(check_removable(&data, RecordKind::Party, 1).unwrap_err().to_string().contains("p_bandits"));
+        assert!(check_removable(&data, RecordKind::Party, 0).is_err());
+        assert!(check_removable(&data, RecordKind::Party, 2).is_ok());
+    }
+}
@@ -11,17 +11,22 @@ use crate::viewmodel::app_viewmodel::AppViewModel;
 
 slint::include_modules!();
 
+// 待执行的界面更新（按键去重）
+type PendingUpdates = HashMap<String, Box<dyn Fn() + Send + Sync>>;
+
 // UI状态批量更新管理器
 struct UiBatchUpdater {
-    pending_updates: Arc<RwLock<HashMap<String, Box<dyn Fn() + Send + Sync>>>>,
+    pending_updates: Arc<RwLock<PendingUpdates>>,
     last_update: Arc<RwLock<Instant>>,
+    debounce_duration: Duration,
 }
 
 impl UiBatchUpdater {
-    fn new() -> Self {
+    fn new(debounce_duration: Duration) -> Self {
         Self {
             pending_updates: Arc::new(RwLock::new(HashMap::new())),
             last_update: Arc::new(RwLock::new(Instant::now())),
+            debounce_duration,
         }
     }
     
@@ -56,7 +61,7 @@ impl UiBatchUpdater {
                 // 执行所有待处理的更新
                 let updates = {
                     let mut pending = pending_updates.write().await;
-                    let current_updates: HashMap<String, Box<dyn Fn() + Send + Sync>> = pending.drain().collect();
+                    let current_updates: PendingUpdates = pending.drain().collect();
                     current_updates
                 };
                 
@@ -66,10 +71,6 @@ impl UiBatchUpdater {
             }
         });
     }
-    
-    async fn start(&self, main_window: &MainWindow) {
-        // ...
-    }
 }
 
 // 启动UI界面
@@ -153,7 +154,7 @@ fn setup_app_callbacks(main_window: &MainWindow, app_viewmodel: &Arc<AppViewMode
 
     // 绑定重新检测游戏回调
     app_bridge.on_redetect_game({
-        let app_vm = Arc::clone(&app_viewmodel);
+        let app_vm = Arc::clone(app_viewmodel);
         move || {
             let _ = app_vm.redetect_game_command.execute();
         }
@@ -161,16 +162,19 @@ fn setup_app_callbacks(main_window: &MainWindow, app_viewmodel: &Arc<AppViewMode
 
     // 设置模块选择回调
     app_bridge.on_select_module({
-        let app_vm = Arc::clone(&app_viewmodel);
+        let app_vm = Arc::clone(app_viewmodel);
         let window_weak = main_window.as_weak();
         move |index| {
             let modules = app_vm.modules.get();
             if let Some(module) = modules.get(index as usize) {
-                app_vm.selected_module.set(module.name.clone());
+                // 加载时按目录名定位模块，界面显示模块名称
+                app_vm.selected_module.set(module.id.clone());
                 // 同时更新UI中的当前模块显示
                 if let Some(window) = window_weak.upgrade() {
                     window.global::<AppBridge>().set_current_module(module.name.clone().into());
                 }
+                // 选中后再统计目录大小，列出模块时不遍历目录
+                app_vm.load_module_stats(index as usize);
             }
         }
     });
@@ -320,7 +324,6 @@ fn setup_state_subscriptions(main_window: &MainWindow, app_viewmodel: &Arc<AppVi
     let app_vm = Arc::clone(app_viewmodel);
     // 创建批量更新管理器
     let batch_updater = Arc::new(UiBatchUpdater::new(Duration::from_millis(50)));
-    batch_updater.start(main_window.as_weak());
     
     // 订阅游戏路径变化
     app_vm.game_path.subscribe({
@@ -366,6 +369,28 @@ fn setup_state_subscriptions(main_window: &MainWindow, app_viewmodel: &Arc<AppVi
         }
     });
     
+    // 订阅游戏版本变化
+    app_vm.game_edition.subscribe({
+        let window_weak = window_weak.clone();
+        let batch_updater = Arc::clone(&batch_updater);
+        move |edition| {
+            let window_weak = window_weak.clone();
+            let edition = edition.clone();
+            let batch_updater = Arc::clone(&batch_updater);
+            
+            tokio::spawn(async move {
+                batch_updater.schedule_update(
+                    "game_edition".to_string(),
+                    move || {
+                        if let Some(window) = window_weak.upgrade() {
+                            window.global::<AppBridge>().set_game_edition(edition.clone().into());
+                        }
+                    }
+                ).await;
+            });
+        }
+    });
+    
     // 订阅加载状态变化
     app_vm.is_loading.subscribe({
         let window_weak = window_weak.clone();
@@ -466,7 +491,7 @@ fn setup_state_subscriptions(main_window: &MainWindow, app_viewmodel: &Arc<AppVi
             if let Some(window) = window_weak.upgrade() {
                 let ui_modules: Vec<slint::StandardListViewItem> = modules
                     .iter()
-                    .map(|module| slint::StandardListViewItem::from(slint::SharedString::from(module.name.clone())))
+                    .map(|module| slint::StandardListViewItem::from(slint::SharedString::from(module.display_label())))
                     .collect();
                 window.global::<AppBridge>().set_modules(slint::ModelRc::new(slint::VecModel::from(ui_modules)));
             }
@@ -490,11 +515,11 @@ fn setup_state_subscriptions(main_window: &MainWindow, app_viewmodel: &Arc<AppVi
             if let Some(window) = window_weak.upgrade() {
                 if let Some(item) = item_option {
                     window.global::<AppBridge>().set_selected_item_name(item.name.clone().into());
-                    window.global::<AppBridge>().set_selected_item_type(item.item_type.clone().into());
+                    window.global::<AppBridge>().set_selected_item_type(item.item_type().as_str().into());
                     window.global::<AppBridge>().set_selected_item_price(item.price);
-                    window.global::<AppBridge>().set_selected_item_weight(item.weight);
-                    window.global::<AppBridge>().set_selected_item_damage(item.damage);
-                    window.global::<AppBridge>().set_selected_item_armor(item.armor);
+                    window.global::<AppBridge>().set_selected_item_weight(item.weight as f32);
+                    window.global::<AppBridge>().set_selected_item_damage(item.damage());
+                    window.global::<AppBridge>().set_selected_item_armor(item.armor());
                 } else {
                     // 清空选中项数据
                     window.global::<AppBridge>().set_selected_item_name("".into());
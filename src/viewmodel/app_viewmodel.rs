@@ -1,7 +1,7 @@
 // 应用程序主ViewModel
 
 use std::sync::Arc;
-use crate::data::{Item, Module};
+use crate::data::{GameCandidate, Item, ItemType, Module, ParseMode, summarize_warnings};
 use crate::editor::Editor;
 use anyhow::Result;
 use crate::viewmodel::{
@@ -19,6 +19,28 @@ pub enum AppState {
     Editing,      // 编辑状态
 }
 
+// 错误信息中最多列出的解析问题数
+const MAX_SHOWN_WARNINGS: usize = 10;
+
+fn parse_mode(strict: bool) -> ParseMode {
+    if strict {
+        ParseMode::Strict
+    } else {
+        ParseMode::Lenient
+    }
+}
+
+// 界面中的小数为 f32，直接转成 f64 会带出二进制误差（2.3 变成 2.299999952316284），
+// 写入文件时就不再是用户输入的值；按 f32 的最短十进制表示转成文本再解析为 f64
+fn f32_to_f64(value: f32) -> f64 {
+    value.to_string().parse().unwrap_or(f64::from(value))
+}
+
+// 已加载游戏的版本说明
+fn edition_label(editor: &Editor) -> String {
+    editor.get_edition().map(|edition| edition.label()).unwrap_or_default()
+}
+
 // 应用程序主ViewModel
 pub struct AppViewModel {
     base: BaseViewModelImpl,
@@ -34,7 +56,11 @@ pub struct AppViewModel {
     pub error_message: Observable<Option<String>>,
     pub current_page: Observable<String>,
     pub current_module: Observable<String>,
+    pub game_edition: Observable<String>, // 已加载游戏的版本说明
     pub data_loaded: Observable<bool>,
+    pub strict_parsing: Observable<bool>,
+    pub game_candidates: Observable<Vec<GameCandidate>>, // 检测到的安装目录（已排序）
+    pub game_search_paths: Observable<Vec<String>>,      // 用户配置的搜索路径
     
     // 物品编辑器相关
     pub items: Observable<Vec<Item>>,
@@ -64,13 +90,23 @@ impl AppViewModel {
         let error_message = Observable::with_debounce(None, 100);
         let current_page = Observable::with_debounce("startup".to_string(), 50);
         let current_module = Observable::with_debounce("Native".to_string(), 50);
+        let game_edition = Observable::with_debounce(String::new(), 50);
         let data_loaded = Observable::with_debounce(false, 50);
-        
+        let strict_parsing = Observable::with_debounce(false, 50);
+        let game_candidates = Observable::with_debounce(Vec::new(), 50);
+        let game_search_paths: Observable<Vec<String>> = Observable::with_debounce(Vec::new(), 100);
+
+        // 搜索路径变化时同步到编辑器
+        let editor_clone = Arc::clone(&editor);
+        game_search_paths.subscribe(move |paths| {
+            editor_clone.set_search_paths(paths.clone());
+        });
+
         // 物品编辑器相关
         let items = Observable::with_debounce(Vec::new(), 100);
         let selected_item = Observable::with_debounce(None, 50);
         let selected_item_id = Observable::with_debounce(String::new(), 50);
-        
+
         // 模块相关
         let modules = Observable::with_debounce(Vec::new(), 100);
         let selected_module = Observable::with_debounce("Native".to_string(), 50);
@@ -113,39 +149,9 @@ impl AppViewModel {
                         game_path_clone2.set(path.clone());
                         is_game_valid_clone2.set(true);
                         app_state_clone2.set(AppState::GameDetected);
-                        
+
                         // 自动扫描模块
-                        let modules_path = std::path::Path::new(&path).join("Modules");
-                        if modules_path.exists() {
-                            let mut found_modules = vec![
-                                Module {
-                                    id: "Native".to_string(),
-                                    name: "原版剧本".to_string(),
-                                    path: modules_path.join("Native").to_string_lossy().to_string(),
-                                    is_native: true,
-                                }
-                            ];
-                            
-                            if let Ok(entries) = std::fs::read_dir(&modules_path) {
-                                for entry in entries.flatten() {
-                                    if let Ok(file_type) = entry.file_type() {
-                                        if file_type.is_dir() {
-                                            let dir_name = entry.file_name().to_string_lossy().to_string();
-                                            if dir_name != "Native" {
-                                                let module_ini = entry.path().join("module.ini");
-                                                if module_ini.exists() {
-                                                    found_modules.push(Module {
-                                                        id: dir_name.clone(),
-                                                        name: dir_name.clone(),
-                                                        path: entry.path().to_string_lossy().to_string(),
-                                                        is_native: false,
-                                                    });
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
-                            }
+                        if let Ok(found_modules) = editor_clone2.list_modules(&path) {
                             modules_clone.set(found_modules);
                         }
                         
@@ -165,6 +171,11 @@ impl AppViewModel {
         let game_path_for_load = game_path.clone();
         let app_state_clone = app_state.clone();
         let game_path_for_can_execute = game_path.clone();
+        let strict_parsing_for_load = strict_parsing.clone();
+        let error_message_for_load = error_message.clone();
+        let selected_module_for_load = selected_module.clone();
+        let current_module_for_load = current_module.clone();
+        let game_edition_for_load = game_edition.clone();
         
         let load_game_command = AsyncCommand::new(
             move || -> Result<()> {
@@ -173,7 +184,12 @@ impl AppViewModel {
                     return Err(anyhow::anyhow!("游戏路径为空"));
                 }
                 
-                editor_clone.load_game(&path)?;
+                let module = selected_module_for_load.get();
+                editor_clone.set_parse_mode(parse_mode(strict_parsing_for_load.get()));
+                editor_clone.load_game(&path, &module)?;
+                current_module_for_load.set(module);
+                game_edition_for_load.set(edition_label(&editor_clone));
+                error_message_for_load.set(summarize_warnings(&editor_clone.get_warnings(), MAX_SHOWN_WARNINGS));
                 app_state_clone.set(AppState::GameLoaded);
                 Ok(())
             },
@@ -190,7 +206,7 @@ impl AppViewModel {
             },
             || true
         );
-
+        
         Ok(Self {
             base,
             editor,
@@ -203,7 +219,11 @@ impl AppViewModel {
             error_message,
             current_page,
             current_module,
+            game_edition,
             data_loaded,
+            strict_parsing,
+            game_candidates,
+            game_search_paths,
             items,
             selected_item,
             selected_item_id,
@@ -219,7 +239,7 @@ impl AppViewModel {
     // 设置游戏路径（从UI调用）
     pub fn set_game_path(&self, path: String) -> Result<()> {
         self.game_path.set(path.clone());
-        
+
         // 验证路径
         let is_valid = !path.is_empty() && std::path::Path::new(&path).exists();
         self.is_game_valid.set(is_valid);
@@ -232,11 +252,14 @@ impl AppViewModel {
         
         Ok(())
     }
-    
+
     // 检测游戏路径
     pub fn detect_game_path(&self) -> Option<String> {
-        match self.editor.detect_game() {
-            Ok(Some(path)) => {
+        let candidates = self.editor.detect_games();
+        let best = candidates.first().map(|candidate| candidate.path.to_string_lossy().to_string());
+        self.game_candidates.set(candidates);
+        match best {
+            Some(path) => {
                 self.game_path.set(path.clone());
                 self.game_detected.set(true);
                 self.is_game_valid.set(true);
@@ -244,7 +267,7 @@ impl AppViewModel {
                 self.status_message.set("游戏检测完成".to_string());
                 Some(path)
             }
-            _ => {
+            None => {
                 self.game_detected.set(false);
                 self.is_game_valid.set(false);
                 self.error_message.set(Some("未找到游戏安装路径".to_string()));
@@ -252,16 +275,19 @@ impl AppViewModel {
             }
         }
     }
-    
+
     // 加载游戏数据
     pub fn load_game_data(&self, game_path: String, module_name: String) -> Result<()> {
         self.is_loading.set(true);
         self.status_message.set("正在加载游戏数据...".to_string());
+        self.editor.set_parse_mode(parse_mode(self.strict_parsing.get()));
         
-        match self.editor.load_game(&game_path) {
+        match self.editor.load_game(&game_path, &module_name) {
             Ok(_) => {
+                self.error_message.set(summarize_warnings(&self.editor.get_warnings(), MAX_SHOWN_WARNINGS));
                 self.data_loaded.set(true);
                 self.current_module.set(module_name);
+                self.game_edition.set(edition_label(&self.editor));
                 self.app_state.set(AppState::GameLoaded);
                 self.status_message.set("游戏数据加载完成".to_string());
                 self.is_loading.set(false);
@@ -317,42 +343,17 @@ impl AppViewModel {
         if !game_path.is_empty() {
             self.is_loading.set(true);
             self.status_message.set("正在加载游戏数据...".to_string());
-            
-            // 加载物品数据
-            match self.editor.load_game(&game_path) {
+            self.editor.set_parse_mode(parse_mode(self.strict_parsing.get()));
+
+            // 加载所选模块的数据
+            let module = self.selected_module.get();
+            match self.editor.load_game(&game_path, &module) {
                 Ok(_) => {
-                    // 创建示例物品数据（暂时用于演示）
-                    let sample_items = vec![
-                        Item {
-                            id: "itm_sword_medieval_a".to_string(),
-                            name: "中世纪剑".to_string(),
-                            item_type: "武器".to_string(),
-                            price: 150,
-                            weight: 2.5,
-                            damage: 30,
-                            armor: 0,
-                        },
-                        Item {
-                            id: "itm_leather_armor".to_string(),
-                            name: "皮甲".to_string(),
-                            item_type: "护甲".to_string(),
-                            price: 80,
-                            weight: 5.0,
-                            damage: 0,
-                            armor: 15,
-                        },
-                        Item {
-                            id: "itm_war_horse".to_string(),
-                            name: "战马".to_string(),
-                            item_type: "坐骑".to_string(),
-                            price: 500,
-                            weight: 0.0,
-                            damage: 0,
-                            armor: 0,
-                        },
-                    ];
-                    self.items.set(sample_items);
+                    self.error_message.set(summarize_warnings(&self.editor.get_warnings(), MAX_SHOWN_WARNINGS));
+                    self.items.set(self.editor.get_items());
                     self.data_loaded.set(true);
+                    self.current_module.set(module);
+                    self.game_edition.set(edition_label(&self.editor));
                     self.status_message.set("游戏数据加载完成".to_string());
                 }
                 Err(e) => {
@@ -366,43 +367,36 @@ impl AppViewModel {
     // 选择物品
     pub fn select_item(&self, item_id: String) {
         self.selected_item_id.set(item_id.clone());
-        
-        // 使用with_value避免克隆整个items列表
-        self.items.with_value(|items| {
-            if let Some(item) = items.iter().find(|item| item.id == item_id) {
-                self.selected_item.set(Some(item.clone()));
-            }
-        });
+
+        // 从编辑器读取，物品可能已在物品编辑器中修改
+        let item = self.editor.with_items(|items| items.iter().find(|item| item.id == item_id).cloned());
+        if item.is_some() {
+            self.selected_item.set(item);
+        }
     }
 
-    // 保存物品修改
+    // 保存物品修改（参数与界面回调 save-item 一一对应）
+    // 只修改编辑器中的这一个物品，再刷新列表，不会覆盖物品编辑器等其他地方的修改
+    #[allow(clippy::too_many_arguments)]
     pub fn save_item(&self, id: String, name: String, item_type: String, price: f32, weight: f32, damage: f32, armor: f32) -> Result<()> {
-        let updated_item = Item {
-            id: id.clone(),
-            name,
-            item_type,
-            price: price as i32,
-            weight,
-            damage: damage as i32,
-            armor: armor as i32,
-        };
-        
-        // 使用update方法避免完整克隆
-        let mut found = false;
-        self.items.update(|items| {
-            if let Some(item) = items.iter_mut().find(|item| item.id == id) {
-                *item = updated_item.clone();
-                found = true;
+        let item = self.editor.update_item(&id, |item| {
+            item.name = name;
+            if let Some(item_type) = ItemType::from_name(&item_type) {
+                item.set_item_type(item_type);
             }
-        });
+            item.price = price as i32;
+            // 只在数值被修改时写回，未修改的重量保持文件中的原值
+            if weight != item.weight as f32 {
+                item.weight = f32_to_f64(weight);
+            }
+            item.set_damage(damage as i32);
+            item.body_armor = armor as i32;
+        })?;
         
-        if found {
-            self.selected_item.set(Some(updated_item));
-            self.status_message.set("物品修改已保存".to_string());
-            Ok(())
-        } else {
-            Err(anyhow::anyhow!("未找到指定的物品"))
-        }
+        self.items.set(self.editor.get_items());
+        self.selected_item.set(Some(item));
+        self.status_message.set("物品修改已保存".to_string());
+        Ok(())
     }
 
     // 扫描游戏模块
@@ -411,46 +405,24 @@ impl AppViewModel {
         if game_path.is_empty() {
             return Err(anyhow::anyhow!("游戏路径为空"));
         }
+        
+        self.modules.set(self.editor.list_modules(&game_path)?);
+        Ok(())
+    }
 
-        let modules_path = std::path::Path::new(&game_path).join("Modules");
-        if !modules_path.exists() {
-            return Err(anyhow::anyhow!("未找到Modules目录"));
-        }
-
-        let mut modules = vec![
-            Module {
-                id: "Native".to_string(),
-                name: "原版剧本".to_string(),
-                path: modules_path.join("Native").to_string_lossy().to_string(),
-                is_native: true,
-            }
-        ];
-
-        // 扫描其他模块
-        if let Ok(entries) = std::fs::read_dir(&modules_path) {
-            for entry in entries.flatten() {
-                if let Ok(file_type) = entry.file_type() {
-                    if file_type.is_dir() {
-                        let dir_name = entry.file_name().to_string_lossy().to_string();
-                        if dir_name != "Native" {
-                            // 检查是否有module.ini文件
-                            let module_ini = entry.path().join("module.ini");
-                            if module_ini.exists() {
-                                modules.push(Module {
-                                    id: dir_name.clone(),
-                                    name: dir_name.clone(),
-                                    path: entry.path().to_string_lossy().to_string(),
-                                    is_native: false,
-                                });
-                            }
-                        }
-                    }
-                }
+    // 统计选中模块的目录大小（遍历整个目录，每个模块只统计一次）
+    pub fn load_module_stats(&self, index: usize) {
+        let Some(path) = self.modules.with_value(|modules| {
+            modules.get(index).filter(|module| module.stats.is_none()).map(|module| module.path.clone())
+        }) else {
+            return;
+        };
+        let stats = self.editor.module_stats(&path);
+        self.modules.update(|modules| {
+            if let Some(module) = modules.get_mut(index) {
+                module.stats = Some(stats);
             }
-        }
-
-        self.modules.set(modules);
-        Ok(())
+        });
     }
 
     // 获取物品列表
@@ -467,10 +439,18 @@ impl AppViewModel {
         if self.data_loaded.get() {
             self.is_loading.set(true);
             self.status_message.set("正在保存游戏数据...".to_string());
-            
-            // 这里应该调用实际的数据保存逻辑
-            // 暂时模拟保存成功
-            self.status_message.set("游戏数据保存完成".to_string());
+
+            // 各编辑器的修改已在编辑时写回，这里只需保存
+            match self.editor.save_data() {
+                Ok(_) => {
+                    self.error_message.set(None);
+                    self.status_message.set("游戏数据保存完成".to_string());
+                }
+                Err(e) => {
+                    self.error_message.set(Some(format!("保存失败: {}", e)));
+                    self.status_message.set("游戏数据保存失败".to_string());
+                }
+            }
             self.is_loading.set(false);
         }
     }
@@ -494,7 +474,7 @@ impl AppViewModel {
 impl BaseViewModel for AppViewModel {
     fn initialize(&self) -> Result<()> {
         self.base.initialize()?;
-        
+
         // 尝试自动检测游戏
         if let Err(e) = self.detect_game_command.execute() {
             self.base.set_status(Some(format!("自动检测失败: {}", e)));
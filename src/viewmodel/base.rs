@@ -85,7 +85,7 @@ pub trait SelectableViewModel<T>: BaseViewModel {
 }
 
 // 通用的ViewModel状态
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct ViewModelState {
     pub is_loading: bool,
     pub is_dirty: bool,
@@ -93,16 +93,6 @@ pub struct ViewModelState {
     pub status_message: Option<String>,
 }
 
-impl Default for ViewModelState {
-    fn default() -> Self {
-        Self {
-            is_loading: false,
-            is_dirty: false,
-            error_message: None,
-            status_message: None,
-        }
-    }
-}
 
 // 通用ViewModel基础实现
 #[derive(Clone)]
@@ -110,6 +100,12 @@ pub struct BaseViewModelImpl {
     pub state: Observable<ViewModelState>,
 }
 
+impl Default for BaseViewModelImpl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl BaseViewModelImpl {
     pub fn new() -> Self {
         Self {
@@ -0,0 +1,325 @@
+// 菜单与界面编辑器ViewModel（menus.txt 与 presentations.txt）
+
+use std::sync::Arc;
+use anyhow::Result;
+use crate::editor::Editor;
+use crate::data::{GameMenu, Presentation};
+use super::{BaseViewModel, BaseViewModelImpl, AsyncCommand, EditableViewModel, LoadableViewModel, SearchableViewModel, SelectableViewModel, EditSession, ListRecord, observable::{Observable, Command}};
+
+// 正在编辑的菜单或界面（按原编号写回，新建时追加）
+#[derive(Debug, Clone, PartialEq)]
+pub enum MenuEdit {
+    Menu(GameMenu),
+    Presentation(Presentation),
+}
+
+// 菜单按 id、文本和选项文本搜索
+impl ListRecord for GameMenu {
+    fn record_id(&self) -> &str {
+        &self.id
+    }
+
+    fn matches_query(&self, query: &str) -> bool {
+        query.is_empty() ||
+            self.id.to_lowercase().contains(query) ||
+            self.text.to_lowercase().contains(query) ||
+            self.options.iter().any(|option| {
+                option.id.to_lowercase().contains(query) ||
+                    option.text.to_lowercase().contains(query)
+            })
+    }
+}
+
+// 界面按 id 搜索
+impl ListRecord for Presentation {
+    fn record_id(&self) -> &str {
+        &self.id
+    }
+
+    fn matches_query(&self, query: &str) -> bool {
+        query.is_empty() || self.id.to_lowercase().contains(query)
+    }
+}
+
+// 菜单与界面编辑器ViewModel
+pub struct MenuViewModel {
+    base: BaseViewModelImpl,
+    editor: Arc<Editor>,
+    
+    // 数据
+    pub menus: Observable<Vec<GameMenu>>,
+    pub presentations: Observable<Vec<Presentation>>,
+    pub filtered_menus: Observable<Vec<GameMenu>>,
+    pub filtered_presentations: Observable<Vec<Presentation>>,
+    pub selected_menu: Observable<Option<GameMenu>>,
+    
+    // 搜索
+    pub search_query: Observable<String>,
+    
+    // 编辑状态
+    pub edit: EditSession<MenuEdit>,
+    
+    // 命令
+    pub load_menus_command: AsyncCommand,
+    pub save_command: Command,
+    pub add_menu_command: Command,
+    pub add_presentation_command: Command,
+    pub search_command: Command,
+}
+
+impl MenuViewModel {
+    pub fn new(editor: Arc<Editor>) -> Result<Self> {
+        let base = BaseViewModelImpl::new();
+        let menus: Observable<Vec<GameMenu>> = Observable::new(Vec::new());
+        let presentations: Observable<Vec<Presentation>> = Observable::new(Vec::new());
+        let filtered_menus = Observable::new(Vec::new());
+        let filtered_presentations = Observable::new(Vec::new());
+        let selected_menu: Observable<Option<GameMenu>> = Observable::new(None);
+        let search_query = Observable::new(String::new());
+        let edit: EditSession<MenuEdit> = EditSession::new(&base);
+
+        // 搜索命令（菜单和界面共用搜索词）
+        let search_query_clone = search_query.clone();
+        let menus_clone = menus.clone();
+        let presentations_clone = presentations.clone();
+        let filtered_menus_clone = filtered_menus.clone();
+        let filtered_presentations_clone = filtered_presentations.clone();
+        
+        let search_command = Command::new(
+            move || -> Result<()> {
+                let query = search_query_clone.get().to_lowercase();
+                
+                let menus: Vec<GameMenu> = menus_clone.get().into_iter()
+                    .filter(|menu| menu.matches_query(&query))
+                    .collect();
+                filtered_menus_clone.set(menus);
+                
+                let presentations: Vec<Presentation> = presentations_clone.get().into_iter()
+                    .filter(|presentation| presentation.matches_query(&query))
+                    .collect();
+                filtered_presentations_clone.set(presentations);
+                Ok(())
+            },
+            || true
+        );
+
+        // 加载菜单命令
+        let editor_clone = Arc::clone(&editor);
+        let menus_clone = menus.clone();
+        let presentations_clone = presentations.clone();
+        let search_command_clone = search_command.clone();
+        let base_clone = base.clone();
+        
+        let load_menus_command = AsyncCommand::new(
+            move || -> Result<()> {
+                base_clone.set_loading(true);
+                base_clone.clear_messages();
+                
+                let loaded_menus = editor_clone.get_menus();
+                let loaded_presentations = editor_clone.get_presentations();
+                let status = format!(
+                    "已加载 {} 个菜单, {} 个界面",
+                    loaded_menus.len(),
+                    loaded_presentations.len()
+                );
+                menus_clone.set(loaded_menus);
+                presentations_clone.set(loaded_presentations);
+                
+                base_clone.set_loading(false);
+                base_clone.set_status(Some(status));
+                search_command_clone.execute()
+            },
+            || true
+        );
+
+        // 保存命令（写回编辑器）
+        let editor_clone = Arc::clone(&editor);
+        let menus_clone = menus.clone();
+        let presentations_clone = presentations.clone();
+        let selected_menu_clone = selected_menu.clone();
+        let edit_clone = edit.clone();
+        let search_command_clone = search_command.clone();
+        let base_clone = base.clone();
+        
+        let save_command = AsyncCommand::new(
+            move || -> Result<()> {
+                let Some(edit) = edit_clone.record.get() else {
+                    return Err(anyhow::anyhow!("没有要保存的菜单"));
+                };
+                
+                match edit {
+                    MenuEdit::Menu(menu) => {
+                        if menu.id.is_empty() {
+                            return Err(anyhow::anyhow!("菜单 id 不能为空"));
+                        }
+                        editor_clone.put_menu(edit_clone.index.get(), menu.clone())?;
+                        menus_clone.set(editor_clone.get_menus());
+                        selected_menu_clone.set(Some(menu));
+                    }
+                    MenuEdit::Presentation(presentation) => {
+                        if presentation.id.is_empty() {
+                            return Err(anyhow::anyhow!("界面 id 不能为空"));
+                        }
+                        editor_clone.put_presentation(edit_clone.index.get(), presentation)?;
+                        presentations_clone.set(editor_clone.get_presentations());
+                    }
+                }
+                
+                edit_clone.finish();
+                base_clone.set_status(Some("菜单保存成功".to_string()));
+                search_command_clone.execute()
+            },
+            edit.can_save()
+        );
+
+        // 添加菜单和界面命令
+        let add_menu_command = edit.add_command(|| MenuEdit::Menu(GameMenu::default()));
+        let add_presentation_command = edit.add_command(|| MenuEdit::Presentation(Presentation::default()));
+
+        // 设置搜索查询变化时自动搜索
+        let search_command_clone = search_command.clone();
+        search_query.subscribe(move |_| {
+            let _ = search_command_clone.execute();
+        });
+        
+        Ok(Self {
+            base,
+            editor,
+            menus,
+            presentations,
+            filtered_menus,
+            filtered_presentations,
+            selected_menu,
+            search_query,
+            edit,
+            load_menus_command,
+            save_command,
+            add_menu_command,
+            add_presentation_command,
+            search_command,
+        })
+    }
+
+    // 开始编辑菜单
+    pub fn start_edit_menu(&self, menu: &GameMenu) -> Result<()> {
+        let index = self.menus.with_value(|menus| menus.iter().position(|m| m.id == menu.id));
+        self.edit.start_at(index, MenuEdit::Menu(menu.clone()))
+    }
+
+    // 开始编辑界面
+    pub fn start_edit_presentation(&self, presentation: &Presentation) -> Result<()> {
+        let index = self.presentations.with_value(|list| list.iter().position(|p| p.id == presentation.id));
+        self.edit.start_at(index, MenuEdit::Presentation(presentation.clone()))
+    }
+
+    // 更新编辑中的菜单或界面
+    pub fn update_edit_item<F>(&self, updater: F) -> Result<()>
+    where
+        F: FnOnce(&mut MenuEdit),
+    {
+        self.edit.update(updater)
+    }
+
+    // 删除菜单
+    pub fn delete_menu(&self, id: &str) -> Result<()> {
+        if !self.menus.with_value(|menus| menus.iter().any(|menu| menu.id == id)) {
+            return Err(anyhow::anyhow!("菜单 {} 不存在", id));
+        }
+        self.menus.update(|menus| menus.retain(|menu| menu.id != id));
+        if self.selected_menu.with_value(|menu| menu.as_ref().is_some_and(|menu| menu.id == id)) {
+            self.selected_menu.set(None);
+        }
+        self.editor.set_menus(self.menus.get())?;
+        self.search_command.execute()
+    }
+
+    // 删除界面
+    pub fn delete_presentation(&self, id: &str) -> Result<()> {
+        if !self.presentations.with_value(|list| list.iter().any(|presentation| presentation.id == id)) {
+            return Err(anyhow::anyhow!("界面 {} 不存在", id));
+        }
+        self.presentations.update(|list| list.retain(|presentation| presentation.id != id));
+        self.editor.set_presentations(self.presentations.get())?;
+        self.search_command.execute()
+    }
+
+    // 菜单 id 列表（用于选择跳转目标）
+    pub fn get_menu_ids(&self) -> Vec<String> {
+        self.menus.with_value(|menus| menus.iter().map(|menu| menu.id.clone()).collect())
+    }
+}
+
+impl BaseViewModel for MenuViewModel {
+    fn initialize(&self) -> Result<()> {
+        self.base.initialize()?;
+        self.load_menus_command.execute()
+    }
+
+    fn cleanup(&self) -> Result<()> {
+        self.base.cleanup()
+    }
+
+    fn has_unsaved_changes(&self) -> bool {
+        self.base.has_unsaved_changes()
+    }
+}
+
+impl EditableViewModel for MenuViewModel {
+    fn save(&self) -> Result<()> {
+        self.save_command.execute()
+    }
+
+    fn cancel(&self) -> Result<()> {
+        self.edit.finish();
+        Ok(())
+    }
+
+    fn reset(&self) -> Result<()> {
+        self.cancel()?;
+        self.selected_menu.set(None);
+        self.search_query.set(String::new());
+        Ok(())
+    }
+}
+
+impl LoadableViewModel for MenuViewModel {
+    fn load(&self) -> Result<()> {
+        self.load_menus_command.execute()
+    }
+
+    fn is_loading(&self) -> bool {
+        self.load_menus_command.is_executing() || self.save_command.is_executing()
+    }
+}
+
+impl SearchableViewModel for MenuViewModel {
+    fn search(&self, query: &str) -> Result<()> {
+        self.search_query.set(query.to_string());
+        Ok(())
+    }
+
+    fn clear_search(&self) -> Result<()> {
+        self.search_query.set(String::new());
+        Ok(())
+    }
+
+    fn get_search_query(&self) -> String {
+        self.search_query.get()
+    }
+}
+
+impl SelectableViewModel<GameMenu> for MenuViewModel {
+    fn select_item(&self, item: &GameMenu) -> Result<()> {
+        self.selected_menu.set(Some(item.clone()));
+        Ok(())
+    }
+
+    fn clear_selection(&self) -> Result<()> {
+        self.selected_menu.set(None);
+        Ok(())
+    }
+
+    fn get_selected_item(&self) -> Option<GameMenu> {
+        self.selected_menu.get()
+    }
+}
@@ -2,13 +2,27 @@
 
 pub mod base;
 pub mod observable;
+pub mod record_list;
 pub mod app_viewmodel;
 pub mod item_viewmodel;
 pub mod troop_viewmodel;
 pub mod faction_viewmodel;
+pub mod party_viewmodel;
+pub mod scene_viewmodel;
+pub mod trigger_viewmodel;
+pub mod text_viewmodel;
+pub mod menu_viewmodel;
+pub mod module_settings_viewmodel;
 
 pub use base::{BaseViewModel, BaseViewModelImpl, AsyncCommand, EditableViewModel, LoadableViewModel, SearchableViewModel, SelectableViewModel};
+pub use record_list::*;
 pub use app_viewmodel::*;
 pub use item_viewmodel::*;
 pub use troop_viewmodel::*;
 pub use faction_viewmodel::*;
+pub use party_viewmodel::*;
+pub use scene_viewmodel::*;
+pub use trigger_viewmodel::*;
+pub use text_viewmodel::*;
+pub use menu_viewmodel::*;
+pub use module_settings_viewmodel::*;
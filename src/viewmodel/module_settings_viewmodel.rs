@@ -0,0 +1,227 @@
+// 模块设置ViewModel（module.ini）
+
+use std::sync::Arc;
+use anyhow::Result;
+use crate::editor::Editor;
+use crate::data::{ModuleIni, IniValueKind, module_ini_key_kind, MODULE_INI_KEYS, LOAD_RESOURCE, LOAD_MOD_RESOURCE};
+use super::{BaseViewModel, BaseViewModelImpl, AsyncCommand, EditableViewModel, LoadableViewModel, observable::Observable};
+
+// 单个设置项（列表键 load_resource/load_mod_resource 单独显示）
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModuleSetting {
+    pub key: String,
+    pub value: String,
+    pub kind: IniValueKind,
+    pub known: bool, // 是否为已知键，未知键按文本编辑
+}
+
+// 模块设置ViewModel
+pub struct ModuleSettingsViewModel {
+    base: BaseViewModelImpl,
+    editor: Arc<Editor>,
+    
+    // 数据
+    pub ini: Observable<Option<ModuleIni>>,
+    pub settings: Observable<Vec<ModuleSetting>>,
+    pub resources: Observable<Vec<String>>,
+    pub mod_resources: Observable<Vec<String>>,
+    
+    // 命令
+    pub load_command: AsyncCommand,
+    pub save_command: AsyncCommand,
+}
+
+impl ModuleSettingsViewModel {
+    pub fn new(editor: Arc<Editor>) -> Result<Self> {
+        let base = BaseViewModelImpl::new();
+        let ini: Observable<Option<ModuleIni>> = Observable::new(None);
+        let settings = Observable::new(Vec::new());
+        let resources = Observable::new(Vec::new());
+        let mod_resources = Observable::new(Vec::new());
+
+        // 加载命令
+        let editor_clone = Arc::clone(&editor);
+        let ini_clone = ini.clone();
+        let base_clone = base.clone();
+        
+        let load_command = AsyncCommand::new(
+            move || -> Result<()> {
+                base_clone.set_loading(true);
+                base_clone.clear_messages();
+                
+                let result = editor_clone.load_module_ini();
+                base_clone.set_loading(false);
+                let loaded = result?;
+                let status = format!("已加载 {} 项设置", loaded.entries().count());
+                ini_clone.set(Some(loaded));
+                base_clone.set_dirty(false);
+                base_clone.set_status(Some(status));
+                Ok(())
+            },
+            || true
+        );
+
+        // 保存命令（按原有行顺序写回）
+        let editor_clone = Arc::clone(&editor);
+        let ini_clone = ini.clone();
+        let base_clone = base.clone();
+        
+        let save_command = AsyncCommand::new(
+            move || -> Result<()> {
+                let Some(current) = ini_clone.get() else {
+                    return Err(anyhow::anyhow!("没有加载的 module.ini"));
+                };
+                editor_clone.save_module_ini(&current)?;
+                base_clone.set_dirty(false);
+                base_clone.set_status(Some("模块设置保存成功".to_string()));
+                Ok(())
+            },
+            {
+                let ini_clone = ini.clone();
+                move || ini_clone.get().is_some()
+            }
+        );
+
+        // 文件变化时刷新设置列表
+        let settings_clone = settings.clone();
+        let resources_clone = resources.clone();
+        let mod_resources_clone = mod_resources.clone();
+        ini.subscribe(move |ini| {
+            let Some(ini) = ini else {
+                settings_clone.set(Vec::new());
+                resources_clone.set(Vec::new());
+                mod_resources_clone.set(Vec::new());
+                return;
+            };
+            settings_clone.set(collect_settings(ini));
+            resources_clone.set(ini.load_resources());
+            mod_resources_clone.set(ini.load_mod_resources());
+        });
+        
+        Ok(Self {
+            base,
+            editor,
+            ini,
+            settings,
+            resources,
+            mod_resources,
+            load_command,
+            save_command,
+        })
+    }
+
+    // 当前模块名（设置页标题）
+    pub fn get_module_name(&self) -> Option<String> {
+        self.editor.current_module()
+    }
+
+    // 修改设置，值必须符合已知类型
+    pub fn set_value(&self, key: &str, text: &str) -> Result<()> {
+        if key == LOAD_RESOURCE || key == LOAD_MOD_RESOURCE {
+            return Err(anyhow::anyhow!("{} 是资源列表，请使用 set_resources", key));
+        }
+        self.edit(|ini| ini.set_text(key, text))
+    }
+
+    // 修改资源列表（load_resource 或 load_mod_resource）
+    pub fn set_resources(&self, key: &str, values: Vec<String>) -> Result<()> {
+        if key != LOAD_RESOURCE && key != LOAD_MOD_RESOURCE {
+            return Err(anyhow::anyhow!("{} 不是资源列表", key));
+        }
+        self.edit(|ini| {
+            ini.set_list(key, &values);
+            Ok(())
+        })
+    }
+
+    // 删除设置
+    pub fn remove_setting(&self, key: &str) -> Result<()> {
+        self.edit(|ini| {
+            ini.remove(key);
+            Ok(())
+        })
+    }
+
+    // 尚未出现在文件中的已知键（用于添加设置）
+    pub fn get_missing_keys(&self) -> Vec<&'static str> {
+        self.ini.with_value(|ini| {
+            MODULE_INI_KEYS.iter()
+                .map(|(key, _)| *key)
+                .filter(|key| ini.as_ref().is_some_and(|ini| ini.get(key).is_none()))
+                .collect()
+        })
+    }
+
+    fn edit<F>(&self, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut ModuleIni) -> Result<()>,
+    {
+        let Some(mut ini) = self.ini.get() else {
+            return Err(anyhow::anyhow!("没有加载的 module.ini"));
+        };
+        f(&mut ini)?;
+        self.ini.set(Some(ini));
+        self.base.set_dirty(true);
+        Ok(())
+    }
+}
+
+// 按文件顺序列出设置，重复的键只保留最后一次出现的值
+fn collect_settings(ini: &ModuleIni) -> Vec<ModuleSetting> {
+    let mut settings: Vec<ModuleSetting> = Vec::new();
+    for (key, value) in ini.entries() {
+        if key == LOAD_RESOURCE || key == LOAD_MOD_RESOURCE {
+            continue;
+        }
+        match settings.iter_mut().find(|setting| setting.key == key) {
+            Some(setting) => setting.value = value.to_string(),
+            None => settings.push(ModuleSetting {
+                key: key.to_string(),
+                value: value.to_string(),
+                kind: module_ini_key_kind(key),
+                known: MODULE_INI_KEYS.iter().any(|(known, _)| *known == key),
+            }),
+        }
+    }
+    settings
+}
+
+impl BaseViewModel for ModuleSettingsViewModel {
+    fn initialize(&self) -> Result<()> {
+        self.base.initialize()?;
+        self.load_command.execute()
+    }
+
+    fn cleanup(&self) -> Result<()> {
+        self.base.cleanup()
+    }
+
+    fn has_unsaved_changes(&self) -> bool {
+        self.base.has_unsaved_changes()
+    }
+}
+
+impl EditableViewModel for ModuleSettingsViewModel {
+    fn save(&self) -> Result<()> {
+        self.save_command.execute()
+    }
+
+    // 放弃修改，重新读取文件
+    fn cancel(&self) -> Result<()> {
+        self.load_command.execute()
+    }
+
+    fn reset(&self) -> Result<()> {
+        self.cancel()
+    }
+}
+
+impl LoadableViewModel for ModuleSettingsViewModel {
+    fn load(&self) -> Result<()> {
+        self.load_command.execute()
+    }
+
+    fn is_loading(&self) -> bool {
+        self.load_command.is_executing() || self.save_command.is_executing()
+    }
+}
@@ -4,8 +4,7 @@ use std::sync::{Arc, RwLock};
 use std::collections::HashMap;
 use anyhow::Result;
 use tokio::sync::broadcast;
-use std::time::{Duration, Instant};
-use tokio::time::sleep;
+use std::time::Duration;
 
 pub type ObserverId = usize;
 pub type ObserverCallback<T> = Box<dyn Fn(&T) + Send + Sync>;
@@ -14,13 +13,12 @@ pub type ObserverCallback<T> = Box<dyn Fn(&T) + Send + Sync>;
 #[derive(Clone)]
 struct BatchUpdate<T> {
     value: T,
-    timestamp: Instant,
 }
 
 // 响应式Observable值
 pub struct Observable<T> {
     value: Arc<RwLock<T>>,
-    observers: Arc<RwLock<HashMap<ObserverId, Box<dyn Fn(&T) + Send + Sync>>>>,
+    observers: Arc<RwLock<HashMap<ObserverId, ObserverCallback<T>>>>,
     next_id: Arc<RwLock<ObserverId>>,
     batch_sender: Option<broadcast::Sender<BatchUpdate<T>>>,
     debounce_duration: Duration,
@@ -93,7 +91,7 @@ where
                         match update {
                             Ok(latest) => {
                                 // 立即处理更新，减少延迟
-                                let observers = observers.read().await;
+                                let observers = observers.read().unwrap();
                                 for callback in observers.values() {
                                     callback(&latest.value);
                                 }
@@ -133,7 +131,6 @@ where
         if let Some(sender) = &self.batch_sender {
             let _ = sender.send(BatchUpdate {
                 value: new_value,
-                timestamp: Instant::now(),
             });
         } else {
             self.notify_observers_sync();
@@ -154,7 +151,6 @@ where
         if let Some(sender) = &self.batch_sender {
             let _ = sender.send(BatchUpdate {
                 value: new_value,
-                timestamp: Instant::now(),
             });
         } else {
             self.notify_observers_sync();
@@ -0,0 +1,205 @@
+// 队伍编辑器ViewModel
+
+use std::sync::Arc;
+use anyhow::Result;
+use crate::editor::{Editor, RecordKind};
+use crate::data::Party;
+use super::{BaseViewModel, BaseViewModelImpl, AsyncCommand, EditableViewModel, LoadableViewModel, SearchableViewModel, SelectableViewModel, RecordList, observable::{Observable, Command}};
+
+// 队伍编辑器ViewModel
+pub struct PartyViewModel {
+    base: BaseViewModelImpl,
+    editor: Arc<Editor>,
+    
+    // 队伍列表、搜索、选择和编辑状态
+    pub parties: RecordList<Party>,
+    
+    // 过滤
+    pub faction_filter: Observable<Option<i32>>,
+    pub party_kind_filter: Observable<Option<String>>,
+    
+    // 命令
+    pub load_parties_command: AsyncCommand,
+    pub save_party_command: Command,
+    pub add_party_command: Command,
+    pub delete_party_command: Command,
+}
+
+impl PartyViewModel {
+    pub fn new(editor: Arc<Editor>) -> Result<Self> {
+        let base = BaseViewModelImpl::new();
+        let faction_filter: Observable<Option<i32>> = Observable::new(None);
+        let party_kind_filter: Observable<Option<String>> = Observable::new(None);
+
+        // 名称或 id 搜索，再按派系和队伍类型过滤
+        let faction_filter_clone = faction_filter.clone();
+        let party_kind_filter_clone = party_kind_filter.clone();
+        let parties = RecordList::new(&base, "队伍", move |party: &Party| {
+            let faction_filter = faction_filter_clone.get();
+            let kind_filter = party_kind_filter_clone.get();
+            let faction_match = faction_filter.is_none() || faction_filter == Some(party.faction);
+            let kind_match = kind_filter.is_none() || kind_filter.as_deref() == Some(party.party_kind());
+            faction_match && kind_match
+        });
+        parties.search_on_change(&faction_filter);
+        parties.search_on_change(&party_kind_filter);
+        
+        let editor_clone = Arc::clone(&editor);
+        let load_parties_command = parties.load_command(move || editor_clone.get_parties());
+        
+        let editor_clone = Arc::clone(&editor);
+        let save_party_command = parties.save_command(move |index, party| {
+            editor_clone.put_party(index, party)?;
+            Ok(editor_clone.get_parties())
+        });
+        
+        let add_party_command = parties.edit.add_command(Party::default);
+
+        // 被其他队伍按编号引用的队伍不能删除
+        let editor_clone = Arc::clone(&editor);
+        let delete_party_command = parties.delete_command(move |index| {
+            editor_clone.remove_record(RecordKind::Party, index)?;
+            Ok(editor_clone.get_parties())
+        });
+        
+        Ok(Self {
+            base,
+            editor,
+            parties,
+            faction_filter,
+            party_kind_filter,
+            load_parties_command,
+            save_party_command,
+            add_party_command,
+            delete_party_command,
+        })
+    }
+
+    // 开始编辑队伍
+    pub fn start_edit(&self, party: &Party) -> Result<()> {
+        self.parties.start_edit(party)
+    }
+
+    // 更新编辑中的队伍
+    pub fn update_edit_party<F>(&self, updater: F) -> Result<()>
+    where
+        F: FnOnce(&mut Party),
+    {
+        self.parties.edit.update(updater)
+    }
+
+    // 队伍的地图图标名称，找不到时显示编号
+    pub fn get_icon_name(&self, party: &Party) -> String {
+        self.editor.with_map_icons(|icons| {
+            party.icon_name(icons)
+                .map(str::to_string)
+                .unwrap_or_else(|| party.icon().to_string())
+        })
+    }
+
+    // 地图图标名称列表（按编号排列，用于选择图标）
+    pub fn get_icon_names(&self) -> Vec<String> {
+        self.editor.with_map_icons(|icons| icons.iter().map(|icon| icon.id.clone()).collect())
+    }
+
+    // 获取派系编号列表（用于过滤）
+    pub fn get_factions(&self) -> Vec<i32> {
+        let parties = self.parties.records.get();
+        let mut factions: Vec<i32> = parties.iter()
+            .map(|party| party.faction)
+            .collect::<std::collections::HashSet<_>>()
+            .into_iter()
+            .collect();
+        factions.sort();
+        factions
+    }
+
+    // 获取队伍类型列表（用于过滤）
+    pub fn get_party_kinds(&self) -> Vec<String> {
+        let parties = self.parties.records.get();
+        let mut kinds: Vec<String> = parties.iter()
+            .map(|party| party.party_kind().to_string())
+            .collect::<std::collections::HashSet<_>>()
+            .into_iter()
+            .collect();
+        kinds.sort();
+        kinds
+    }
+}
+
+impl BaseViewModel for PartyViewModel {
+    fn initialize(&self) -> Result<()> {
+        self.base.initialize()?;
+        self.load_parties_command.execute()
+    }
+
+    fn cleanup(&self) -> Result<()> {
+        self.base.cleanup()
+    }
+
+    fn has_unsaved_changes(&self) -> bool {
+        self.base.has_unsaved_changes()
+    }
+}
+
+impl EditableViewModel for PartyViewModel {
+    fn save(&self) -> Result<()> {
+        self.save_party_command.execute()
+    }
+
+    fn cancel(&self) -> Result<()> {
+        self.parties.edit.finish();
+        Ok(())
+    }
+
+    fn reset(&self) -> Result<()> {
+        self.parties.reset();
+        self.faction_filter.set(None);
+        self.party_kind_filter.set(None);
+        Ok(())
+    }
+}
+
+impl LoadableViewModel for PartyViewModel {
+    fn load(&self) -> Result<()> {
+        self.load_parties_command.execute()
+    }
+
+    fn is_loading(&self) -> bool {
+        self.load_parties_command.is_executing() || self.save_party_command.is_executing()
+    }
+}
+
+impl SearchableViewModel for PartyViewModel {
+    fn search(&self, query: &str) -> Result<()> {
+        self.parties.search_query.set(query.to_string());
+        Ok(())
+    }
+
+    fn clear_search(&self) -> Result<()> {
+        self.parties.search_query.set(String::new());
+        self.faction_filter.set(None);
+        self.party_kind_filter.set(None);
+        Ok(())
+    }
+
+    fn get_search_query(&self) -> String {
+        self.parties.search_query.get()
+    }
+}
+
+impl SelectableViewModel<Party> for PartyViewModel {
+    fn select_item(&self, item: &Party) -> Result<()> {
+        self.parties.selected.set(Some(item.clone()));
+        Ok(())
+    }
+
+    fn clear_selection(&self) -> Result<()> {
+        self.parties.selected.set(None);
+        Ok(())
+    }
+
+    fn get_selected_item(&self) -> Option<Party> {
+        self.parties.selected.get()
+    }
+}
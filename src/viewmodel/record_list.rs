@@ -0,0 +1,336 @@
+// 列表类编辑器共用的状态和命令（记录列表、搜索过滤、选择与编辑）
+
+use anyhow::Result;
+use crate::data::{Faction, Item, Party, Scene, Troop};
+use super::{AsyncCommand, BaseViewModelImpl, observable::{Observable, Command}};
+
+// 可在列表中编辑的记录
+pub trait ListRecord: Clone + Send + Sync + 'static {
+    // 保存时按 id 定位记录
+    fn record_id(&self) -> &str;
+    
+    // 是否匹配搜索词（已转为小写，空串匹配全部）
+    fn matches_query(&self, query: &str) -> bool;
+}
+
+// 按名称或 id 搜索的记录
+macro_rules! named_records {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl ListRecord for $ty {
+                fn record_id(&self) -> &str {
+                    &self.id
+                }
+
+                fn matches_query(&self, query: &str) -> bool {
+                    query.is_empty() ||
+                        self.name.to_lowercase().contains(query) ||
+                        self.id.to_lowercase().contains(query)
+                }
+            }
+        )*
+    };
+}
+
+named_records!(Item, Troop, Faction, Party, Scene);
+
+// 编辑状态：正在编辑的记录副本和它在数据表中的原编号，保存或取消后清空
+#[derive(Clone)]
+pub struct EditSession<T> {
+    base: BaseViewModelImpl,
+    pub is_editing: Observable<bool>,
+    pub record: Observable<Option<T>>,
+    pub index: Observable<Option<usize>>, // 新建的记录为 None
+}
+
+impl<T> EditSession<T>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    pub fn new(base: &BaseViewModelImpl) -> Self {
+        Self {
+            base: base.clone(),
+            is_editing: Observable::new(false),
+            record: Observable::new(None),
+            index: Observable::new(None),
+        }
+    }
+
+    // 开始编辑新记录
+    pub fn start(&self, record: T) -> Result<()> {
+        self.start_at(None, record)
+    }
+
+    // 开始编辑编号为 index 的记录副本，保存时按原编号写回（修改 id 不会变成新记录）
+    pub fn start_at(&self, index: Option<usize>, record: T) -> Result<()> {
+        if self.is_editing.get() {
+            return Err(anyhow::anyhow!("已经在编辑模式中"));
+        }
+        
+        self.index.set(index);
+        self.record.set(Some(record));
+        self.is_editing.set(true);
+        self.base.set_dirty(false);
+        Ok(())
+    }
+
+    // 修改编辑中的记录
+    pub fn update<F>(&self, updater: F) -> Result<()>
+    where
+        F: FnOnce(&mut T),
+    {
+        if !self.is_editing.get() {
+            return Err(anyhow::anyhow!("不在编辑模式中"));
+        }
+        
+        self.record.update(|record_opt| {
+            if let Some(record) = record_opt.as_mut() {
+                updater(record);
+            }
+        });
+        
+        self.base.set_dirty(true);
+        Ok(())
+    }
+
+    // 结束编辑（保存成功或取消）
+    pub fn finish(&self) {
+        self.is_editing.set(false);
+        self.record.set(None);
+        self.index.set(None);
+        self.base.set_dirty(false);
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.is_editing.get()
+    }
+
+    // 新建记录命令，编辑中不可用
+    pub fn add_command<F>(&self, new_record: F) -> Command
+    where
+        F: Fn() -> T + Send + Sync + 'static,
+    {
+        let session = self.clone();
+        let is_editing = self.is_editing.clone();
+        Command::new(
+            move || session.start(new_record()),
+            move || !is_editing.get()
+        )
+    }
+
+    // 保存命令的可执行条件：有正在编辑的记录
+    pub fn can_save(&self) -> impl Fn() -> bool + Send + Sync + 'static {
+        let session = self.clone();
+        move || session.is_editing.get() && session.record.with_value(Option::is_some)
+    }
+}
+
+// 记录列表：全部记录、搜索结果、选中记录和编辑状态
+#[derive(Clone)]
+pub struct RecordList<T> {
+    base: BaseViewModelImpl,
+    label: &'static str, // 记录类型名称，用于状态和错误消息
+    pub records: Observable<Vec<T>>,
+    pub filtered: Observable<Vec<T>>,
+    pub selected: Observable<Option<T>>,
+    pub search_query: Observable<String>,
+    pub edit: EditSession<T>,
+    pub search_command: Command,
+}
+
+impl<T: ListRecord> RecordList<T> {
+    // filter 为搜索词之外的过滤条件
+    pub fn new<F>(base: &BaseViewModelImpl, label: &'static str, filter: F) -> Self
+    where
+        F: Fn(&T) -> bool + Send + Sync + 'static,
+    {
+        let records: Observable<Vec<T>> = Observable::new(Vec::new());
+        let filtered = Observable::new(Vec::new());
+        let search_query = Observable::new(String::new());
+
+        // 搜索命令
+        let records_clone = records.clone();
+        let filtered_clone = filtered.clone();
+        let search_query_clone = search_query.clone();
+        let search_command = Command::new(
+            move || -> Result<()> {
+                let query = search_query_clone.get().to_lowercase();
+                let matched: Vec<T> = records_clone.get().into_iter()
+                    .filter(|record| record.matches_query(&query) && filter(record))
+                    .collect();
+                filtered_clone.set(matched);
+                Ok(())
+            },
+            || true
+        );
+        
+        let list = Self {
+            base: base.clone(),
+            label,
+            records,
+            filtered,
+            selected: Observable::new(None),
+            search_query,
+            edit: EditSession::new(base),
+            search_command,
+        };
+        list.search_on_change(&list.search_query);
+        list
+    }
+
+    // 搜索词或过滤条件变化时自动重新搜索
+    pub fn search_on_change<V>(&self, observable: &Observable<V>)
+    where
+        V: Clone + Send + Sync + 'static,
+    {
+        let search_command = self.search_command.clone();
+        observable.subscribe(move |_| {
+            let _ = search_command.execute();
+        });
+    }
+
+    // 加载命令：从编辑器读取全部记录
+    pub fn load_command<F>(&self, load: F) -> AsyncCommand
+    where
+        F: Fn() -> Vec<T> + Send + Sync + 'static,
+    {
+        let list = self.clone();
+        AsyncCommand::new(
+            move || -> Result<()> {
+                list.base.set_loading(true);
+                list.base.clear_messages();
+                
+                let records = load();
+                let count = records.len();
+                list.records.set(records);
+                
+                list.base.set_loading(false);
+                list.base.set_status(Some(format!("已加载 {} 个{}", count, list.label)));
+                list.search_command.execute()
+            },
+            || true
+        )
+    }
+
+    // 开始编辑列表中的记录，记下它的编号
+    pub fn start_edit(&self, record: &T) -> Result<()> {
+        let index = self.records.with_value(|records| {
+            records.iter().position(|r| r.record_id() == record.record_id())
+        });
+        self.edit.start_at(index, record.clone())
+    }
+
+    // 保存命令：put 按原编号把编辑中的记录写回编辑器（新建时追加），返回写入后的列表
+    // 写入失败时列表保持不变
+    pub fn save_command<F>(&self, put: F) -> AsyncCommand
+    where
+        F: Fn(Option<usize>, T) -> Result<Vec<T>> + Send + Sync + 'static,
+    {
+        let list = self.clone();
+        AsyncCommand::new(
+            move || -> Result<()> {
+                let Some(record) = list.edit.record.get() else {
+                    return Err(anyhow::anyhow!("没有要保存的{}", list.label));
+                };
+                list.base.set_loading(true);
+                let result = put(list.edit.index.get(), record.clone());
+                list.base.set_loading(false);
+                
+                list.records.set(result?);
+                list.selected.set(Some(record));
+                list.edit.finish();
+                list.base.set_status(Some(format!("{}保存成功", list.label)));
+                list.search_command.execute()
+            },
+            self.edit.can_save()
+        )
+    }
+
+    // 删除命令：remove 按编号删除编辑器中的记录，返回删除后的列表
+    pub fn delete_command<F>(&self, remove: F) -> Command
+    where
+        F: Fn(usize) -> Result<Vec<T>> + Send + Sync + 'static,
+    {
+        let list = self.clone();
+        let selected = self.selected.clone();
+        Command::new(
+            move || -> Result<()> {
+                let Some(record) = list.selected.get() else {
+                    return Err(anyhow::anyhow!("没有选中的{}", list.label));
+                };
+                let index = list.records
+                    .with_value(|records| records.iter().position(|r| r.record_id() == record.record_id()))
+                    .ok_or_else(|| anyhow::anyhow!("未找到选中的{}", list.label))?;
+                
+                list.records.set(remove(index)?);
+                list.selected.set(None);
+                list.search_command.execute()
+            },
+            move || selected.with_value(Option::is_some)
+        )
+    }
+
+    // 回到初始状态：取消编辑，清除选择和搜索词
+    pub fn reset(&self) {
+        self.edit.finish();
+        self.selected.set(None);
+        self.search_query.set(String::new());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    fn troop(id: &str, name: &str, faction: i32) -> Troop {
+        Troop { id: id.to_string(), name: name.to_string(), faction, ..Troop::default() }
+    }
+
+    #[test]
+    fn test_record_list_search_and_save() {
+        let base = BaseViewModelImpl::new();
+        let faction_filter: Observable<Option<i32>> = Observable::new(None);
+        let faction_filter_clone = faction_filter.clone();
+        let list = RecordList::new(&base, "兵种", move |troop: &Troop| {
+            faction_filter_clone.get().is_none_or(|faction| faction == troop.faction)
+        });
+        list.search_on_change(&faction_filter);
+        
+        let load = list.load_command(|| vec![troop("trp_a", "Swadian", 1), troop("trp_b", "Vaegir", 2)]);
+        load.execute().unwrap();
+        assert_eq!(list.filtered.get().len(), 2);
+        
+        list.search_query.set("swad".to_string());
+        assert_eq!(list.filtered.get()[0].id, "trp_a");
+        faction_filter.set(Some(2));
+        assert!(list.filtered.get().is_empty());
+
+        // 保存按原编号写回，修改 id 也不会追加新记录
+        let table = Arc::new(Mutex::new(list.records.get()));
+        let table_clone = Arc::clone(&table);
+        let save = list.save_command(move |index, record| {
+            let mut table = table_clone.lock().unwrap();
+            match index {
+                Some(index) => table[index] = record,
+                None => table.push(record),
+            }
+            Ok(table.clone())
+        });
+        let vaegir = list.records.get()[1].clone();
+        list.start_edit(&vaegir).unwrap();
+        list.edit.update(|troop| troop.id = "trp_vaegir_guard".to_string()).unwrap();
+        save.execute().unwrap();
+        assert!(!list.edit.is_active());
+        let ids: Vec<String> = list.records.get().into_iter().map(|troop| troop.id).collect();
+        assert_eq!(ids, vec!["trp_a", "trp_vaegir_guard"]);
+
+        // 写入失败时本地列表不变
+        let failing = list.save_command(|_, _| Err(anyhow::anyhow!("id 重复")));
+        list.start_edit(&list.records.get()[0]).unwrap();
+        list.edit.update(|troop| troop.id = "trp_vaegir_guard".to_string()).unwrap();
+        assert!(failing.execute().is_err());
+        assert!(list.edit.is_active());
+        assert_eq!(list.records.get()[0].id, "trp_a");
+    }
+}
@@ -0,0 +1,173 @@
+// 场景编辑器ViewModel
+
+use std::sync::Arc;
+use anyhow::Result;
+use crate::editor::{Editor, RecordKind};
+use crate::data::{Scene, SCENE_PASSAGE_EXIT};
+use super::{BaseViewModel, BaseViewModelImpl, AsyncCommand, EditableViewModel, LoadableViewModel, SearchableViewModel, SelectableViewModel, RecordList, observable::{Observable, Command}};
+
+// 场景编辑器ViewModel
+pub struct SceneViewModel {
+    base: BaseViewModelImpl,
+    
+    // 场景列表、搜索、选择和编辑状态
+    pub scenes: RecordList<Scene>,
+    
+    // 过滤
+    pub indoors_filter: Observable<Option<bool>>,
+    
+    // 命令
+    pub load_scenes_command: AsyncCommand,
+    pub save_scene_command: Command,
+    pub add_scene_command: Command,
+    pub delete_scene_command: Command,
+}
+
+impl SceneViewModel {
+    pub fn new(editor: Arc<Editor>) -> Result<Self> {
+        let base = BaseViewModelImpl::new();
+        let indoors_filter: Observable<Option<bool>> = Observable::new(None);
+
+        // 名称或 id 搜索，再按室内/室外过滤
+        let indoors_filter_clone = indoors_filter.clone();
+        let scenes = RecordList::new(&base, "场景", move |scene: &Scene| {
+            let indoors_filter = indoors_filter_clone.get();
+            indoors_filter.is_none() || indoors_filter == Some(scene.is_indoors())
+        });
+        scenes.search_on_change(&indoors_filter);
+        
+        let editor_clone = Arc::clone(&editor);
+        let load_scenes_command = scenes.load_command(move || editor_clone.get_scenes());
+        
+        let editor_clone = Arc::clone(&editor);
+        let save_scene_command = scenes.save_command(move |index, scene| {
+            editor_clone.put_scene(index, scene)?;
+            Ok(editor_clone.get_scenes())
+        });
+        
+        let add_scene_command = scenes.edit.add_command(Scene::default);
+
+        // 被通道或兵种按编号引用的场景不能删除
+        let editor_clone = Arc::clone(&editor);
+        let delete_scene_command = scenes.delete_command(move |index| {
+            editor_clone.remove_record(RecordKind::Scene, index)?;
+            Ok(editor_clone.get_scenes())
+        });
+        
+        Ok(Self {
+            base,
+            scenes,
+            indoors_filter,
+            load_scenes_command,
+            save_scene_command,
+            add_scene_command,
+            delete_scene_command,
+        })
+    }
+
+    // 开始编辑场景
+    pub fn start_edit(&self, scene: &Scene) -> Result<()> {
+        self.scenes.start_edit(scene)
+    }
+
+    // 更新编辑中的场景
+    pub fn update_edit_scene<F>(&self, updater: F) -> Result<()>
+    where
+        F: FnOnce(&mut Scene),
+    {
+        self.scenes.edit.update(updater)
+    }
+
+    // 当前编辑场景的通道（场景编号, 场景 id），出口和空通道的 id 为空
+    pub fn get_passage_targets(&self) -> Vec<(i32, String)> {
+        let scenes = self.scenes.records.get();
+        let passages = self.scenes.edit.record.with_value(|scene| {
+            scene.as_ref().map(|scene| scene.passages.clone()).unwrap_or_default()
+        });
+        passages.into_iter()
+            .map(|passage| {
+                let id = usize::try_from(passage).ok()
+                    .filter(|_| passage > 0 && passage != SCENE_PASSAGE_EXIT)
+                    .and_then(|index| scenes.get(index))
+                    .map(|scene| scene.id.clone())
+                    .unwrap_or_default();
+                (passage, id)
+            })
+            .collect()
+    }
+}
+
+impl BaseViewModel for SceneViewModel {
+    fn initialize(&self) -> Result<()> {
+        self.base.initialize()?;
+        self.load_scenes_command.execute()
+    }
+
+    fn cleanup(&self) -> Result<()> {
+        self.base.cleanup()
+    }
+
+    fn has_unsaved_changes(&self) -> bool {
+        self.base.has_unsaved_changes()
+    }
+}
+
+impl EditableViewModel for SceneViewModel {
+    fn save(&self) -> Result<()> {
+        self.save_scene_command.execute()
+    }
+
+    fn cancel(&self) -> Result<()> {
+        self.scenes.edit.finish();
+        Ok(())
+    }
+
+    fn reset(&self) -> Result<()> {
+        self.scenes.reset();
+        self.indoors_filter.set(None);
+        Ok(())
+    }
+}
+
+impl LoadableViewModel for SceneViewModel {
+    fn load(&self) -> Result<()> {
+        self.load_scenes_command.execute()
+    }
+
+    fn is_loading(&self) -> bool {
+        self.load_scenes_command.is_executing() || self.save_scene_command.is_executing()
+    }
+}
+
+impl SearchableViewModel for SceneViewModel {
+    fn search(&self, query: &str) -> Result<()> {
+        self.scenes.search_query.set(query.to_string());
+        Ok(())
+    }
+
+    fn clear_search(&self) -> Result<()> {
+        self.scenes.search_query.set(String::new());
+        self.indoors_filter.set(None);
+        Ok(())
+    }
+
+    fn get_search_query(&self) -> String {
+        self.scenes.search_query.get()
+    }
+}
+
+impl SelectableViewModel<Scene> for SceneViewModel {
+    fn select_item(&self, item: &Scene) -> Result<()> {
+        self.scenes.selected.set(Some(item.clone()));
+        Ok(())
+    }
+
+    fn clear_selection(&self) -> Result<()> {
+        self.scenes.selected.set(None);
+        Ok(())
+    }
+
+    fn get_selected_item(&self) -> Option<Scene> {
+        self.scenes.selected.get()
+    }
+}
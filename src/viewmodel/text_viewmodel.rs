@@ -0,0 +1,337 @@
+// 文本编辑器ViewModel（strings.txt、quests.txt 与 info_pages.txt）
+
+use std::sync::Arc;
+use anyhow::Result;
+use crate::editor::Editor;
+use crate::data::{GameString, Quest, InfoPage};
+use super::{BaseViewModel, BaseViewModelImpl, AsyncCommand, EditableViewModel, LoadableViewModel, SearchableViewModel, SelectableViewModel, ListRecord, RecordList, observable::{Observable, Command}};
+
+// 文本所在的文件
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TextKind {
+    String,
+    Quest,
+    InfoPage,
+}
+
+// 统一的文本条目（按文件中的序号定位）
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextEntry {
+    pub kind: TextKind,
+    pub index: usize,
+    pub id: String,
+    pub name: String, // 字符串没有名称，为空
+    pub text: String,
+}
+
+impl TextEntry {
+    // id 和字符串的名称不在文本编辑器中修改，被改动时拒绝保存而不是忽略
+    fn check_read_only(&self, original: &TextEntry) -> Result<()> {
+        if self.id != original.id {
+            return Err(anyhow::anyhow!("文本 id 不能修改（{} → {}）", original.id, self.id));
+        }
+        if self.kind == TextKind::String && self.name != original.name {
+            return Err(anyhow::anyhow!("字符串没有名称，只能修改文本"));
+        }
+        Ok(())
+    }
+}
+
+impl ListRecord for TextEntry {
+    fn record_id(&self) -> &str {
+        &self.id
+    }
+
+    fn matches_query(&self, query: &str) -> bool {
+        query.is_empty() ||
+            self.id.to_lowercase().contains(query) ||
+            self.name.to_lowercase().contains(query) ||
+            self.text.to_lowercase().contains(query)
+    }
+}
+
+// 文本编辑器ViewModel
+pub struct TextViewModel {
+    base: BaseViewModelImpl,
+    editor: Arc<Editor>,
+    
+    // 数据
+    pub strings: Observable<Vec<GameString>>,
+    pub quests: Observable<Vec<Quest>>,
+    pub info_pages: Observable<Vec<InfoPage>>,
+    
+    // 文本条目列表、搜索、选择和编辑状态
+    pub entries: RecordList<TextEntry>,
+    
+    // 过滤
+    pub kind_filter: Observable<Option<TextKind>>,
+    
+    // 命令
+    pub load_texts_command: AsyncCommand,
+    pub save_entry_command: Command,
+}
+
+impl TextViewModel {
+    pub fn new(editor: Arc<Editor>) -> Result<Self> {
+        let base = BaseViewModelImpl::new();
+        let strings = Observable::new(Vec::new());
+        let quests = Observable::new(Vec::new());
+        let info_pages = Observable::new(Vec::new());
+        let kind_filter: Observable<Option<TextKind>> = Observable::new(None);
+
+        // 按 id、名称或文本搜索，再按文件过滤
+        let kind_filter_clone = kind_filter.clone();
+        let entries = RecordList::new(&base, "文本", move |entry: &TextEntry| {
+            let kind_filter = kind_filter_clone.get();
+            kind_filter.is_none() || kind_filter == Some(entry.kind)
+        });
+        entries.search_on_change(&kind_filter);
+
+        // 加载文本命令
+        let editor_clone = Arc::clone(&editor);
+        let strings_clone = strings.clone();
+        let quests_clone = quests.clone();
+        let info_pages_clone = info_pages.clone();
+        let entries_clone = entries.clone();
+        let base_clone = base.clone();
+        
+        let load_texts_command = AsyncCommand::new(
+            move || -> Result<()> {
+                base_clone.set_loading(true);
+                base_clone.clear_messages();
+                
+                let loaded_strings = editor_clone.get_strings();
+                let loaded_quests = editor_clone.get_quests();
+                let loaded_pages = editor_clone.get_info_pages();
+                let status = format!(
+                    "已加载 {} 个字符串, {} 个任务, {} 个百科页面",
+                    loaded_strings.len(),
+                    loaded_quests.len(),
+                    loaded_pages.len()
+                );
+                entries_clone.records.set(collect_entries(&loaded_strings, &loaded_quests, &loaded_pages));
+                strings_clone.set(loaded_strings);
+                quests_clone.set(loaded_quests);
+                info_pages_clone.set(loaded_pages);
+                
+                base_clone.set_loading(false);
+                base_clone.set_status(Some(status));
+                entries_clone.search_command.execute()
+            },
+            || true
+        );
+
+        // 保存文本命令（写回对应文件的列表）
+        let editor_clone = Arc::clone(&editor);
+        let strings_clone = strings.clone();
+        let quests_clone = quests.clone();
+        let info_pages_clone = info_pages.clone();
+        let entries_clone = entries.clone();
+        let base_clone = base.clone();
+        
+        let save_entry_command = AsyncCommand::new(
+            move || -> Result<()> {
+                let Some(entry) = entries_clone.edit.record.get() else {
+                    return Err(anyhow::anyhow!("没有要保存的文本"));
+                };
+                
+                let original = entries_clone.records
+                    .with_value(|entries| entries.iter().find(|e| e.kind == entry.kind && e.index == entry.index).cloned())
+                    .ok_or_else(|| anyhow::anyhow!("文本 #{} 不存在", entry.index))?;
+                entry.check_read_only(&original)?;
+                
+                // 只修改编辑器中的这一条记录，再重新读取对应的列表
+                let missing = || anyhow::anyhow!("文本 #{} 不存在", entry.index);
+                match entry.kind {
+                    TextKind::String => {
+                        let mut string = editor_clone.with_strings(|list| list.get(entry.index).cloned()).ok_or_else(missing)?;
+                        string.text = entry.text.clone();
+                        editor_clone.put_string(Some(entry.index), string)?;
+                        strings_clone.set(editor_clone.get_strings());
+                    }
+                    TextKind::Quest => {
+                        let mut quest = editor_clone.with_quests(|list| list.get(entry.index).cloned()).ok_or_else(missing)?;
+                        quest.name = entry.name.clone();
+                        quest.description = entry.text.clone();
+                        editor_clone.put_quest(Some(entry.index), quest)?;
+                        quests_clone.set(editor_clone.get_quests());
+                    }
+                    TextKind::InfoPage => {
+                        let mut page = editor_clone.with_info_pages(|list| list.get(entry.index).cloned()).ok_or_else(missing)?;
+                        page.name = entry.name.clone();
+                        page.text = entry.text.clone();
+                        editor_clone.put_info_page(Some(entry.index), page)?;
+                        info_pages_clone.set(editor_clone.get_info_pages());
+                    }
+                }
+                entries_clone.records.set(collect_entries(&strings_clone.get(), &quests_clone.get(), &info_pages_clone.get()));
+                entries_clone.selected.set(Some(entry));
+                entries_clone.edit.finish();
+                base_clone.set_status(Some("文本保存成功".to_string()));
+                entries_clone.search_command.execute()
+            },
+            entries.edit.can_save()
+        );
+        
+        Ok(Self {
+            base,
+            editor,
+            strings,
+            quests,
+            info_pages,
+            entries,
+            kind_filter,
+            load_texts_command,
+            save_entry_command,
+        })
+    }
+
+    // 开始编辑文本
+    pub fn start_edit(&self, entry: &TextEntry) -> Result<()> {
+        self.entries.edit.start(entry.clone())
+    }
+
+    // 更新编辑中的文本（任务和百科页面可改名称和文本，字符串只能改文本）
+    pub fn update_edit_entry<F>(&self, updater: F) -> Result<()>
+    where
+        F: FnOnce(&mut TextEntry),
+    {
+        self.entries.edit.update(updater)
+    }
+
+    // 按 id 查找字符串文本（用于显示脚本中引用的字符串）
+    pub fn get_string_text(&self, id: &str) -> Option<String> {
+        self.editor.with_strings(|strings| {
+            strings.iter().find(|string| string.id == id).map(|string| string.text.clone())
+        })
+    }
+}
+
+// 把三个文件的记录合并为文本条目列表
+fn collect_entries(strings: &[GameString], quests: &[Quest], pages: &[InfoPage]) -> Vec<TextEntry> {
+    let strings = strings.iter().enumerate().map(|(index, string)| TextEntry {
+        kind: TextKind::String,
+        index,
+        id: string.id.clone(),
+        name: String::new(),
+        text: string.text.clone(),
+    });
+    let quests = quests.iter().enumerate().map(|(index, quest)| TextEntry {
+        kind: TextKind::Quest,
+        index,
+        id: quest.id.clone(),
+        name: quest.name.clone(),
+        text: quest.description.clone(),
+    });
+    let pages = pages.iter().enumerate().map(|(index, page)| TextEntry {
+        kind: TextKind::InfoPage,
+        index,
+        id: page.id.clone(),
+        name: page.name.clone(),
+        text: page.text.clone(),
+    });
+    strings.chain(quests).chain(pages).collect()
+}
+
+impl BaseViewModel for TextViewModel {
+    fn initialize(&self) -> Result<()> {
+        self.base.initialize()?;
+        self.load_texts_command.execute()
+    }
+
+    fn cleanup(&self) -> Result<()> {
+        self.base.cleanup()
+    }
+
+    fn has_unsaved_changes(&self) -> bool {
+        self.base.has_unsaved_changes()
+    }
+}
+
+impl EditableViewModel for TextViewModel {
+    fn save(&self) -> Result<()> {
+        self.save_entry_command.execute()
+    }
+
+    fn cancel(&self) -> Result<()> {
+        self.entries.edit.finish();
+        Ok(())
+    }
+
+    fn reset(&self) -> Result<()> {
+        self.entries.reset();
+        self.kind_filter.set(None);
+        Ok(())
+    }
+}
+
+impl LoadableViewModel for TextViewModel {
+    fn load(&self) -> Result<()> {
+        self.load_texts_command.execute()
+    }
+
+    fn is_loading(&self) -> bool {
+        self.load_texts_command.is_executing() || self.save_entry_command.is_executing()
+    }
+}
+
+impl SearchableViewModel for TextViewModel {
+    fn search(&self, query: &str) -> Result<()> {
+        self.entries.search_query.set(query.to_string());
+        Ok(())
+    }
+
+    fn clear_search(&self) -> Result<()> {
+        self.entries.search_query.set(String::new());
+        self.kind_filter.set(None);
+        Ok(())
+    }
+
+    fn get_search_query(&self) -> String {
+        self.entries.search_query.get()
+    }
+}
+
+impl SelectableViewModel<TextEntry> for TextViewModel {
+    fn select_item(&self, item: &TextEntry) -> Result<()> {
+        self.entries.selected.set(Some(item.clone()));
+        Ok(())
+    }
+
+    fn clear_selection(&self) -> Result<()> {
+        self.entries.selected.set(None);
+        Ok(())
+    }
+
+    fn get_selected_item(&self) -> Option<TextEntry> {
+        self.entries.selected.get()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_only_fields() {
+        let original = TextEntry {
+            kind: TextKind::String,
+            index: 0,
+            id: "str_hello".to_string(),
+            name: String::new(),
+            text: "Hello".to_string(),
+        };
+        let edited = |f: fn(&mut TextEntry)| {
+            let mut entry = original.clone();
+            f(&mut entry);
+            entry.check_read_only(&original)
+        };
+        
+        assert!(edited(|entry| entry.text = "Hi".to_string()).is_ok());
+        assert!(edited(|entry| entry.id = "str_hi".to_string()).is_err());
+        assert!(edited(|entry| entry.name = "Greeting".to_string()).is_err());
+        let quest = TextEntry { kind: TextKind::Quest, ..original.clone() };
+        let renamed = TextEntry { name: "Greeting".to_string(), ..quest.clone() };
+        assert!(renamed.check_read_only(&quest).is_ok());
+    }
+}
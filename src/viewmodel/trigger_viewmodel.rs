@@ -0,0 +1,213 @@
+// 触发器编辑器ViewModel（simple_triggers.txt 与 triggers.txt）
+
+use std::sync::Arc;
+use anyhow::Result;
+use crate::editor::Editor;
+use crate::data::{SimpleTrigger, Trigger};
+use super::{BaseViewModel, BaseViewModelImpl, AsyncCommand, EditableViewModel, LoadableViewModel, EditSession, observable::{Observable, Command}};
+
+// 正在编辑的触发器（按文件中的序号定位，None 表示新增）
+#[derive(Debug, Clone, PartialEq)]
+pub enum TriggerEdit {
+    Simple(Option<usize>, SimpleTrigger),
+    Trigger(Option<usize>, Trigger),
+}
+
+// 触发器编辑器ViewModel
+pub struct TriggerViewModel {
+    base: BaseViewModelImpl,
+    editor: Arc<Editor>,
+    
+    // 数据
+    pub simple_triggers: Observable<Vec<SimpleTrigger>>,
+    pub triggers: Observable<Vec<Trigger>>,
+    
+    // 编辑状态
+    pub edit: EditSession<TriggerEdit>,
+    
+    // 命令
+    pub load_triggers_command: AsyncCommand,
+    pub save_trigger_command: Command,
+    pub add_simple_trigger_command: Command,
+    pub add_trigger_command: Command,
+}
+
+impl TriggerViewModel {
+    pub fn new(editor: Arc<Editor>) -> Result<Self> {
+        let base = BaseViewModelImpl::new();
+        let simple_triggers = Observable::new(Vec::new());
+        let triggers = Observable::new(Vec::new());
+        let edit: EditSession<TriggerEdit> = EditSession::new(&base);
+
+        // 加载触发器命令
+        let editor_clone = Arc::clone(&editor);
+        let simple_triggers_clone = simple_triggers.clone();
+        let triggers_clone = triggers.clone();
+        let base_clone = base.clone();
+        
+        let load_triggers_command = AsyncCommand::new(
+            move || -> Result<()> {
+                base_clone.set_loading(true);
+                base_clone.clear_messages();
+                
+                let loaded_simple_triggers = editor_clone.get_simple_triggers();
+                let loaded_triggers = editor_clone.get_triggers();
+                let status = format!(
+                    "已加载 {} 个简单触发器, {} 个触发器",
+                    loaded_simple_triggers.len(),
+                    loaded_triggers.len()
+                );
+                simple_triggers_clone.set(loaded_simple_triggers);
+                triggers_clone.set(loaded_triggers);
+                
+                base_clone.set_loading(false);
+                base_clone.set_status(Some(status));
+                Ok(())
+            },
+            || true
+        );
+
+        // 保存触发器命令（写回编辑器）
+        let editor_clone = Arc::clone(&editor);
+        let simple_triggers_clone = simple_triggers.clone();
+        let triggers_clone = triggers.clone();
+        let edit_clone = edit.clone();
+        let base_clone = base.clone();
+        
+        let save_trigger_command = AsyncCommand::new(
+            move || -> Result<()> {
+                let Some(edit) = edit_clone.record.get() else {
+                    return Err(anyhow::anyhow!("没有要保存的触发器"));
+                };
+                
+                match edit {
+                    TriggerEdit::Simple(index, trigger) => {
+                        simple_triggers_clone.update(|list| replace_or_push(list, index, trigger));
+                        editor_clone.set_simple_triggers(simple_triggers_clone.get())?;
+                    }
+                    TriggerEdit::Trigger(index, trigger) => {
+                        triggers_clone.update(|list| replace_or_push(list, index, trigger));
+                        editor_clone.set_triggers(triggers_clone.get())?;
+                    }
+                }
+                
+                edit_clone.finish();
+                base_clone.set_status(Some("触发器保存成功".to_string()));
+                Ok(())
+            },
+            edit.can_save()
+        );
+
+        // 添加简单触发器和触发器命令
+        let add_simple_trigger_command = edit.add_command(|| TriggerEdit::Simple(None, SimpleTrigger::default()));
+        let add_trigger_command = edit.add_command(|| TriggerEdit::Trigger(None, Trigger::default()));
+        
+        Ok(Self {
+            base,
+            editor,
+            simple_triggers,
+            triggers,
+            edit,
+            load_triggers_command,
+            save_trigger_command,
+            add_simple_trigger_command,
+            add_trigger_command,
+        })
+    }
+
+    // 开始编辑简单触发器
+    pub fn start_edit_simple_trigger(&self, index: usize) -> Result<()> {
+        let trigger = self.simple_triggers.with_value(|list| list.get(index).cloned())
+            .ok_or_else(|| anyhow::anyhow!("简单触发器 #{} 不存在", index))?;
+        self.start_edit(TriggerEdit::Simple(Some(index), trigger))
+    }
+
+    // 开始编辑触发器
+    pub fn start_edit_trigger(&self, index: usize) -> Result<()> {
+        let trigger = self.triggers.with_value(|list| list.get(index).cloned())
+            .ok_or_else(|| anyhow::anyhow!("触发器 #{} 不存在", index))?;
+        self.start_edit(TriggerEdit::Trigger(Some(index), trigger))
+    }
+
+    fn start_edit(&self, edit: TriggerEdit) -> Result<()> {
+        self.edit.start(edit)
+    }
+
+    // 更新编辑中的触发器
+    pub fn update_edit_trigger<F>(&self, updater: F) -> Result<()>
+    where
+        F: FnOnce(&mut TriggerEdit),
+    {
+        self.edit.update(updater)
+    }
+
+    // 删除简单触发器
+    pub fn delete_simple_trigger(&self, index: usize) -> Result<()> {
+        if index >= self.simple_triggers.with_value(|list| list.len()) {
+            return Err(anyhow::anyhow!("简单触发器 #{} 不存在", index));
+        }
+        self.simple_triggers.update(|list| {
+            list.remove(index);
+        });
+        self.editor.set_simple_triggers(self.simple_triggers.get())
+    }
+
+    // 删除触发器
+    pub fn delete_trigger(&self, index: usize) -> Result<()> {
+        if index >= self.triggers.with_value(|list| list.len()) {
+            return Err(anyhow::anyhow!("触发器 #{} 不存在", index));
+        }
+        self.triggers.update(|list| {
+            list.remove(index);
+        });
+        self.editor.set_triggers(self.triggers.get())
+    }
+}
+
+// 按序号替换，序号为空或越界时追加到末尾
+fn replace_or_push<T>(list: &mut Vec<T>, index: Option<usize>, value: T) {
+    match index.and_then(|index| list.get_mut(index)) {
+        Some(existing) => *existing = value,
+        None => list.push(value),
+    }
+}
+
+impl BaseViewModel for TriggerViewModel {
+    fn initialize(&self) -> Result<()> {
+        self.base.initialize()?;
+        self.load_triggers_command.execute()
+    }
+
+    fn cleanup(&self) -> Result<()> {
+        self.base.cleanup()
+    }
+
+    fn has_unsaved_changes(&self) -> bool {
+        self.base.has_unsaved_changes()
+    }
+}
+
+impl EditableViewModel for TriggerViewModel {
+    fn save(&self) -> Result<()> {
+        self.save_trigger_command.execute()
+    }
+
+    fn cancel(&self) -> Result<()> {
+        self.edit.finish();
+        Ok(())
+    }
+
+    fn reset(&self) -> Result<()> {
+        self.cancel()
+    }
+}
+
+impl LoadableViewModel for TriggerViewModel {
+    fn load(&self) -> Result<()> {
+        self.load_triggers_command.execute()
+    }
+
+    fn is_loading(&self) -> bool {
+        self.load_triggers_command.is_executing() || self.save_trigger_command.is_executing()
+    }
+}